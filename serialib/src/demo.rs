@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    io::{self, Read, Write},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::DataSource;
+
+/// Most samples generated by one read, so a stalled reader doesn't get buried
+const MAX_BURST: u64 = 1_000;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Waveform {
+    #[default]
+    Sine,
+    /// Rises from -1 to 1, then jumps back
+    Ramp,
+    /// Uniform noise between -1 and 1
+    Noise,
+}
+
+impl Display for Waveform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Waveform::Sine => write!(f, "Sine"),
+            Waveform::Ramp => write!(f, "Ramp"),
+            Waveform::Noise => write!(f, "Noise"),
+        }
+    }
+}
+
+/// What the demo source generates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DemoSettings {
+    pub waveform: Waveform,
+    /// Cycles per second of every channel
+    pub frequency: f64,
+    pub channels: usize,
+    /// Lines generated per second
+    pub sample_rate: u32,
+}
+
+impl Default for DemoSettings {
+    fn default() -> Self {
+        Self {
+            waveform: Waveform::default(),
+            frequency: 1.,
+            channels: 3,
+            sample_rate: 100,
+        }
+    }
+}
+
+/// Generates space separated `\r\n` lines as if a device were sending them.
+///
+/// Every channel gets the same waveform, shifted in phase so they can be told apart.
+pub(crate) struct DemoSource {
+    settings: DemoSettings,
+    started: Instant,
+    /// Index of the next sample to generate
+    next_sample: u64,
+    pending: VecDeque<u8>,
+    /// xorshift state for [`Waveform::Noise`]
+    seed: u64,
+}
+
+impl DemoSource {
+    pub(crate) fn new(settings: DemoSettings) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        Self {
+            settings,
+            started: Instant::now(),
+            next_sample: 0,
+            pending: VecDeque::new(),
+            // xorshift never leaves 0
+            seed: seed | 1,
+        }
+    }
+
+    fn noise(&mut self) -> f64 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        (self.seed >> 11) as f64 / (1u64 << 53) as f64 * 2. - 1.
+    }
+
+    fn value(&mut self, t: f64, channel: usize) -> f64 {
+        let phase = channel as f64 / self.settings.channels.max(1) as f64;
+        let cycles = self.settings.frequency * t + phase;
+        match self.settings.waveform {
+            Waveform::Sine => (cycles * std::f64::consts::TAU).sin(),
+            Waveform::Ramp => cycles.fract() * 2. - 1.,
+            Waveform::Noise => self.noise(),
+        }
+    }
+
+    /// Queue a line for every sample that is due by now
+    fn generate(&mut self) {
+        let rate = self.settings.sample_rate.max(1) as f64;
+        let due = (self.started.elapsed().as_secs_f64() * rate) as u64;
+        let last = due.min(self.next_sample + MAX_BURST);
+        while self.next_sample < last {
+            let t = self.next_sample as f64 / rate;
+            let line = (0..self.settings.channels.max(1))
+                .map(|channel| format!("{:.4}", self.value(t, channel)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            self.pending.extend(line.bytes().chain(*b"\r\n"));
+            self.next_sample += 1;
+        }
+        // A reader that fell far behind skips ahead instead of catching up
+        self.next_sample = self.next_sample.max(due);
+    }
+}
+
+impl Read for DemoSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pending.is_empty() {
+            self.generate();
+        }
+        if self.pending.is_empty() {
+            // Wait like a serial port would for the next sample
+            let rate = self.settings.sample_rate.max(1) as f64;
+            std::thread::sleep(Duration::from_secs_f64(1. / rate).min(Duration::from_millis(10)));
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "no sample due yet"));
+        }
+        let count = buf.len().min(self.pending.len());
+        for (slot, byte) in buf.iter_mut().zip(self.pending.drain(..count)) {
+            *slot = byte;
+        }
+        Ok(count)
+    }
+}
+
+impl Write for DemoSource {
+    /// Commands sent to the demo go nowhere
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl DataSource for DemoSource {
+    fn describe(&self) -> String {
+        format!(
+            "Demo: {} channels of {} at {} Hz",
+            self.settings.channels, self.settings.waveform, self.settings.frequency
+        )
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        Err(serialport::Error::new(
+            serialport::ErrorKind::Unknown,
+            "the demo source has no break condition",
+        ))
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    time::Duration,
+};
+
+use crate::DataSource;
+
+/// How long each read from a file source waits for data
+const FILE_READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Chunks read ahead of the serial thread, so replaying a file doesn't race the queue
+const READ_AHEAD: usize = 16;
+
+/// A FIFO, character device or regular file read like a serial port.
+///
+/// A regular file is replayed once from the start, a FIFO or device is read for
+/// as long as its writer keeps it open. Reads happen on a thread of their own,
+/// since a FIFO blocks until there is data and would never notice a disconnect.
+pub(crate) struct FileSource {
+    path: PathBuf,
+    kind: &'static str,
+    chunks: Receiver<io::Result<Vec<u8>>>,
+    /// Rest of a chunk that didn't fit the last read
+    leftover: Vec<u8>,
+    /// Character devices are also written to, FIFOs and files only read
+    writer: Option<File>,
+}
+
+impl FileSource {
+    /// Open `path`, which blocks for a FIFO until something opens it for writing
+    pub(crate) fn open(path: &Path) -> io::Result<Self> {
+        let kind = kind(path);
+        let (file, writer) = if kind == "character device" {
+            let file = File::options().read(true).write(true).open(path)?;
+            let writer = file.try_clone()?;
+            (file, Some(writer))
+        } else {
+            (File::open(path)?, None)
+        };
+        let (tx, chunks) = mpsc::sync_channel(READ_AHEAD);
+        std::thread::spawn(move || pump(file, tx));
+        Ok(Self {
+            path: path.to_path_buf(),
+            kind,
+            chunks,
+            leftover: Vec::new(),
+            writer,
+        })
+    }
+}
+
+/// What sort of file `path` is, for display
+fn kind(path: &Path) -> &'static str {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return "file";
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        let file_type = metadata.file_type();
+        if file_type.is_fifo() {
+            return "FIFO";
+        }
+        if file_type.is_char_device() {
+            return "character device";
+        }
+    }
+    match metadata.is_file() {
+        true => "regular file",
+        false => "file",
+    }
+}
+
+/// Read `file` into `tx` until it ends, fails, or nobody listens anymore
+fn pump(mut file: File, tx: mpsc::SyncSender<io::Result<Vec<u8>>>) {
+    let mut buf = [0; 4096];
+    loop {
+        let chunk = match file.read(&mut buf) {
+            Ok(0) => return,
+            Ok(n) => Ok(buf[..n].to_vec()),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => Err(e),
+        };
+        let failed = chunk.is_err();
+        if tx.send(chunk).is_err() || failed {
+            return;
+        }
+    }
+}
+
+impl Read for FileSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.leftover.is_empty() {
+            self.leftover = match self.chunks.recv_timeout(FILE_READ_TIMEOUT) {
+                Ok(chunk) => chunk?,
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(io::Error::new(io::ErrorKind::TimedOut, "no data yet"))
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!("the {} ended", self.kind),
+                    ))
+                }
+            };
+        }
+        let n = buf.len().min(self.leftover.len());
+        buf[..n].copy_from_slice(&self.leftover[..n]);
+        self.leftover.drain(..n);
+        Ok(n)
+    }
+}
+
+impl Write for FileSource {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.writer {
+            Some(writer) => writer.write(buf),
+            None => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("a {} is only read from", self.kind),
+            )),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.writer {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl DataSource for FileSource {
+    fn describe(&self) -> String {
+        format!("Reading {} {}", self.kind, self.path.display())
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        Err(serialport::Error::new(
+            serialport::ErrorKind::Unknown,
+            "a file has no break condition",
+        ))
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+}
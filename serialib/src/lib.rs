@@ -1,23 +1,69 @@
 use serde::{Deserialize, Serialize};
 use serialport::{DataBits, FlowControl, Parity, SerialPort, StopBits};
 use std::{
+    collections::VecDeque,
     fmt::Display,
-    io::{BufRead, BufReader},
-    sync::{mpsc::Sender, Arc, RwLock},
+    fs::{File, TryLockError},
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{Receiver, SyncSender, TrySendError},
+        Arc, Mutex, RwLock,
+    },
     time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use thiserror::Error;
 
+mod demo;
+mod file;
+pub mod modbus;
+
+pub use demo::{DemoSettings, Waveform};
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Couldn't find Serial Ports because {0}")]
     NoPortsAvailable(serialport::Error),
+    #[error("Couldn't open {path} because it is busy, another program has it open")]
+    Busy { path: String },
+    #[error("Couldn't open {path} because {source}")]
+    Open {
+        path: String,
+        source: serialport::Error,
+    },
+    #[error("Couldn't connect to {address} because {source}")]
+    Connect {
+        address: String,
+        source: std::io::Error,
+    },
+    #[error("Couldn't open {path} because {source}")]
+    File {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("Gave up opening {name} after {timeout:?} without an answer")]
+    OpenTimeout { name: String, timeout: Duration },
+    #[error("Couldn't read from the port because {0}")]
+    Read(std::io::Error),
+    #[error("Couldn't write to the port because {0}")]
+    Write(std::io::Error),
+    #[error("Couldn't parse '{input}' because {reason}")]
+    Parse { input: String, reason: String },
+    #[error("Nothing is listening for raw data anymore")]
+    ReceiverClosed,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SerialDevices {
     /// Hold all devices
     pub devices: Vec<Device>,
+    /// Name each of `devices` is saved under, in the same order
+    pub names: Vec<String>,
+    /// Free text saved with each of `devices`, like wiring or quirks, in the same order
+    pub notes: Vec<String>,
     /// To show in drop down
     pub labels: Vec<Vec<String>>,
     /// NOTE: No idea what this is for
@@ -28,13 +74,80 @@ impl Default for SerialDevices {
     fn default() -> Self {
         Self {
             devices: Vec::default(),
+            names: Vec::default(),
+            notes: Vec::default(),
             labels: Vec::default(),
             number_of_plots: vec![1],
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl SerialDevices {
+    /// Every saved device with its name
+    pub fn profiles(&self) -> impl Iterator<Item = (&str, &Device)> {
+        self.names
+            .iter()
+            .map(String::as_str)
+            .zip(self.devices.iter())
+    }
+
+    /// Device saved under `name`
+    pub fn get(&self, name: &str) -> Option<&Device> {
+        self.profiles()
+            .find(|(saved, _)| *saved == name)
+            .map(|(_, device)| device)
+    }
+
+    /// Save `device` under `name`, replacing whatever was saved under it before
+    pub fn save(&mut self, name: &str, device: Device) {
+        match self.names.iter().position(|saved| saved == name) {
+            Some(idx) if idx < self.devices.len() => self.devices[idx] = device,
+            _ => {
+                // Devices saved without a name can't be picked, so they go
+                self.devices.truncate(self.names.len());
+                self.names.push(name.to_string());
+                self.devices.push(device);
+                self.notes.resize(self.names.len(), String::new());
+            }
+        }
+    }
+
+    /// Notes of the device saved under `name`, empty if it has none
+    pub fn notes(&self, name: &str) -> &str {
+        self.names
+            .iter()
+            .position(|saved| saved == name)
+            .and_then(|idx| self.notes.get(idx))
+            .map_or("", String::as_str)
+    }
+
+    /// Notes of the device saved under `name` to edit, `None` if nothing is
+    pub fn notes_mut(&mut self, name: &str) -> Option<&mut String> {
+        let idx = self.names.iter().position(|saved| saved == name)?;
+        if idx >= self.notes.len() {
+            // Profiles saved before there were notes have none
+            self.notes.resize(self.names.len(), String::new());
+        }
+        self.notes.get_mut(idx)
+    }
+
+    /// Forget the device saved under `name`, returning whether there was one
+    pub fn remove(&mut self, name: &str) -> bool {
+        let Some(idx) = self.names.iter().position(|saved| saved == name) else {
+            return false;
+        };
+        self.names.remove(idx);
+        if idx < self.devices.len() {
+            self.devices.remove(idx);
+        }
+        if idx < self.notes.len() {
+            self.notes.remove(idx);
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Device {
     /// Name of device
     pub path: String,
@@ -56,14 +169,11 @@ impl Display for Device {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Device: {}, {}, {}, {}, {}, {}, {}, {:?}",
+            "Device: {}, {} baud, {}, {} flow control, {:?} timeout",
             &self.path,
             &self.baud_rate,
-            &self.data_bits,
+            self.frame(),
             &self.flow_control,
-            &self.flow_control,
-            &self.parity,
-            &self.stop_bits,
             &self.timeout
         )
     }
@@ -83,6 +193,45 @@ impl Default for Device {
     }
 }
 
+/// Shortest read timeout, anything less would make [`perform_reads`] spin
+pub const MIN_TIMEOUT: Duration = Duration::from_millis(1);
+
+/// First sleep after a read that found nothing, see [`IdleBackoff`]
+const IDLE_SLEEP_STEP: Duration = Duration::from_millis(1);
+
+/// Sleeps between reads of an idle source, so the serial thread doesn't spin
+/// through read timeouts.
+///
+/// Every read that finds nothing doubles the sleep after it, up to `max`, and
+/// the first byte that arrives brings it back to none. Data that arrives while
+/// the thread sleeps waits in the OS buffer, so nothing is lost, but the first
+/// packet after a quiet spell, and a command sent during one, can be up to
+/// `max` late. A zero `max` never sleeps.
+#[derive(Debug, Clone, Copy)]
+struct IdleBackoff {
+    max: Duration,
+    sleep: Duration,
+}
+
+impl IdleBackoff {
+    fn new(max: Duration) -> Self {
+        Self {
+            max,
+            sleep: Duration::ZERO,
+        }
+    }
+
+    /// Sleep as long as the reads before call for, after a read that got `read` bytes
+    fn after_read(&mut self, read: usize) {
+        if read > 0 {
+            self.sleep = Duration::ZERO;
+            return;
+        }
+        std::thread::sleep(self.sleep);
+        self.sleep = (self.sleep * 2).max(IDLE_SLEEP_STEP).min(self.max);
+    }
+}
+
 impl Device {
     pub fn new(
         name: String,
@@ -145,15 +294,703 @@ impl Device {
         self
     }
 
-    pub fn open(&self) -> serialport::Result<Box<dyn SerialPort>> {
+    /// Read back the settings `port` is actually using, where the driver reports them
+    pub fn from_port(port: &dyn SerialPort) -> serialport::Result<Self> {
+        Ok(Self {
+            path: port.name().unwrap_or_default(),
+            baud_rate: port.baud_rate()? as usize,
+            data_bits: port.data_bits()?,
+            flow_control: port.flow_control()?,
+            parity: port.parity()?,
+            stop_bits: port.stop_bits()?,
+            timeout: port.timeout(),
+        })
+    }
+
+    /// Character frame in the usual short form, like `8N1`
+    pub fn frame(&self) -> String {
+        let parity = match self.parity {
+            Parity::None => 'N',
+            Parity::Odd => 'O',
+            Parity::Even => 'E',
+        };
+        format!(
+            "{}{}{}",
+            u8::from(self.data_bits),
+            parity,
+            u8::from(self.stop_bits)
+        )
+    }
+
+    /// Check that the settings make a combination serial backends accept
+    pub fn validate(&self) -> Result<(), String> {
+        if self.baud_rate == 0 {
+            return Err(String::from("Baud rate must be greater than zero"));
+        }
+        if self.timeout < MIN_TIMEOUT {
+            return Err(format!(
+                "Timeout must be at least {} ms",
+                MIN_TIMEOUT.as_millis()
+            ));
+        }
+        if u32::try_from(self.baud_rate).is_err() {
+            return Err(format!("Baud rate {} is too large", self.baud_rate));
+        }
+        if self.data_bits == DataBits::Five && self.stop_bits == StopBits::Two {
+            return Err(String::from(
+                "Five data bits with two stop bits isn't supported, use one stop bit",
+            ));
+        }
+        Ok(())
+    }
+
+    /// A setting the port may open with but that many adapters get wrong,
+    /// worth a line in the log rather than refusing to connect
+    pub fn warning(&self) -> Option<String> {
+        let long_frame = self.data_bits == DataBits::Eight
+            && self.parity != Parity::None
+            && self.stop_bits == StopBits::Two;
+        long_frame.then(|| {
+            format!(
+                "Eight data bits with {} parity and two stop bits is a 12 bit frame some adapters can't send",
+                self.parity
+            )
+        })
+    }
+
+    pub fn open(&self) -> Result<Box<dyn SerialPort>, Error> {
         serialport::new(self.path.clone(), self.baud_rate as u32)
-            .timeout(self.timeout)
+            .timeout(self.timeout.max(MIN_TIMEOUT))
             .data_bits(self.data_bits)
             .parity(self.parity)
             .flow_control(self.flow_control)
             .stop_bits(self.stop_bits)
             .open()
+            .map_err(|source| {
+                let path = self.path.clone();
+                if is_busy(&source, &self.path) {
+                    Error::Busy { path }
+                } else {
+                    Error::Open { path, source }
+                }
+            })
+    }
+}
+
+/// How often the serial thread checks how many bytes wait in the OS buffer
+const WAITING_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Longest a TCP source opened without a timeout of its own waits for the
+/// connection to be accepted, see [`Source::open_within`]
+const TCP_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long each read from a TCP source waits for data
+const TCP_READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Something [`serial_thread`] can read packets from and write commands to
+pub trait DataSource: Read + Write + Send {
+    /// What the source says about itself once open, for display
+    fn describe(&self) -> String;
+    /// Start holding the line in the break condition
+    fn set_break(&self) -> serialport::Result<()>;
+    /// Release the line from the break condition
+    fn clear_break(&self) -> serialport::Result<()>;
+    /// Bytes received by the OS but not read yet, `None` if the source can't tell
+    fn bytes_waiting(&self) -> Option<u32> {
+        None
+    }
+}
+
+impl DataSource for Box<dyn SerialPort> {
+    fn describe(&self) -> String {
+        match Device::from_port(self.as_ref()) {
+            Ok(applied) => format!("Reported {}", applied),
+            Err(e) => format!("Driver didn't report its settings because: {}", e),
+        }
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        self.as_ref().set_break()
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        self.as_ref().clear_break()
+    }
+
+    fn bytes_waiting(&self) -> Option<u32> {
+        self.bytes_to_read().ok()
+    }
+}
+
+/// A serial port bridged over TCP, e.g. by ser2net or esp-link
+struct TcpSource {
+    stream: TcpStream,
+}
+
+impl Read for TcpSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.stream.read(buf)? {
+            // Unlike a serial port, a socket only reads nothing once the other end is gone
+            0 if !buf.is_empty() => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "the connection was closed",
+            )),
+            n => Ok(n),
+        }
+    }
+}
+
+impl Write for TcpSource {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl DataSource for TcpSource {
+    fn describe(&self) -> String {
+        match self.stream.peer_addr() {
+            Ok(peer) => format!("Connected to {}", peer),
+            Err(e) => format!("Connected, but the peer is unknown because: {}", e),
+        }
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        Err(serialport::Error::new(
+            serialport::ErrorKind::Unknown,
+            "a TCP connection has no break condition",
+        ))
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+}
+
+/// Where [`serial_thread`] gets its bytes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Serial(Device),
+    /// A bridged serial port, as `host:port`
+    Tcp(String),
+    /// Synthetic data, for trying things out without a device
+    Demo(DemoSettings),
+    /// A FIFO, character device or regular file, like a pipe from another tool
+    File(PathBuf),
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Serial(device) => write!(f, "{}", device),
+            Source::Tcp(address) => write!(f, "TCP: {}", address),
+            Source::Demo(settings) => write!(f, "Demo: {} channels", settings.channels),
+            Source::File(path) => write!(f, "File: {}", path.display()),
+        }
+    }
+}
+
+impl Source {
+    pub fn open(&self) -> Result<Box<dyn DataSource>, Error> {
+        self.open_for(TCP_CONNECT_TIMEOUT)
+    }
+
+    /// Open the source, giving a TCP host up to `timeout` to accept
+    fn open_for(&self, timeout: Duration) -> Result<Box<dyn DataSource>, Error> {
+        match self {
+            Source::Serial(device) => Ok(Box::new(device.open()?)),
+            Source::Tcp(address) => {
+                let connect = || {
+                    let deadline = Instant::now() + timeout;
+                    let stream = connect_any(address.to_socket_addrs()?, deadline)?;
+                    stream.set_read_timeout(Some(TCP_READ_TIMEOUT))?;
+                    stream.set_nodelay(true)?;
+                    Ok(stream)
+                };
+                connect()
+                    .map(|stream| Box::new(TcpSource { stream }) as Box<dyn DataSource>)
+                    .map_err(|source| Error::Connect {
+                        address: address.clone(),
+                        source,
+                    })
+            }
+            Source::Demo(settings) => Ok(Box::new(demo::DemoSource::new(settings.clone()))),
+            Source::File(path) => file::FileSource::open(path)
+                .map(|source| Box::new(source) as Box<dyn DataSource>)
+                .map_err(|source| Error::File {
+                    path: path.display().to_string(),
+                    source,
+                }),
+        }
+    }
+
+    /// Like [`Source::open`], but gives up after `timeout`.
+    ///
+    /// The open runs on a thread of its own that is abandoned on timeout, should
+    /// it still succeed later the source is closed again right away.
+    pub fn open_within(&self, timeout: Duration) -> Result<Box<dyn DataSource>, Error> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let source = self.clone();
+        std::thread::spawn(move || {
+            // Nobody listening anymore drops the source, which closes it
+            let _ = tx.send(source.open_for(timeout));
+        });
+        rx.recv_timeout(timeout).unwrap_or_else(|_| {
+            Err(Error::OpenTimeout {
+                name: self.to_string(),
+                timeout,
+            })
+        })
+    }
+}
+
+/// Connect to the first of `addrs` that accepts before `deadline`.
+///
+/// A host name can resolve to several addresses, like IPv6 and IPv4 ones for
+/// `localhost`, of which the server may only listen on some.
+fn connect_any(
+    addrs: impl IntoIterator<Item = SocketAddr>,
+    deadline: Instant,
+) -> io::Result<TcpStream> {
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "the host has no address");
+    for addr in addrs {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!(
+                    "no address answered in time, the last because {}",
+                    last_error
+                ),
+            ));
+        }
+        match TcpStream::connect_timeout(&addr, left) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+/// Whether opening the port at `path` failed because something else holds it.
+///
+/// serialport already opens ports exclusively where the platform supports it
+/// (`TIOCEXCL` on unix, and COM ports are always exclusive), see [`looks_busy`].
+fn is_busy(error: &serialport::Error, path: &str) -> bool {
+    looks_busy(error, cfg!(windows), || {
+        // Paths like `\\.\COM10` list as `COM10`
+        let name = path.trim_start_matches("\\\\.\\");
+        serialport::available_ports().is_ok_and(|ports| {
+            ports
+                .iter()
+                .any(|port| port.port_name.eq_ignore_ascii_case(name))
+        })
+    })
+}
+
+/// Whether `error` from opening a port means someone else holds it.
+///
+/// On unix an exclusive hold comes back as `EBUSY`. Windows answers a held
+/// COM port with `ERROR_ACCESS_DENIED`, which serialport reports as
+/// [`serialport::ErrorKind::NoDevice`] like a port that isn't there, in the
+/// language of the system. A port that is still `listed` is there, so it's held.
+fn looks_busy(error: &serialport::Error, windows: bool, listed: impl FnOnce() -> bool) -> bool {
+    match error.kind() {
+        serialport::ErrorKind::Unknown => error.description.to_lowercase().contains("busy"),
+        serialport::ErrorKind::NoDevice if windows => {
+            error
+                .description
+                .to_lowercase()
+                .contains("access is denied")
+                || listed()
+        }
+        _ => false,
+    }
+}
+
+/// Advisory lock telling other Symphony windows a port is in use.
+///
+/// An OS lock on a file in the temp directory, which the OS lets go of when
+/// the process exits, so a crash can't leave a lock behind that still counts.
+/// It only lets another window warn before it tries the port, opening stays
+/// the real test.
+#[derive(Debug)]
+pub struct PortLock {
+    file: PathBuf,
+    /// Holds the lock until dropped
+    _handle: File,
+}
+
+/// Another Symphony process with a port locked, see [`PortLock::holder`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockHolder {
+    /// Its process id, where the platform lets a locked file be read
+    pub pid: Option<u32>,
+}
+
+impl Display for LockHolder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.pid {
+            Some(pid) => write!(f, "Symphony process {}", pid),
+            None => write!(f, "another Symphony window"),
+        }
+    }
+}
+
+impl PortLock {
+    /// Lock file for the port at `path`, in the temp directory
+    fn file(path: &str) -> PathBuf {
+        let name: String = path
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        std::env::temp_dir().join(format!("symphony-{}.lock", name))
+    }
+
+    /// Mark the port at `path` as used by this process
+    pub fn acquire(path: &str) -> io::Result<Self> {
+        let file = Self::file(path);
+        let mut handle = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&file)?;
+        handle.try_lock()?;
+        handle.set_len(0)?;
+        write!(handle, "{}", std::process::id())?;
+        Ok(Self {
+            file,
+            _handle: handle,
+        })
+    }
+
+    /// Another process that has the port at `path` locked
+    pub fn holder(path: &str) -> Option<LockHolder> {
+        let file = Self::file(path);
+        // No file, or one a crashed process left behind that locks right away
+        let handle = File::open(&file).ok()?;
+        match handle.try_lock() {
+            Ok(()) => None,
+            Err(TryLockError::WouldBlock) => {
+                // Windows won't read a file another process has locked
+                let pid = std::fs::read_to_string(&file)
+                    .ok()
+                    .and_then(|pid| pid.trim().parse::<u32>().ok());
+                (pid != Some(std::process::id())).then_some(LockHolder { pid })
+            }
+            Err(TryLockError::Error(_)) => None,
+        }
+    }
+}
+
+impl Drop for PortLock {
+    fn drop(&mut self) {
+        // Still locked, so nobody else took the file over meanwhile
+        let _ = std::fs::remove_file(&self.file);
+    }
+}
+
+/// How the incoming byte stream is cut into packets
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Framing {
+    #[default]
+    /// Split on `\r\n` when a line has it, otherwise on `\0\0`
+    Auto,
+    /// Lines end in `\r\n`, a lone `\n` is accepted too
+    Crlf,
+    /// Lines end in `\n`
+    Lf,
+    /// Packets end in a NUL byte
+    Nul,
+    /// Packets end in a user chosen sequence
+    Custom(String),
+    /// Binary packets of `length` bytes, each starting with `sync` when set
+    Fixed { length: usize, sync: Option<u8> },
+    /// Modbus RTU replies, whose length follows from their function code
+    ModbusRtu,
+}
+
+impl Display for Framing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Framing::Auto => write!(f, "Auto"),
+            Framing::Crlf => write!(f, "CRLF"),
+            Framing::Lf => write!(f, "LF"),
+            Framing::Nul => write!(f, "NUL"),
+            Framing::Custom(_) => write!(f, "Custom"),
+            Framing::Fixed { .. } => write!(f, "Fixed"),
+            Framing::ModbusRtu => write!(f, "Modbus RTU"),
+        }
+    }
+}
+
+impl Framing {
+    /// Byte a read can stop at, since every text packet ends with it
+    fn last_byte(&self) -> u8 {
+        match self {
+            Framing::Auto
+            | Framing::Crlf
+            | Framing::Lf
+            | Framing::Fixed { .. }
+            | Framing::ModbusRtu => b'\n',
+            Framing::Nul => b'\0',
+            Framing::Custom(terminator) => terminator.bytes().last().unwrap_or(b'\n'),
+        }
+    }
+
+    /// Length of the start of `buf` that ends in a whole terminator, `None`
+    /// while no packet in it is complete.
+    ///
+    /// A read stops at [`Framing::last_byte`], which on its own doesn't end a
+    /// packet when a custom terminator is longer than one byte.
+    fn complete_len(&self, buf: &[u8]) -> Option<usize> {
+        match self {
+            Framing::Custom(terminator) if terminator.len() > 1 => {
+                let terminator = terminator.as_bytes();
+                buf.windows(terminator.len())
+                    .rposition(|window| window == terminator)
+                    .map(|start| start + terminator.len())
+            }
+            _ => {
+                let last_byte = self.last_byte();
+                buf.iter().rposition(|b| *b == last_byte).map(|end| end + 1)
+            }
+        }
+    }
+
+    /// Terminator `buf` is split on, [`Framing::Auto`] picks it per read
+    pub fn terminator(&self, buf: &[u8]) -> Vec<u8> {
+        match self {
+            Framing::Auto if buf.windows(2).any(|w| w == b"\r\n") => b"\r\n".to_vec(),
+            Framing::Auto => b"\0\0".to_vec(),
+            Framing::Crlf => b"\r\n".to_vec(),
+            Framing::Lf => b"\n".to_vec(),
+            Framing::Nul => b"\0".to_vec(),
+            Framing::Custom(terminator) => terminator.as_bytes().to_vec(),
+            Framing::Fixed { .. } | Framing::ModbusRtu => Vec::new(),
+        }
+    }
+
+    /// Cut `buf` into payloads, without their terminators
+    fn split<'a>(&self, buf: &'a [u8]) -> Vec<&'a [u8]> {
+        match self {
+            Framing::Auto => split_terminator(buf, &self.terminator(buf)),
+            Framing::Crlf => split_terminator(buf, b"\n")
+                .into_iter()
+                .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+                .collect(),
+            Framing::Lf => split_terminator(buf, b"\n"),
+            Framing::Nul => split_terminator(buf, b"\0"),
+            Framing::Custom(terminator) => split_terminator(buf, terminator.as_bytes()),
+            Framing::Fixed { .. } | Framing::ModbusRtu => vec![buf],
+        }
+    }
+}
+
+/// Like [`str::split_terminator`], but for bytes and a whole-sequence terminator
+fn split_terminator<'a>(buf: &'a [u8], terminator: &[u8]) -> Vec<&'a [u8]> {
+    if terminator.is_empty() {
+        return vec![buf];
     }
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut idx = 0;
+    while idx + terminator.len() <= buf.len() {
+        if &buf[idx..idx + terminator.len()] == terminator {
+            parts.push(&buf[start..idx]);
+            idx += terminator.len();
+            start = idx;
+        } else {
+            idx += 1;
+        }
+    }
+    if start < buf.len() {
+        parts.push(&buf[start..]);
+    }
+    parts
+}
+
+/// Cut every complete `length` byte frame off the front of `pending`.
+///
+/// With a `sync` byte, bytes before it are thrown away so a frame that was
+/// cut short doesn't shift every frame after it.
+fn take_frames(pending: &mut Vec<u8>, length: usize, sync: Option<u8>) -> Vec<Vec<u8>> {
+    let length = length.max(1);
+    let mut frames = Vec::new();
+    loop {
+        if let Some(sync) = sync {
+            match pending.iter().position(|b| *b == sync) {
+                Some(start) => drop(pending.drain(..start)),
+                None => {
+                    pending.clear();
+                    break;
+                }
+            }
+        }
+        if pending.len() < length {
+            break;
+        }
+        frames.push(pending.drain(..length).collect());
+    }
+    frames
+}
+
+/// Everything the serial thread shares with whoever spawned it
+#[derive(Debug, Clone, Default)]
+pub struct SerialShared {
+    /// How far the thread got with the port
+    pub state: Arc<RwLock<ConnectionState>>,
+    /// Raise to make the thread close the port and return
+    pub stop: Arc<AtomicBool>,
+    /// Packets dropped because the queue was full
+    pub dropped: Arc<AtomicUsize>,
+    /// Modbus frames dropped because their CRC didn't match
+    pub bad_frames: Arc<AtomicUsize>,
+    /// Text packets cut off at the [`LineLimit`] without a terminator
+    pub overlong: Arc<AtomicUsize>,
+    /// Set once the port refused to send a break
+    pub break_unsupported: Arc<AtomicBool>,
+    /// Bytes waiting unread in the OS buffer as of the last poll, rising when
+    /// reading falls behind. `None` for sources that can't tell
+    pub bytes_waiting: Arc<RwLock<Option<u32>>>,
+    /// How the last read was framed
+    pub diagnostics: Arc<RwLock<ReadDiagnostics>>,
+    /// Bytes received since the last terminator, like a prompt without a newline
+    pub partial: Arc<RwLock<Vec<u8>>>,
+    /// Every byte read, before framing, until someone takes them
+    pub stream: Arc<RwLock<Vec<u8>>>,
+}
+
+/// Where the serial thread is with its port
+#[derive(Debug, Default)]
+pub enum ConnectionState {
+    #[default]
+    /// The port is still being opened
+    Connecting,
+    /// The source is open, with what it reports about itself
+    Connected(String),
+    /// The port couldn't be opened
+    Error(Error),
+    /// The port was closed again
+    Closed,
+}
+
+/// What the last read looked like, to work out why lines are framed oddly
+#[derive(Debug, Clone, Default)]
+pub struct ReadDiagnostics {
+    /// Terminator the read was split on, empty for binary frames
+    pub terminator: Vec<u8>,
+    /// Start of the bytes as read, terminators included
+    pub sample: Vec<u8>,
+    /// The last [`ReadDiagnostics::WINDOW_LEN`] bytes read, over all reads
+    pub window: VecDeque<u8>,
+}
+
+/// How often bytes that matter for framing turn up in [`ReadDiagnostics::window`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ByteTally {
+    pub carriage_returns: usize,
+    pub line_feeds: usize,
+    pub nuls: usize,
+    /// Control and non-ASCII bytes other than the above, tabs excluded
+    pub non_printable: usize,
+    pub total: usize,
+}
+
+impl ReadDiagnostics {
+    /// Bytes kept in the window the tally is taken over
+    pub const WINDOW_LEN: usize = 4096;
+
+    fn push_window(&mut self, buf: &[u8]) {
+        let keep = buf.len().min(Self::WINDOW_LEN);
+        let overflow = (self.window.len() + keep).saturating_sub(Self::WINDOW_LEN);
+        self.window.drain(..overflow);
+        self.window.extend(&buf[buf.len() - keep..]);
+    }
+
+    /// Count of every kind of byte in the recent window
+    pub fn tally(&self) -> ByteTally {
+        let mut tally = ByteTally {
+            total: self.window.len(),
+            ..ByteTally::default()
+        };
+        for &byte in self.window.iter() {
+            match byte {
+                b'\r' => tally.carriage_returns += 1,
+                b'\n' => tally.line_feeds += 1,
+                0 => tally.nuls += 1,
+                b'\t' | b' '..=b'~' => {}
+                _ => tally.non_printable += 1,
+            }
+        }
+        tally
+    }
+}
+
+impl SerialShared {
+    fn set_partial(&self, bytes: &[u8]) {
+        if let Ok(mut partial) = self.partial.write() {
+            if *partial != bytes {
+                *partial = bytes.to_vec();
+            }
+        }
+    }
+
+    /// Most bytes [`SerialShared::stream`] holds before the oldest are dropped
+    pub const STREAM_LEN: usize = 64 * 1024;
+
+    fn push_stream(&self, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        if let Ok(mut stream) = self.stream.write() {
+            stream.extend_from_slice(bytes);
+            let overflow = stream.len().saturating_sub(Self::STREAM_LEN);
+            stream.drain(..overflow);
+        }
+    }
+
+    /// Bytes read since the last call, exactly as they arrived
+    pub fn take_stream(&self) -> Vec<u8> {
+        self.stream
+            .write()
+            .map(|mut stream| std::mem::take(&mut *stream))
+            .unwrap_or_default()
+    }
+
+    fn set_state(&self, state: ConnectionState) {
+        if let Ok(mut current) = self.state.write() {
+            *current = state;
+        }
+    }
+
+    /// Longest sample kept in [`ReadDiagnostics`]
+    const SAMPLE_LEN: usize = 64;
+
+    /// Note that `buf` was just read and split on `terminator`
+    fn record_read(&self, buf: &[u8], terminator: Vec<u8>) {
+        if buf.is_empty() {
+            return;
+        }
+        if let Ok(mut diagnostics) = self.diagnostics.write() {
+            diagnostics.terminator = terminator;
+            diagnostics.sample = buf[..buf.len().min(Self::SAMPLE_LEN)].to_vec();
+            diagnostics.push_window(buf);
+        }
+    }
+}
+
+/// Something for the serial thread to do to the open port
+#[derive(Debug, Clone, PartialEq)]
+pub enum PortCommand {
+    /// Write the bytes as they are
+    Write(Vec<u8>),
+    /// Hold the line in the break condition for this long
+    Break(Duration),
 }
 
 #[derive(Debug, PartialEq)]
@@ -161,61 +998,1052 @@ pub struct Packet {
     pub absolute_time: u128,
     pub relative_time: u128,
     pub payload: String,
+    /// Bytes the payload was decoded from, without the terminator
+    pub raw: Vec<u8>,
 }
 
+impl Packet {
+    fn new(t_zero: Instant, payload: String, raw: Vec<u8>) -> Self {
+        Self::arrived_at(t_zero, Instant::now(), payload, raw)
+    }
+
+    /// Packet stamped with when its data arrived at `at` instead of now
+    fn arrived_at(t_zero: Instant, at: Instant, payload: String, raw: Vec<u8>) -> Self {
+        Self {
+            relative_time: at.saturating_duration_since(t_zero).as_millis(),
+            absolute_time: unix_millis(at),
+            payload,
+            raw,
+        }
+    }
+}
+
+/// Set once the clock was seen before the epoch, so it's only reported once
+static CLOCK_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Wall clock time of `at` in milliseconds since the epoch.
+///
+/// Hosts without a real time clock can boot with the clock before the epoch,
+/// those times come out as 0 instead of panicking the serial thread.
+fn unix_millis(at: Instant) -> u128 {
+    let since_epoch = SystemTime::now()
+        .checked_sub(at.elapsed())
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok());
+    match since_epoch {
+        Some(since_epoch) => since_epoch.as_millis(),
+        None => {
+            if !CLOCK_WARNED.swap(true, Ordering::Relaxed) {
+                eprintln!(
+                    "WARNING: system clock is before the Unix epoch, absolute times will be 0"
+                );
+            }
+            0
+        }
+    }
+}
+
+/// Which moment a text packet's timestamps describe
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Timestamping {
+    #[default]
+    /// When the packet was split off, after its terminator was read
+    PerLine,
+    /// When the packet's first byte arrived, tracked for every read
+    PerByte,
+}
+
+/// What becomes of a text packet that reaches [`LineLimit::max_len`] without a terminator
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Overlong {
+    #[default]
+    /// Queue the bytes so far as a packet of their own
+    Emit,
+    /// Throw the bytes so far away
+    Drop,
+}
+
+impl Display for Overlong {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Overlong::Emit => write!(f, "Emit"),
+            Overlong::Drop => write!(f, "Drop"),
+        }
+    }
+}
+
+/// Longest a text packet may grow while its terminator doesn't turn up, so a
+/// device that never sends one can't eat up all memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LineLimit {
+    pub max_len: usize,
+    pub overlong: Overlong,
+}
+
+impl Default for LineLimit {
+    fn default() -> Self {
+        Self {
+            max_len: 64 * 1024,
+            overlong: Overlong::default(),
+        }
+    }
+}
+
+impl LineLimit {
+    /// Whether `pending` is as long as a packet may get, counting it in
+    /// `shared.overlong` if so
+    fn reached(&self, pending: &[u8], shared: &SerialShared) -> bool {
+        let reached = pending.len() >= self.max_len.max(1);
+        if reached {
+            shared.overlong.fetch_add(1, Ordering::Relaxed);
+        }
+        reached
+    }
+}
+
+/// Joins text packets that follow each other closely into one, for devices
+/// that send a character at a time, like keypads
+#[derive(Debug, Default)]
+pub struct PacketMerge {
+    /// Longest gap between two packets that still joins them, zero never joins
+    window: Duration,
+    /// Longest a joined packet may get, like [`LineLimit::max_len`]
+    max_len: usize,
+    /// Packets joined so far, and when the last of them was split off
+    held: Option<(Packet, Instant)>,
+}
+
+impl PacketMerge {
+    pub fn new(window: Duration, max_len: usize) -> Self {
+        Self {
+            window,
+            max_len,
+            held: None,
+        }
+    }
+
+    /// Join `packet` to the held one, or queue that and hold `packet` instead
+    /// if it came too late or would make it too long.
+    ///
+    /// The joined packet keeps the timestamps of its first part.
+    fn queue(
+        &mut self,
+        raw_data_tx: &SyncSender<Packet>,
+        packet: Packet,
+        dropped: &AtomicUsize,
+    ) -> Result<(), Error> {
+        if self.window.is_zero() {
+            return queue_packet(raw_data_tx, packet, dropped);
+        }
+        let now = Instant::now();
+        match self.held.take() {
+            Some((mut held, last))
+                if now.duration_since(last) < self.window
+                    && held.raw.len() + packet.raw.len() <= self.max_len.max(1) =>
+            {
+                held.payload.push_str(&packet.payload);
+                held.raw.extend_from_slice(&packet.raw);
+                self.held = Some((held, now));
+            }
+            Some((held, _)) => {
+                self.held = Some((packet, now));
+                queue_packet(raw_data_tx, held, dropped)?;
+            }
+            None => self.held = Some((packet, now)),
+        }
+        Ok(())
+    }
+
+    /// Queue the held packet once `window` passed without another to join it
+    fn flush_due(
+        &mut self,
+        raw_data_tx: &SyncSender<Packet>,
+        dropped: &AtomicUsize,
+    ) -> Result<(), Error> {
+        match self.held.take() {
+            Some((held, last)) if last.elapsed() >= self.window => {
+                queue_packet(raw_data_tx, held, dropped)
+            }
+            held => {
+                self.held = held;
+                Ok(())
+            }
+        }
+    }
+
+    /// Queue the held packet right away, like when the source is closed
+    fn flush(&mut self, raw_data_tx: &SyncSender<Packet>, dropped: &AtomicUsize) {
+        if let Some((held, _)) = self.held.take() {
+            // Nothing to do if nobody is receiving anymore
+            let _ = queue_packet(raw_data_tx, held, dropped);
+        }
+    }
+}
+
+/// Bytes that don't make up a whole packet yet, with when they arrived
+#[derive(Debug, Default)]
+pub struct Arrivals {
+    bytes: Vec<u8>,
+    /// Offset in `bytes` each read started at, and when that read returned
+    chunks: VecDeque<(usize, Instant)>,
+}
+
+impl Arrivals {
+    fn push(&mut self, bytes: &[u8], at: Instant) {
+        self.chunks.push_back((self.bytes.len(), at));
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    /// When the byte at `offset` arrived
+    fn arrived(&self, offset: usize) -> Instant {
+        let idx = self.chunks.partition_point(|(start, _)| *start <= offset);
+        self.chunks[idx.saturating_sub(1)].1
+    }
+
+    /// Forget the first `count` bytes
+    fn consume(&mut self, count: usize) {
+        self.bytes.drain(..count);
+        while self.chunks.get(1).is_some_and(|(start, _)| *start <= count) {
+            self.chunks.pop_front();
+        }
+        for (start, _) in self.chunks.iter_mut() {
+            *start = start.saturating_sub(count);
+        }
+        if self.bytes.is_empty() {
+            self.chunks.clear();
+        }
+    }
+}
+
+/// Queue `packet`, counting it in `dropped` if the queue is full
+fn queue_packet(
+    raw_data_tx: &SyncSender<Packet>,
+    packet: Packet,
+    dropped: &AtomicUsize,
+) -> Result<(), Error> {
+    match raw_data_tx.try_send(packet) {
+        Ok(()) => Ok(()),
+        Err(TrySendError::Full(_)) => {
+            dropped.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+        Err(TrySendError::Disconnected(_)) => Err(Error::ReceiverClosed),
+    }
+}
+
+/// How long repeats of the same error are counted instead of printed
+const REPORT_WINDOW: Duration = Duration::from_secs(1);
+
+/// Last error [`report`] printed, when, and how often it came again since
+static REPORTED: Mutex<Option<(String, Instant, usize)>> = Mutex::new(None);
+
+/// Print `message` as an error, folding repeats within [`REPORT_WINDOW`] into
+/// one `message (×N)` line, so an error on every read doesn't flood stderr
+fn report(message: String) {
+    let Ok(mut reported) = REPORTED.lock() else {
+        eprintln!("ERROR: {}", message);
+        return;
+    };
+    for line in fold_report(&mut reported, message, Instant::now()) {
+        eprintln!("ERROR: {}", line);
+    }
+}
+
+/// Print the `message (×N)` line repeats are still owed, once
+/// [`REPORT_WINDOW`] has passed since the last line or right away when `now`
+/// is set, like when the thread stops
+fn flush_report(now: bool) {
+    let Ok(mut reported) = REPORTED.lock() else {
+        return;
+    };
+    if let Some(line) = due_report(&mut reported, Instant::now(), now) {
+        eprintln!("ERROR: {}", line);
+    }
+}
+
+/// Lines to print for `message` coming in at `now`, counting it in `reported`
+/// instead if it repeats the last one within [`REPORT_WINDOW`]
+fn fold_report(
+    reported: &mut Option<(String, Instant, usize)>,
+    message: String,
+    now: Instant,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some((last, at, repeats)) = reported.as_mut() {
+        if *last == message {
+            if now.duration_since(*at) < REPORT_WINDOW {
+                *repeats += 1;
+            } else {
+                lines.push(format!("{} (×{})", message, *repeats + 1));
+                *at = now;
+                *repeats = 0;
+            }
+            return lines;
+        }
+        if *repeats > 0 {
+            lines.push(format!("{} (×{})", last, repeats));
+        }
+    }
+    lines.push(message.clone());
+    *reported = Some((message, now, 0));
+    lines
+}
+
+/// The `message (×N)` line for the repeats counted in `reported`, if there
+/// are any and [`REPORT_WINDOW`] has passed or `force` is set
+fn due_report(
+    reported: &mut Option<(String, Instant, usize)>,
+    now: Instant,
+    force: bool,
+) -> Option<String> {
+    let (last, at, repeats) = reported.as_mut()?;
+    if *repeats == 0 || !(force || now.duration_since(*at) >= REPORT_WINDOW) {
+        return None;
+    }
+    let line = format!("{} (×{})", last, repeats);
+    *at = now;
+    *repeats = 0;
+    Some(line)
+}
+
+/// Whether reading can carry on after `e`, printing the errors worth knowing about
+fn read_failed(e: io::Error) -> Result<(), Error> {
+    match e.kind() {
+        // Timeout is ok, just means there is no data to read
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => Ok(()),
+        // The source is gone for good, like a closed TCP connection
+        io::ErrorKind::UnexpectedEof => Err(Error::Read(e)),
+        _ => {
+            report(Error::Read(e).to_string());
+            Ok(())
+        }
+    }
+}
+
+/// Read up to the next `framing` terminator from `port` and queue its packets
+/// on `raw_data_tx`.
+///
+/// Bytes that arrive without a terminator before the read times out wait in
+/// `pending`, and are shared as `shared.partial` meanwhile. So does a read
+/// that stopped at the last byte of a longer terminator without the rest of
+/// it, until the whole terminator has arrived. Every byte also
+/// goes to `shared.stream` as soon as it's read, framed or not.
+/// Packets that don't fit in the queue are dropped and counted in `shared.dropped`.
+/// Once `pending` reaches the line limit of `options` without a terminator
+/// its bytes are emitted or dropped, see [`LineLimit`]. Packets go through `merge` on their way to
+/// the queue, see [`PacketMerge`].
+/// Returns how many bytes were read, or [`Error::ReceiverClosed`] once nobody
+/// is receiving anymore.
 pub fn perform_reads(
-    port: &mut BufReader<Box<dyn SerialPort>>,
-    raw_data_tx: &Sender<Packet>,
+    port: &mut BufReader<Box<dyn DataSource>>,
+    raw_data_tx: &SyncSender<Packet>,
     t_zero: Instant,
-) {
-    let mut buf = "".to_string();
-    let read_to_buf = port.read_line(&mut buf);
+    options: &ReadOptions,
+    pending: &mut Vec<u8>,
+    merge: &mut PacketMerge,
+    shared: &SerialShared,
+) -> Result<usize, Error> {
+    let (framing, limit) = (&options.framing, options.line_limit);
+    let before = pending.len();
+    // Stop at the limit, a source that never sends the terminator would keep this read going
+    let room = limit.max_len.saturating_sub(before) as u64;
+    let read_to_buf = port
+        .by_ref()
+        .take(room)
+        .read_until(framing.last_byte(), pending);
+    shared.push_stream(&pending[before.min(pending.len())..]);
     match read_to_buf {
-        Ok(_) => {
-            let delimiter = if buf.contains("\r\n") { "\r\n" } else { "\0\0" };
-            buf.split_terminator(delimiter).for_each(|s| {
-                let packet = Packet {
-                    relative_time: Instant::now().duration_since(t_zero).as_millis(),
-                    absolute_time: SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap()
-                        .as_millis(),
-                    payload: s.to_owned(),
-                };
-                raw_data_tx.send(packet).expect("failed to send raw data");
-            });
+        Ok(read) => {
+            let Some(end) = framing.complete_len(pending) else {
+                if limit.reached(pending, shared) {
+                    let raw = std::mem::take(pending);
+                    if limit.overlong == Overlong::Emit {
+                        let payload = String::from_utf8_lossy(&raw).into_owned();
+                        merge.queue(
+                            raw_data_tx,
+                            Packet::new(t_zero, payload, raw),
+                            &shared.dropped,
+                        )?;
+                    }
+                }
+                shared.set_partial(pending);
+                return Ok(read);
+            };
+            let buf: Vec<u8> = pending.drain(..end).collect();
+            shared.set_partial(pending);
+            shared.record_read(&buf, framing.terminator(&buf));
+            for raw in framing.split(&buf) {
+                let payload = String::from_utf8_lossy(raw).into_owned();
+                merge.queue(
+                    raw_data_tx,
+                    Packet::new(t_zero, payload, raw.to_vec()),
+                    &shared.dropped,
+                )?;
+            }
+            Ok(read)
         }
-        // Timeout is ok, just means there is no data to read
-        Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => {}
         Err(e) => {
-            println!("Error reading: {:?}", e);
+            shared.set_partial(pending);
+            read_failed(e).map(|()| 0)
         }
     }
 }
 
+/// Open `device` and count the printable lines that arrive within `window`.
+///
+/// The baud rate the other side actually uses gives the highest score,
+/// wrong rates mostly produce garbage bytes.
+pub fn score_baud_rate(
+    device: &Device,
+    window: Duration,
+    options: &ReadOptions,
+) -> Result<usize, Error> {
+    let buf = listen(device, window, options)?;
+    let score = buf
+        .split(|b| *b == b'\n')
+        .filter(|line| {
+            line.iter().any(u8::is_ascii_graphic)
+                && line
+                    .iter()
+                    .all(|b| b.is_ascii_graphic() || matches!(b, b' ' | b'\r' | b'\t'))
+        })
+        .count();
+    Ok(score)
+}
+
+/// Open `device`, collect every byte that arrives within `window` and close it again.
+///
+/// Opening gives up after the connect timeout of `options`, like a connection does.
+pub fn listen(device: &Device, window: Duration, options: &ReadOptions) -> Result<Vec<u8>, Error> {
+    let mut port = Source::Serial(device.clone()).open_within(options.connect_timeout)?;
+    let mut buf = Vec::new();
+    let mut chunk = [0; 256];
+    let start = Instant::now();
+    while start.elapsed() < window {
+        match port.read(&mut chunk) {
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+            Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => {}
+            Err(e) => return Err(Error::Read(e)),
+        }
+    }
+    Ok(buf)
+}
+
+/// Read whatever bytes are waiting and queue every complete fixed-width frame.
+///
+/// Bytes of a frame that isn't complete yet stay in `pending` for the next call.
+pub fn perform_frame_reads(
+    port: &mut BufReader<Box<dyn DataSource>>,
+    raw_data_tx: &SyncSender<Packet>,
+    t_zero: Instant,
+    (length, sync): (usize, Option<u8>),
+    pending: &mut Vec<u8>,
+    shared: &SerialShared,
+) -> Result<usize, Error> {
+    match port.fill_buf() {
+        Ok(bytes) => {
+            shared.record_read(bytes, Vec::new());
+            shared.push_stream(bytes);
+            pending.extend_from_slice(bytes);
+            let consumed = bytes.len();
+            port.consume(consumed);
+            for raw in take_frames(pending, length, sync) {
+                queue_packet(
+                    raw_data_tx,
+                    Packet::new(t_zero, hex_payload(&raw), raw),
+                    &shared.dropped,
+                )?;
+            }
+            Ok(consumed)
+        }
+        Err(e) => read_failed(e).map(|()| 0),
+    }
+}
+
+/// Read whatever bytes are waiting and queue every complete Modbus RTU reply.
+///
+/// Like [`perform_frame_reads`], but frames whose CRC doesn't match are
+/// dropped and counted in `shared.bad_frames`.
+pub fn perform_modbus_reads(
+    port: &mut BufReader<Box<dyn DataSource>>,
+    raw_data_tx: &SyncSender<Packet>,
+    t_zero: Instant,
+    pending: &mut Vec<u8>,
+    shared: &SerialShared,
+) -> Result<usize, Error> {
+    match port.fill_buf() {
+        Ok(bytes) => {
+            shared.record_read(bytes, Vec::new());
+            shared.push_stream(bytes);
+            pending.extend_from_slice(bytes);
+            let consumed = bytes.len();
+            port.consume(consumed);
+            let (frames, bad) = modbus::take_frames(pending);
+            shared.bad_frames.fetch_add(bad, Ordering::Relaxed);
+            for raw in frames {
+                queue_packet(
+                    raw_data_tx,
+                    Packet::new(t_zero, hex_payload(&raw), raw),
+                    &shared.dropped,
+                )?;
+            }
+            Ok(consumed)
+        }
+        Err(e) => read_failed(e).map(|()| 0),
+    }
+}
+
+/// The payload of a binary packet, its bytes in hex
+fn hex_payload(raw: &[u8]) -> String {
+    raw.iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Like [`perform_reads`], but stamps every packet with when its first byte arrived.
+///
+/// Takes whatever bytes are waiting instead of reading up to a terminator, so
+/// it costs a timestamp per read. Incomplete packets wait in `arrivals`, up
+/// to the line limit of `options` at a time. Packets go through `merge` like there.
+pub fn perform_timed_reads(
+    port: &mut BufReader<Box<dyn DataSource>>,
+    raw_data_tx: &SyncSender<Packet>,
+    t_zero: Instant,
+    options: &ReadOptions,
+    arrivals: &mut Arrivals,
+    merge: &mut PacketMerge,
+    shared: &SerialShared,
+) -> Result<usize, Error> {
+    let (framing, limit) = (&options.framing, options.line_limit);
+    let read = match port.fill_buf() {
+        Ok(bytes) => {
+            shared.record_read(bytes, framing.terminator(bytes));
+            shared.push_stream(bytes);
+            arrivals.push(bytes, Instant::now());
+            let consumed = bytes.len();
+            port.consume(consumed);
+            consumed
+        }
+        Err(e) => return read_failed(e).map(|()| 0),
+    };
+    let Some(end) = framing.complete_len(&arrivals.bytes) else {
+        // One read can bring more than the limit, each limit's worth is a packet
+        while limit.reached(&arrivals.bytes, shared) {
+            let len = limit.max_len.max(1);
+            let at = arrivals.arrived(0);
+            let raw = arrivals.bytes[..len].to_vec();
+            arrivals.consume(len);
+            if limit.overlong == Overlong::Emit {
+                let payload = String::from_utf8_lossy(&raw).into_owned();
+                merge.queue(
+                    raw_data_tx,
+                    Packet::arrived_at(t_zero, at, payload, raw),
+                    &shared.dropped,
+                )?;
+            }
+        }
+        shared.set_partial(&arrivals.bytes);
+        return Ok(read);
+    };
+    let complete = &arrivals.bytes[..end];
+    let packets: Vec<Packet> = framing
+        .split(complete)
+        .into_iter()
+        .map(|raw| {
+            let offset = raw.as_ptr() as usize - complete.as_ptr() as usize;
+            let payload = String::from_utf8_lossy(raw).into_owned();
+            Packet::arrived_at(t_zero, arrivals.arrived(offset), payload, raw.to_vec())
+        })
+        .collect();
+    arrivals.consume(end);
+    shared.set_partial(&arrivals.bytes);
+    for packet in packets {
+        merge.queue(raw_data_tx, packet, &shared.dropped)?;
+    }
+    Ok(read)
+}
+
 pub fn get_serial_devices() -> Result<Vec<String>, Error> {
-    let ports = serialport::available_ports().expect("Getting all available ports");
+    let ports = serialport::available_ports().map_err(Error::NoPortsAvailable)?;
     let ports: Vec<String> = ports.iter().map(|p| p.port_name.clone()).collect();
     Ok(ports)
 }
 
+/// What a USB serial adapter reports about itself, which stays the same when
+/// it comes back under another path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsbIdentity {
+    pub vid: u16,
+    pub pid: u16,
+    /// Tells apart several adapters of the same model, not every adapter has one
+    pub serial_number: Option<String>,
+}
+
+impl Display for UsbIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04x}:{:04x}", self.vid, self.pid)?;
+        if let Some(serial_number) = &self.serial_number {
+            write!(f, " ({})", serial_number)?;
+        }
+        Ok(())
+    }
+}
+
+impl UsbIdentity {
+    /// Identity of the adapter at `path`, `None` if it isn't a USB port or is gone
+    pub fn of(path: &str) -> Option<Self> {
+        usb_ports()
+            .into_iter()
+            .find(|(port, _)| port == path)
+            .map(|(_, identity)| identity)
+    }
+
+    /// Path the adapter can be found at now
+    pub fn find(&self) -> Option<String> {
+        usb_ports()
+            .into_iter()
+            .find(|(_, identity)| identity == self)
+            .map(|(port, _)| port)
+    }
+}
+
+/// Path and identity of every USB port present
+fn usb_ports() -> Vec<(String, UsbIdentity)> {
+    let Ok(ports) = serialport::available_ports() else {
+        return Vec::new();
+    };
+    ports
+        .into_iter()
+        .filter_map(|port| match port.port_type {
+            serialport::SerialPortType::UsbPort(info) => Some((
+                port.port_name,
+                UsbIdentity {
+                    vid: info.vid,
+                    pid: info.pid,
+                    serial_number: info.serial_number,
+                },
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Hold `port` in the break condition for `duration`, noting in `shared` if it can't
+fn send_break(port: &dyn DataSource, duration: Duration, shared: &SerialShared) {
+    if let Err(e) = port.set_break() {
+        shared.break_unsupported.store(true, Ordering::Relaxed);
+        eprintln!("ERROR: couldn't send break because {e}");
+        return;
+    }
+    std::thread::sleep(duration);
+    if let Err(e) = port.clear_break() {
+        eprintln!("ERROR: couldn't clear break because {e}");
+    }
+}
+
+/// How [`serial_thread`] opens its source and turns what it reads into packets
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadOptions {
+    /// Opening is abandoned after this long, see [`Source::open_within`]
+    pub connect_timeout: Duration,
+    pub framing: Framing,
+    /// Text packets that never end are cut off here
+    pub line_limit: LineLimit,
+    /// Text packets that follow each other within this are joined, see [`PacketMerge`]
+    pub merge_window: Duration,
+    pub timestamping: Timestamping,
+    /// Longest the thread sleeps between reads while the source is idle,
+    /// which delays the first packet after a quiet spell by as much
+    pub max_idle_sleep: Duration,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            framing: Framing::default(),
+            line_limit: LineLimit::default(),
+            merge_window: Duration::ZERO,
+            timestamping: Timestamping::default(),
+            max_idle_sleep: Duration::from_millis(10),
+        }
+    }
+}
+
+/// Open `source` and keep reading from it until `shared.stop` is raised or the
+/// receiver is dropped.
+///
+/// A dropped receiver, like after the GUI is gone, or a source that ended is
+/// reported on stderr, and the thread returns with `shared.state` closed.
+///
+/// Opening and reading go as `options` say. Whether opening worked is
+/// reported through `shared.state`. Packet times are relative to `t_zero`, so
+/// the caller decides where the time axis starts. Anything queued on
+/// `command_rx` is done to the source between reads.
 pub fn serial_thread(
-    raw_data_tx: Sender<Packet>,
-    device: Device,
-    connected_lock: Arc<RwLock<bool>>,
+    raw_data_tx: SyncSender<Packet>,
+    command_rx: Receiver<PortCommand>,
+    source: Source,
+    options: ReadOptions,
+    t_zero: Instant,
+    shared: SerialShared,
 ) {
-    loop {
-        match device.open() {
-            Ok(p) => {
-                if let Ok(mut connected) = connected_lock.write() {
-                    *connected = true;
-                }
-                perform_reads(&mut BufReader::new(p), &raw_data_tx, Instant::now())
+    let port = match source.open_within(options.connect_timeout) {
+        Ok(port) => port,
+        Err(e) => {
+            shared.set_state(ConnectionState::Error(e));
+            return;
+        }
+    };
+    shared.set_state(ConnectionState::Connected(port.describe()));
+    let mut port = BufReader::new(port);
+    let mut pending = Vec::new();
+    let mut arrivals = Arrivals::default();
+    let mut merge = PacketMerge::new(options.merge_window, options.line_limit.max_len);
+    let mut polled_at: Option<Instant> = None;
+    let mut backoff = IdleBackoff::new(options.max_idle_sleep);
+    while !shared.stop.load(Ordering::Relaxed) {
+        if polled_at.is_none_or(|at| at.elapsed() >= WAITING_POLL_INTERVAL) {
+            polled_at = Some(Instant::now());
+            if let Ok(mut waiting) = shared.bytes_waiting.write() {
+                *waiting = port.get_ref().bytes_waiting();
+            }
+        }
+        let read = match options.framing {
+            Framing::Fixed { length, sync } => perform_frame_reads(
+                &mut port,
+                &raw_data_tx,
+                t_zero,
+                (length, sync),
+                &mut pending,
+                &shared,
+            ),
+            Framing::ModbusRtu => {
+                perform_modbus_reads(&mut port, &raw_data_tx, t_zero, &mut pending, &shared)
             }
+            _ if options.timestamping == Timestamping::PerByte => perform_timed_reads(
+                &mut port,
+                &raw_data_tx,
+                t_zero,
+                &options,
+                &mut arrivals,
+                &mut merge,
+                &shared,
+            ),
+            _ => perform_reads(
+                &mut port,
+                &raw_data_tx,
+                t_zero,
+                &options,
+                &mut pending,
+                &mut merge,
+                &shared,
+            ),
+        };
+        let read = read.and_then(|read| {
+            merge
+                .flush_due(&raw_data_tx, &shared.dropped)
+                .map(|()| read)
+        });
+        let read = match read {
+            Ok(read) => read,
             Err(e) => {
-                eprintln!("ERROR: couldn't connect to port {device} because {e}");
-                continue;
+                // Nobody to read for or nothing left to read, either way the thread is done
+                report(format!("Stopping the serial thread, because: {}", e));
+                break;
             }
         };
+        for command in command_rx.try_iter() {
+            match command {
+                PortCommand::Write(bytes) => {
+                    if let Err(e) = port.get_mut().write_all(&bytes) {
+                        report(Error::Write(e).to_string());
+                    }
+                }
+                PortCommand::Break(duration) => {
+                    send_break(port.get_ref().as_ref(), duration, &shared)
+                }
+            }
+        }
+        flush_report(false);
+        backoff.after_read(read);
+    }
+    merge.flush(&raw_data_tx, &shared.dropped);
+    flush_report(true);
+    shared.set_state(ConnectionState::Closed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    /// Hands out the chunks queued on it one read at a time, and times out
+    /// while there are none, like a port between bursts
+    struct Chunks(Arc<Mutex<VecDeque<Vec<u8>>>>);
+
+    impl Read for Chunks {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let mut chunks = self.0.lock().unwrap();
+            let Some(mut chunk) = chunks.pop_front() else {
+                return Err(io::ErrorKind::TimedOut.into());
+            };
+            let len = chunk.len().min(buf.len());
+            buf[..len].copy_from_slice(&chunk[..len]);
+            if len < chunk.len() {
+                chunks.push_front(chunk.split_off(len));
+            }
+            Ok(len)
+        }
+    }
+
+    impl Write for Chunks {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl DataSource for Chunks {
+        fn describe(&self) -> String {
+            "Chunks".into()
+        }
+
+        fn set_break(&self) -> serialport::Result<()> {
+            Ok(())
+        }
+
+        fn clear_break(&self) -> serialport::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Payloads that came out after each of `chunks` arrived and was read
+    fn read_in_chunks(framing: &Framing, chunks: &[&[u8]], timed: bool) -> Vec<Vec<String>> {
+        let queued = Arc::new(Mutex::new(VecDeque::new()));
+        let source: Box<dyn DataSource> = Box::new(Chunks(queued.clone()));
+        let mut port = BufReader::new(source);
+        let (tx, rx) = mpsc::sync_channel(16);
+        let t_zero = Instant::now();
+        let options = ReadOptions {
+            framing: framing.clone(),
+            ..ReadOptions::default()
+        };
+        let mut merge = PacketMerge::default();
+        let shared = SerialShared::default();
+        let mut pending = Vec::new();
+        let mut arrivals = Arrivals::default();
+        chunks
+            .iter()
+            .map(|chunk| {
+                queued.lock().unwrap().push_back(chunk.to_vec());
+                while !queued.lock().unwrap().is_empty() || !port.buffer().is_empty() {
+                    let read = match timed {
+                        true => perform_timed_reads(
+                            &mut port,
+                            &tx,
+                            t_zero,
+                            &options,
+                            &mut arrivals,
+                            &mut merge,
+                            &shared,
+                        ),
+                        false => perform_reads(
+                            &mut port,
+                            &tx,
+                            t_zero,
+                            &options,
+                            &mut pending,
+                            &mut merge,
+                            &shared,
+                        ),
+                    };
+                    read.unwrap();
+                }
+                rx.try_iter().map(|packet| packet.payload).collect()
+            })
+            .collect()
+    }
+
+    /// Every way of cutting `stream` in two, and one byte per read
+    fn chunkings(stream: &[u8]) -> Vec<Vec<&[u8]>> {
+        let mut chunkings: Vec<Vec<&[u8]>> = (1..stream.len())
+            .map(|split| vec![&stream[..split], &stream[split..]])
+            .collect();
+        chunkings.push(stream.chunks(1).collect());
+        chunkings
+    }
+
+    fn assert_one_packet_at_the_end(framing: &Framing, stream: &[u8], payload: &str) {
+        for timed in [false, true] {
+            for chunks in chunkings(stream) {
+                let out = read_in_chunks(framing, &chunks, timed);
+                let (last, early) = out.split_last().unwrap();
+                assert!(
+                    early.iter().all(Vec::is_empty),
+                    "{framing:?} timed {timed} {chunks:?} emitted early: {out:?}"
+                );
+                assert_eq!(last, &[payload], "{framing:?} timed {timed} {chunks:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn two_byte_terminators_split_between_reads_end_one_packet() {
+        assert_one_packet_at_the_end(&Framing::Custom("\r\n".into()), b"a\rb\r\n", "a\rb");
+        assert_one_packet_at_the_end(&Framing::Custom("#\n".into()), b"x\ny#\n", "x\ny");
+        assert_one_packet_at_the_end(&Framing::Crlf, b"1,2\r\n", "1,2");
+    }
+
+    #[test]
+    fn three_byte_terminators_split_between_reads_end_one_packet() {
+        let framing = Framing::Custom("<E>".into());
+        assert_one_packet_at_the_end(&framing, b"a>b<E>", "a>b");
+        assert_one_packet_at_the_end(&framing, b"<<E<E>", "<<E");
+        assert_one_packet_at_the_end(&Framing::Custom("\r\n\0".into()), b"ok\r\n\r\n\0", "ok\r\n");
+    }
+
+    #[test]
+    fn repeated_reports_are_summed_up_once_the_window_passes() {
+        let start = Instant::now();
+        let later = |ms| start + Duration::from_millis(ms);
+        let mut reported = None;
+        assert_eq!(
+            fold_report(&mut reported, "timeout".into(), start),
+            ["timeout"]
+        );
+        assert!(fold_report(&mut reported, "timeout".into(), later(100)).is_empty());
+        assert!(fold_report(&mut reported, "timeout".into(), later(200)).is_empty());
+        assert_eq!(due_report(&mut reported, later(500), false), None);
+        assert_eq!(
+            due_report(&mut reported, later(1000), false).as_deref(),
+            Some("timeout (×2)")
+        );
+        // Nothing more is owed until it repeats again, which is printed right away
+        assert_eq!(due_report(&mut reported, later(3000), false), None);
+        assert_eq!(
+            fold_report(&mut reported, "timeout".into(), later(3100)),
+            ["timeout (×1)"]
+        );
+        assert!(fold_report(&mut reported, "timeout".into(), later(3150)).is_empty());
+        assert_eq!(
+            fold_report(&mut reported, "gone".into(), later(3200)),
+            ["timeout (×1)", "gone"]
+        );
+    }
+
+    #[test]
+    fn repeats_are_summed_up_when_the_thread_stops() {
+        let start = Instant::now();
+        let mut reported = None;
+        fold_report(&mut reported, "timeout".into(), start);
+        fold_report(&mut reported, "timeout".into(), start);
+        assert_eq!(due_report(&mut reported, start, false), None);
+        assert_eq!(
+            due_report(&mut reported, start, true).as_deref(),
+            Some("timeout (×1)")
+        );
+        assert_eq!(due_report(&mut reported, start, true), None);
+    }
+
+    #[test]
+    fn the_thread_stops_once_nobody_receives() {
+        let demo = DemoSettings {
+            sample_rate: 1000,
+            ..DemoSettings::default()
+        };
+        for timestamping in [Timestamping::PerLine, Timestamping::PerByte] {
+            let (tx, rx) = mpsc::sync_channel(4);
+            let (_command_tx, command_rx) = mpsc::channel();
+            let (done_tx, done_rx) = mpsc::channel();
+            let shared = SerialShared::default();
+            let thread_shared = shared.clone();
+            let source = Source::Demo(demo.clone());
+            std::thread::spawn(move || {
+                serial_thread(
+                    tx,
+                    command_rx,
+                    source,
+                    ReadOptions {
+                        connect_timeout: Duration::from_secs(1),
+                        framing: Framing::Crlf,
+                        timestamping,
+                        ..ReadOptions::default()
+                    },
+                    Instant::now(),
+                    thread_shared,
+                );
+                let _ = done_tx.send(());
+            });
+            rx.recv_timeout(Duration::from_secs(2))
+                .expect("the demo source sends lines");
+            drop(rx);
+            done_rx
+                .recv_timeout(Duration::from_secs(2))
+                .unwrap_or_else(|_| panic!("{timestamping:?} thread still running"));
+            assert!(matches!(
+                *shared.state.read().unwrap(),
+                ConnectionState::Closed
+            ));
+        }
+    }
+
+    #[test]
+    fn tcp_sources_try_each_address_in_turn() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let refused = {
+            let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            closed.local_addr().unwrap()
+        };
+        let open = listener.local_addr().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let stream = connect_any([refused, open], deadline).unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), open);
+        assert!(connect_any([refused], deadline).is_err());
+        assert!(connect_any([], deadline).is_err());
+    }
+
+    #[test]
+    fn held_ports_are_busy_on_every_platform() {
+        let error = |kind, description| serialport::Error::new(kind, description);
+        let busy = error(serialport::ErrorKind::Unknown, "Device or resource busy");
+        assert!(looks_busy(&busy, false, || false));
+        // Windows in another language, with the port still listed
+        let denied = error(serialport::ErrorKind::NoDevice, "Zugriff verweigert");
+        assert!(looks_busy(&denied, true, || true));
+        assert!(!looks_busy(&denied, true, || false));
+        assert!(!looks_busy(&denied, false, || true));
+        let english = error(serialport::ErrorKind::NoDevice, "Access is denied.");
+        assert!(looks_busy(&english, true, || false));
+        let missing = error(serialport::ErrorKind::NoDevice, "No such file or directory");
+        assert!(!looks_busy(&missing, false, || false));
+    }
+
+    #[test]
+    fn port_locks_go_with_their_holder() {
+        let path = format!("/dev/symphony-lock-test-{}", std::process::id());
+        let lock = PortLock::acquire(&path).unwrap();
+        let file = PortLock::file(&path);
+        // Held, though not by another process
+        let other = File::open(&file).unwrap();
+        assert!(matches!(other.try_lock(), Err(TryLockError::WouldBlock)));
+        drop(other);
+        assert_eq!(PortLock::holder(&path), None);
+        drop(lock);
+        assert!(!file.exists());
+
+        // A file a crashed process left behind holds nothing
+        std::fs::write(&file, "4000000").unwrap();
+        assert_eq!(PortLock::holder(&path), None);
+        let lock = PortLock::acquire(&path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            std::process::id().to_string()
+        );
+        drop(lock);
     }
 }
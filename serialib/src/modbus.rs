@@ -0,0 +1,207 @@
+//! Modbus RTU, read from the replies of a device on the other end of the line.
+//!
+//! RTU frames have no terminator, their length follows from the function code
+//! and, for reads, the byte count after it. Every frame ends in a CRC-16 sent
+//! low byte first.
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// Shortest frame there is: address, function, exception code and CRC
+const MIN_FRAME_LEN: usize = 5;
+
+/// CRC-16/MODBUS: reflected polynomial 0xA001, initial value 0xFFFF
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0xFFFF;
+    for byte in data {
+        crc ^= *byte as u16;
+        for _ in 0..8 {
+            if crc & 1 != 0 {
+                crc = (crc >> 1) ^ 0xA001;
+            } else {
+                crc >>= 1;
+            }
+        }
+    }
+    crc
+}
+
+/// How long the reply starting at the front of a buffer is
+enum Length {
+    /// This many bytes, CRC included
+    Known(usize),
+    /// Not enough bytes have arrived to tell
+    NeedMore,
+    /// The function code isn't one a reply can have
+    Unknown,
+}
+
+/// Length of the reply at the front of `buf`
+fn reply_len(buf: &[u8]) -> Length {
+    let Some(function) = buf.get(1) else {
+        return Length::NeedMore;
+    };
+    match function {
+        0x01..=0x04 => match buf.get(2) {
+            Some(count) => Length::Known(3 + *count as usize + 2),
+            None => Length::NeedMore,
+        },
+        0x05 | 0x06 | 0x0F | 0x10 => Length::Known(8),
+        function if function & 0x80 != 0 => Length::Known(MIN_FRAME_LEN),
+        _ => Length::Unknown,
+    }
+}
+
+/// Whether the last two bytes of `frame` are the CRC of the rest
+fn crc_matches(frame: &[u8]) -> bool {
+    let (data, crc) = frame.split_at(frame.len() - 2);
+    crc16(data) == u16::from_le_bytes([crc[0], crc[1]])
+}
+
+/// Cut every complete reply with a valid CRC off the front of `pending`.
+///
+/// When the front doesn't hold a valid frame, bytes are skipped one at a time
+/// until one starts again. Returns the frames and how many bad frames were
+/// skipped, a run of skipped bytes counting as one.
+pub(crate) fn take_frames(pending: &mut Vec<u8>) -> (Vec<Vec<u8>>, usize) {
+    let mut frames = Vec::new();
+    let mut bad = 0;
+    let mut in_sync = true;
+    while pending.len() >= 2 {
+        let frame_len = match reply_len(pending) {
+            Length::NeedMore => break,
+            Length::Known(len) if pending.len() < len => break,
+            Length::Known(len) => Some(len),
+            Length::Unknown => None,
+        };
+        match frame_len {
+            Some(len) if crc_matches(&pending[..len]) => {
+                frames.push(pending.drain(..len).collect());
+                in_sync = true;
+            }
+            _ => {
+                bad += usize::from(in_sync);
+                in_sync = false;
+                pending.remove(0);
+            }
+        }
+    }
+    (frames, bad)
+}
+
+/// Function codes the request builder knows
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Function {
+    ReadCoils,
+    ReadDiscreteInputs,
+    #[default]
+    ReadHoldingRegisters,
+    ReadInputRegisters,
+    WriteSingleCoil,
+    WriteSingleRegister,
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Function::ReadCoils => write!(f, "01 Read Coils"),
+            Function::ReadDiscreteInputs => write!(f, "02 Read Discrete Inputs"),
+            Function::ReadHoldingRegisters => write!(f, "03 Read Holding Registers"),
+            Function::ReadInputRegisters => write!(f, "04 Read Input Registers"),
+            Function::WriteSingleCoil => write!(f, "05 Write Single Coil"),
+            Function::WriteSingleRegister => write!(f, "06 Write Single Register"),
+        }
+    }
+}
+
+impl Function {
+    pub const ALL: [Function; 6] = [
+        Function::ReadCoils,
+        Function::ReadDiscreteInputs,
+        Function::ReadHoldingRegisters,
+        Function::ReadInputRegisters,
+        Function::WriteSingleCoil,
+        Function::WriteSingleRegister,
+    ];
+
+    pub fn code(&self) -> u8 {
+        match self {
+            Function::ReadCoils => 0x01,
+            Function::ReadDiscreteInputs => 0x02,
+            Function::ReadHoldingRegisters => 0x03,
+            Function::ReadInputRegisters => 0x04,
+            Function::WriteSingleCoil => 0x05,
+            Function::WriteSingleRegister => 0x06,
+        }
+    }
+}
+
+/// A request for a device, as its fields
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Request {
+    /// Device address, 1 to 247
+    pub address: u8,
+    pub function: Function,
+    /// First coil or register
+    pub start: u16,
+    /// How many to read, or the value to write
+    pub value: u16,
+}
+
+impl Default for Request {
+    fn default() -> Self {
+        Self {
+            address: 1,
+            function: Function::default(),
+            start: 0,
+            value: 1,
+        }
+    }
+}
+
+impl Request {
+    /// Bytes of the request, CRC included
+    pub fn encode(&self) -> Vec<u8> {
+        let value = match self.function {
+            // A coil is switched on by 0xFF00, anything else switches it off
+            Function::WriteSingleCoil if self.value != 0 => 0xFF00,
+            _ => self.value,
+        };
+        let mut frame = vec![self.address, self.function.code()];
+        frame.extend_from_slice(&self.start.to_be_bytes());
+        frame.extend_from_slice(&value.to_be_bytes());
+        let crc = crc16(&frame);
+        frame.extend_from_slice(&crc.to_le_bytes());
+        frame
+    }
+}
+
+/// Readings carried by the reply `frame`, one per coil or register.
+///
+/// Coils and inputs come out as 0 or 1, including the bits that pad out the
+/// last byte. Registers are unsigned. Replies to writes carry no readings,
+/// an exception reply is an error.
+pub fn decode(frame: &[u8]) -> Result<Vec<f64>, String> {
+    let (Some(function), Some(data)) = (frame.get(1), frame.get(3..frame.len().saturating_sub(2)))
+    else {
+        return Err(String::from("the frame is too short"));
+    };
+    match function {
+        0x01 | 0x02 => Ok(data
+            .iter()
+            .flat_map(|byte| (0..8).map(move |bit| f64::from(byte >> bit & 1)))
+            .collect()),
+        0x03 | 0x04 => Ok(data
+            .chunks_exact(2)
+            .map(|word| f64::from(u16::from_be_bytes([word[0], word[1]])))
+            .collect()),
+        function if function & 0x80 != 0 => Err(format!(
+            "the device answered function {:02X} with exception {:02X}",
+            function & 0x7F,
+            frame[2]
+        )),
+        _ => Ok(Vec::new()),
+    }
+}
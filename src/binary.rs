@@ -0,0 +1,129 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FieldType {
+    U8,
+    I8,
+    U16,
+    #[default]
+    I16,
+    U32,
+    I32,
+    F32,
+}
+
+impl Display for FieldType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldType::U8 => write!(f, "uint8"),
+            FieldType::I8 => write!(f, "int8"),
+            FieldType::U16 => write!(f, "uint16"),
+            FieldType::I16 => write!(f, "int16"),
+            FieldType::U32 => write!(f, "uint32"),
+            FieldType::I32 => write!(f, "int32"),
+            FieldType::F32 => write!(f, "float32"),
+        }
+    }
+}
+
+impl FieldType {
+    pub const ALL: [FieldType; 7] = [
+        FieldType::U8,
+        FieldType::I8,
+        FieldType::U16,
+        FieldType::I16,
+        FieldType::U32,
+        FieldType::I32,
+        FieldType::F32,
+    ];
+
+    /// Bytes the field takes up in a frame
+    pub fn size(&self) -> usize {
+        match self {
+            FieldType::U8 | FieldType::I8 => 1,
+            FieldType::U16 | FieldType::I16 => 2,
+            FieldType::U32 | FieldType::I32 | FieldType::F32 => 4,
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        *self != FieldType::F32
+    }
+
+    /// Value of the field stored in `bytes`, which must be [`FieldType::size`] long
+    fn decode(&self, bytes: &[u8], endianness: Endianness) -> f64 {
+        let mut word = [0; 4];
+        word[..bytes.len()].copy_from_slice(bytes);
+        if endianness == Endianness::Big {
+            word[..bytes.len()].reverse();
+        }
+        match self {
+            FieldType::U8 => word[0] as f64,
+            FieldType::I8 => word[0] as i8 as f64,
+            FieldType::U16 => u16::from_le_bytes([word[0], word[1]]) as f64,
+            FieldType::I16 => i16::from_le_bytes([word[0], word[1]]) as f64,
+            FieldType::U32 => u32::from_le_bytes(word) as f64,
+            FieldType::I32 => i32::from_le_bytes(word) as f64,
+            FieldType::F32 => f32::from_le_bytes(word) as f64,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Endianness {
+    #[default]
+    /// Least significant byte first
+    Little,
+    /// Most significant byte first
+    Big,
+}
+
+impl Display for Endianness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Endianness::Little => write!(f, "Little endian"),
+            Endianness::Big => write!(f, "Big endian"),
+        }
+    }
+}
+
+/// How the bytes of a fixed-width frame map to channel values
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BinaryLayout {
+    /// One field per channel, in the order they appear in the frame
+    pub fields: Vec<FieldType>,
+    pub endianness: Endianness,
+}
+
+impl Default for BinaryLayout {
+    fn default() -> Self {
+        Self {
+            fields: vec![FieldType::I16; 3],
+            endianness: Endianness::Little,
+        }
+    }
+}
+
+impl BinaryLayout {
+    /// Bytes needed for every field
+    pub fn size(&self) -> usize {
+        self.fields.iter().map(FieldType::size).sum()
+    }
+
+    /// Value of every field that fits in `frame`
+    pub fn decode(&self, frame: &[u8]) -> Vec<f64> {
+        let mut offset = 0;
+        let mut values = Vec::with_capacity(self.fields.len());
+        for field in self.fields.iter() {
+            let Some(bytes) = frame.get(offset..offset + field.size()) else {
+                break;
+            };
+            values.push(field.decode(bytes, self.endianness));
+            offset += field.size();
+        }
+        values
+    }
+}
@@ -0,0 +1,137 @@
+//! Compact binary capture files, for long sessions that would make huge CSVs.
+//!
+//! Everything is little endian:
+//!
+//! | Bytes       | Field                                                   |
+//! |-------------|---------------------------------------------------------|
+//! | 4           | magic, `SYMC`                                           |
+//! | 2           | format version, [`VERSION`]                             |
+//! | 8           | `u64` Unix time in milliseconds of time 0               |
+//! | 4           | `u32` channel count                                     |
+//!
+//! followed by every channel in turn:
+//!
+//! | Bytes       | Field                                                   |
+//! |-------------|---------------------------------------------------------|
+//! | 4           | `u32` channel index                                     |
+//! | 2           | `u16` label length in bytes                             |
+//! | label       | UTF-8 label                                             |
+//! | 8           | `u64` sample count                                      |
+//! | 16 × count  | `f64` time in seconds since time 0, then `f64` value    |
+//!
+//! A gap in a channel is stored as a NaN value.
+
+use std::io::{self, Write};
+
+/// First bytes of every capture file
+pub const MAGIC: [u8; 4] = *b"SYMC";
+
+/// Version written into new capture files
+pub const VERSION: u16 = 1;
+
+/// File extension for capture files
+pub const EXTENSION: &str = "symc";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capture {
+    /// Unix time in milliseconds the sample times are relative to
+    pub origin_millis: u64,
+    pub channels: Vec<CaptureChannel>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureChannel {
+    pub index: u32,
+    pub label: String,
+    /// Time and value of every sample
+    pub points: Vec<[f64; 2]>,
+}
+
+/// Whether `bytes` look like the start of a capture file
+pub fn is_capture(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+impl Capture {
+    pub fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        out.write_all(&MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        out.write_all(&self.origin_millis.to_le_bytes())?;
+        out.write_all(&(self.channels.len() as u32).to_le_bytes())?;
+        for channel in self.channels.iter() {
+            let label = channel.label.as_bytes();
+            let label = &label[..label.len().min(u16::MAX as usize)];
+            out.write_all(&channel.index.to_le_bytes())?;
+            out.write_all(&(label.len() as u16).to_le_bytes())?;
+            out.write_all(label)?;
+            out.write_all(&(channel.points.len() as u64).to_le_bytes())?;
+            for [x, y] in channel.points.iter() {
+                out.write_all(&x.to_le_bytes())?;
+                out.write_all(&y.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Capture stored in `bytes`, as written by [`Capture::write`]
+    pub fn read(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader { bytes };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(String::from("it isn't a capture file"));
+        }
+        let version = u16::from_le_bytes(reader.array()?);
+        if version != VERSION {
+            return Err(format!("capture version {} isn't supported", version));
+        }
+        let origin_millis = u64::from_le_bytes(reader.array()?);
+        let count = u32::from_le_bytes(reader.array()?);
+        let mut channels = Vec::new();
+        for _ in 0..count {
+            let index = u32::from_le_bytes(reader.array()?);
+            let len = u16::from_le_bytes(reader.array()?) as usize;
+            let label = String::from_utf8(reader.take(len)?.to_vec())
+                .map_err(|_| format!("the label of channel {} isn't UTF-8", index))?;
+            let samples = u64::from_le_bytes(reader.array()?) as usize;
+            if samples > reader.bytes.len() / 16 {
+                return Err(format!("channel {} is cut short", index));
+            }
+            let points = (0..samples)
+                .map(|_| {
+                    Ok([
+                        f64::from_le_bytes(reader.array()?),
+                        f64::from_le_bytes(reader.array()?),
+                    ])
+                })
+                .collect::<Result<_, String>>()?;
+            channels.push(CaptureChannel {
+                index,
+                label,
+                points,
+            });
+        }
+        Ok(Self {
+            origin_millis,
+            channels,
+        })
+    }
+}
+
+/// Reads fields off the front of a byte slice
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() < len {
+            return Err(String::from("the file ends too early"));
+        }
+        let (field, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(field)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take(N)?.try_into().expect("took exactly N bytes"))
+    }
+}
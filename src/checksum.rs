@@ -0,0 +1,87 @@
+use std::{borrow::Cow, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Scheme {
+    #[default]
+    /// Don't check anything
+    None,
+    /// XOR of every byte
+    Xor,
+    /// Sum of every byte, modulo 256
+    Sum,
+    /// CRC-16 with the Modbus polynomial and initial value
+    Crc16,
+}
+
+impl Display for Scheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scheme::None => write!(f, "None"),
+            Scheme::Xor => write!(f, "XOR"),
+            Scheme::Sum => write!(f, "Sum"),
+            Scheme::Crc16 => write!(f, "CRC16"),
+        }
+    }
+}
+
+impl Scheme {
+    /// Checksum of `data`, `None` when nothing should be checked
+    pub fn compute(&self, data: &[u8]) -> Option<u16> {
+        match self {
+            Scheme::None => None,
+            Scheme::Xor => Some(data.iter().fold(0u8, |acc, b| acc ^ b) as u16),
+            Scheme::Sum => Some(data.iter().fold(0u8, |acc, b| acc.wrapping_add(*b)) as u16),
+            Scheme::Crc16 => Some(crc16(data)),
+        }
+    }
+}
+
+/// CRC-16/MODBUS: reflected polynomial 0xA001, initial value 0xFFFF
+pub fn crc16(data: &[u8]) -> u16 {
+    serialib::modbus::crc16(data)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChecksumOptions {
+    pub scheme: Scheme,
+    /// Which field holds the checksum, counted from the end so 1 is the last
+    pub field_from_end: usize,
+}
+
+impl Default for ChecksumOptions {
+    fn default() -> Self {
+        Self {
+            scheme: Scheme::None,
+            field_from_end: 1,
+        }
+    }
+}
+
+impl ChecksumOptions {
+    /// Check the hex checksum field of `payload` against the fields before it.
+    ///
+    /// Returns the payload without the checksum field, or `None` if the
+    /// checksum is missing or wrong.
+    pub fn verify<'a>(&self, payload: &'a str, delimiter: &str) -> Option<Cow<'a, str>> {
+        if self.scheme == Scheme::None {
+            return Some(Cow::Borrowed(payload));
+        }
+        let mut fields: Vec<&str> = payload.split(delimiter).collect();
+        if self.field_from_end == 0 || fields.len() <= self.field_from_end {
+            return None;
+        }
+        let idx = fields.len() - self.field_from_end;
+        let expected = u16::from_str_radix(fields[idx].trim(), 16).ok()?;
+        let actual = self
+            .scheme
+            .compute(fields[..idx].join(delimiter).as_bytes())?;
+        if actual != expected {
+            return None;
+        }
+        fields.remove(idx);
+        Some(Cow::Owned(fields.join(delimiter)))
+    }
+}
@@ -0,0 +1,428 @@
+//! Commands sent to the device, and the files command lists are shared in.
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// What ends every line of a multi-line command
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineEnding {
+    None,
+    #[default]
+    /// `\n`
+    Lf,
+    /// `\r`
+    Cr,
+    /// `\r\n`
+    CrLf,
+}
+
+impl Display for LineEnding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LineEnding::None => write!(f, "None"),
+            LineEnding::Lf => write!(f, "LF"),
+            LineEnding::Cr => write!(f, "CR"),
+            LineEnding::CrLf => write!(f, "CRLF"),
+        }
+    }
+}
+
+/// Rewriting of the newlines in text commands on their way out, for devices
+/// that want another newline than the one typed or pasted
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NewlineTranslation {
+    #[default]
+    None,
+    /// A `\n` without a `\r` before it becomes `\r\n`
+    LfToCrLf,
+    /// A `\r` without a `\n` after it becomes `\r\n`
+    CrToCrLf,
+    /// Every `\r\n` and `\n` becomes `\r`
+    LfToCr,
+    /// Every `\r\n` and `\r` becomes `\n`
+    CrToLf,
+}
+
+impl Display for NewlineTranslation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NewlineTranslation::None => write!(f, "None"),
+            NewlineTranslation::LfToCrLf => write!(f, "LF → CRLF"),
+            NewlineTranslation::CrToCrLf => write!(f, "CR → CRLF"),
+            NewlineTranslation::LfToCr => write!(f, "LF → CR"),
+            NewlineTranslation::CrToLf => write!(f, "CR → LF"),
+        }
+    }
+}
+
+impl NewlineTranslation {
+    pub const ALL: [NewlineTranslation; 5] = [
+        NewlineTranslation::None,
+        NewlineTranslation::LfToCrLf,
+        NewlineTranslation::CrToCrLf,
+        NewlineTranslation::LfToCr,
+        NewlineTranslation::CrToLf,
+    ];
+
+    /// `bytes` with their newlines rewritten
+    pub fn apply(&self, bytes: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(bytes.len());
+        for (idx, byte) in bytes.iter().copied().enumerate() {
+            let prev = idx.checked_sub(1).map(|prev| bytes[prev]);
+            let next = bytes.get(idx + 1).copied();
+            match (self, byte) {
+                (NewlineTranslation::LfToCrLf, b'\n') if prev != Some(b'\r') => {
+                    out.extend_from_slice(b"\r\n")
+                }
+                (NewlineTranslation::CrToCrLf, b'\r') if next != Some(b'\n') => {
+                    out.extend_from_slice(b"\r\n")
+                }
+                (NewlineTranslation::LfToCr, b'\r') if next == Some(b'\n') => {}
+                (NewlineTranslation::LfToCr, b'\n') => out.push(b'\r'),
+                (NewlineTranslation::CrToLf, b'\r') if next == Some(b'\n') => {}
+                (NewlineTranslation::CrToLf, b'\r') => out.push(b'\n'),
+                _ => out.push(byte),
+            }
+        }
+        out
+    }
+}
+
+impl LineEnding {
+    pub fn bytes(&self) -> &'static [u8] {
+        match self {
+            LineEnding::None => b"",
+            LineEnding::Lf => b"\n",
+            LineEnding::Cr => b"\r",
+            LineEnding::CrLf => b"\r\n",
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Command {
+    pub cmd: String,
+    pub fmt: bool,
+    /// Ask before sending, for commands like erase or reset
+    #[serde(default)]
+    pub confirm: bool,
+    /// Send `cmd` one line at a time, each ended by `line_ending`
+    #[serde(default)]
+    pub multiline: bool,
+    #[serde(default)]
+    pub line_ending: LineEnding,
+    /// Pause between the lines of a multi-line command
+    #[serde(default)]
+    pub line_delay_ms: u64,
+    #[serde(default)]
+    pub skip_blank_lines: bool,
+    /// Edit a hex command as a grid of bytes instead of typing the hex
+    #[serde(default)]
+    pub byte_grid: bool,
+    /// Show the lines that arrive right after sending in the reply area
+    #[serde(default)]
+    pub capture_reply: bool,
+}
+
+impl Command {
+    /// Bytes to put on the wire, decoding the text as hex when `fmt` is set
+    pub fn to_bytes(&self) -> Result<Vec<u8>, serialib::Error> {
+        self.encode(&self.cmd)
+    }
+
+    fn encode(&self, text: &str) -> Result<Vec<u8>, serialib::Error> {
+        if self.fmt {
+            parse_hex_command(text).map_err(|reason| serialib::Error::Parse {
+                input: text.to_string(),
+                reason,
+            })
+        } else {
+            Ok(text.as_bytes().to_vec())
+        }
+    }
+
+    /// Bytes of every write the command takes, one per line when `multiline` is set
+    pub fn writes(&self) -> Result<Vec<Vec<u8>>, serialib::Error> {
+        if !self.multiline {
+            return Ok(vec![self.to_bytes()?]);
+        }
+        self.cmd
+            .lines()
+            .filter(|line| !(self.skip_blank_lines && line.trim().is_empty()))
+            .map(|line| {
+                // A blank line of hex is just its line ending
+                let mut bytes = match line.trim().is_empty() {
+                    true => Vec::new(),
+                    false => self.encode(line)?,
+                };
+                bytes.extend_from_slice(self.line_ending.bytes());
+                Ok(bytes)
+            })
+            .collect()
+    }
+
+    /// The first line only, for places with room for one
+    pub fn summary(&self) -> String {
+        let mut lines = self.cmd.lines();
+        let first = lines.next().unwrap_or_default();
+        match lines.next() {
+            Some(_) => format!("{}…", first),
+            None => first.to_string(),
+        }
+    }
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let format = if self.fmt {
+            String::from("HEX")
+        } else {
+            String::from("ASCII")
+        };
+        write!(f, "Command: '{}', Format: {}", self.cmd, format)
+    }
+}
+
+/// Parse hex bytes such as `1F 2A`, `0x1F 0x2A` or `1f2a`.
+///
+/// Each whitespace separated word may have a `0x` prefix and holds one or more
+/// bytes of two digits each. A lone digit is a byte of its own, any other odd
+/// number of digits is rejected since it's unclear which byte is short, and
+/// so is text without any digits.
+pub fn parse_hex_command(cmd: &str) -> Result<Vec<u8>, String> {
+    if cmd.trim().is_empty() {
+        return Err(String::from(
+            "there are no hex bytes, enter some like '1F 2A'",
+        ));
+    }
+    let mut bytes = Vec::new();
+    for word in cmd.split_whitespace() {
+        let digits = word
+            .strip_prefix("0x")
+            .or_else(|| word.strip_prefix("0X"))
+            .unwrap_or(word);
+        if let Some(bad) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+            return Err(format!(
+                "'{}' has '{}', which is not a hex digit",
+                word, bad
+            ));
+        }
+        match digits.len() {
+            0 => return Err(format!("'{}' has no digits after 0x", word)),
+            1 => bytes.push(u8::from_str_radix(digits, 16).expect("checked to be a hex digit")),
+            len if len % 2 == 1 => {
+                return Err(format!(
+                    "'{}' has an odd number of digits, split it into bytes like '0{}' or '{}0'",
+                    word, digits, digits
+                ))
+            }
+            _ => {
+                for pair in digits.as_bytes().chunks(2) {
+                    let pair = std::str::from_utf8(pair).expect("hex digits are ASCII");
+                    bytes.push(u8::from_str_radix(pair, 16).expect("checked to be hex digits"));
+                }
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+/// The command list saved in the JSON file at `path`.
+///
+/// A list with an empty command, or with the same command twice, is refused
+/// as a whole so the current list isn't replaced by one that half works.
+pub fn import(path: &str) -> Result<Vec<Command>, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => format!("there is no file at {}", path),
+        _ => e.to_string(),
+    })?;
+    let commands: Vec<Command> = serde_json::from_str(&contents)
+        .map_err(|e| format!("it isn't a list of commands ({})", e))?;
+    for (idx, command) in commands.iter().enumerate() {
+        if command.cmd.trim().is_empty() {
+            return Err(format!("command {} is empty", idx + 1));
+        }
+        if let Some(first) = commands[..idx]
+            .iter()
+            .position(|c| c.cmd == command.cmd && c.fmt == command.fmt)
+        {
+            return Err(format!(
+                "command {} repeats command {}, '{}'",
+                idx + 1,
+                first + 1,
+                command.cmd
+            ));
+        }
+    }
+    Ok(commands)
+}
+
+/// Save `commands` as JSON to `path`, for [`import`] to read back
+pub fn export(path: &str, commands: &[Command]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(commands).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepted_hex_forms() {
+        let cases: [(&str, &[u8]); 10] = [
+            ("1F 2A", &[0x1F, 0x2A]),
+            ("1F2A", &[0x1F, 0x2A]),
+            ("0x1F 0x2A", &[0x1F, 0x2A]),
+            ("0X1F 0x2a", &[0x1F, 0x2A]),
+            ("0x1F2A", &[0x1F, 0x2A]),
+            ("1f 2A", &[0x1F, 0x2A]),
+            ("aBcD", &[0xAB, 0xCD]),
+            ("  1F\t2A\n", &[0x1F, 0x2A]),
+            ("1F  2A   3B", &[0x1F, 0x2A, 0x3B]),
+            // A lone digit can only be one byte
+            ("A 0x5", &[0x0A, 0x05]),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                parse_hex_command(input).as_deref(),
+                Ok(expected),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejected_hex_forms() {
+        let cases = [
+            // Odd numbers of digits, which byte is short is unclear
+            ("1F2", "odd number of digits"),
+            ("0x1F2", "odd number of digits"),
+            ("1F 2A3", "odd number of digits"),
+            // Not hex
+            ("1G", "not a hex digit"),
+            ("0x1F,0x2A", "not a hex digit"),
+            ("hello", "not a hex digit"),
+            ("-1F", "not a hex digit"),
+            // Nothing to send
+            ("", "no hex bytes"),
+            ("   ", "no hex bytes"),
+            ("0x", "no digits after 0x"),
+        ];
+        for (input, reason) in cases {
+            match parse_hex_command(input) {
+                Ok(bytes) => panic!("{input:?} parsed as {bytes:?}"),
+                Err(e) => assert!(e.contains(reason), "{input:?} gave {e:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn blank_lines_of_a_multiline_hex_command_send_their_line_ending() {
+        let command = Command {
+            cmd: String::from("01 02\n\n03"),
+            fmt: true,
+            multiline: true,
+            line_ending: LineEnding::CrLf,
+            ..Default::default()
+        };
+        let writes = command.writes().unwrap();
+        assert_eq!(
+            writes,
+            [
+                vec![0x01, 0x02, b'\r', b'\n'],
+                vec![b'\r', b'\n'],
+                vec![0x03, b'\r', b'\n']
+            ]
+        );
+    }
+
+    #[test]
+    fn text_commands_send_their_utf8_bytes() {
+        let command = Command {
+            cmd: String::from("é€😀"),
+            fmt: false,
+            ..Default::default()
+        };
+        let writes = command.writes().unwrap();
+        assert_eq!(writes, ["é€😀".as_bytes()]);
+
+        let command = Command {
+            cmd: String::from("é€\n😀"),
+            multiline: true,
+            line_ending: LineEnding::Lf,
+            ..command
+        };
+        let writes = command.writes().unwrap();
+        assert_eq!(writes, ["é€\n".as_bytes(), "😀\n".as_bytes()]);
+        let translated = NewlineTranslation::LfToCrLf.apply(&writes[1]);
+        assert_eq!(translated, "😀\r\n".as_bytes());
+        for translation in NewlineTranslation::ALL {
+            for write in &writes {
+                let text = String::from_utf8(translation.apply(write)).unwrap();
+                let kept: String = text.chars().filter(|c| !matches!(c, '\r' | '\n')).collect();
+                assert!(
+                    ["é€", "😀"].contains(&kept.as_str()),
+                    "{translation}: {text:?}"
+                );
+            }
+        }
+    }
+
+    /// Import `contents` from a file of its own, named after `name`
+    fn import_json(name: &str, contents: &str) -> Result<Vec<Command>, String> {
+        let path =
+            std::env::temp_dir().join(format!("symphony-{}-{}.json", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        let imported = import(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        imported
+    }
+
+    fn text(cmd: &str) -> Command {
+        Command {
+            cmd: String::from(cmd),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn exported_commands_import_again() {
+        let commands = [text("AT"), text("AT+RST")];
+        let path =
+            std::env::temp_dir().join(format!("symphony-export-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        export(path, &commands).unwrap();
+        let imported = import(path);
+        std::fs::remove_file(path).unwrap();
+        let imported: Vec<String> = imported.unwrap().into_iter().map(|c| c.cmd).collect();
+        assert_eq!(imported, ["AT", "AT+RST"]);
+    }
+
+    #[test]
+    fn import_tells_a_missing_file_from_a_malformed_one() {
+        let missing = std::env::temp_dir().join("symphony-no-such-commands.json");
+        let missing = import(missing.to_str().unwrap()).unwrap_err();
+        assert!(missing.starts_with("there is no file at"), "{missing}");
+        let malformed = import_json("malformed", "{\"cmd\": ").unwrap_err();
+        assert!(
+            malformed.starts_with("it isn't a list of commands"),
+            "{malformed}"
+        );
+    }
+
+    #[test]
+    fn import_refuses_empty_and_repeated_commands() {
+        let json = |commands: &[Command]| serde_json::to_string(commands).unwrap();
+        let empty = import_json("empty", &json(&[text("AT"), text("  ")]));
+        assert_eq!(empty.unwrap_err(), "command 2 is empty");
+        let repeated = import_json("repeated", &json(&[text("AT"), text("ATZ"), text("AT")]));
+        assert_eq!(repeated.unwrap_err(), "command 3 repeats command 1, 'AT'");
+        let hex = Command {
+            fmt: true,
+            ..text("41 54")
+        };
+        let same_text_as_hex = import_json("hex", &json(&[text("41 54"), hex]));
+        assert_eq!(same_text_as_hex.map(|c| c.len()), Ok(2));
+    }
+}
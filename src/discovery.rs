@@ -0,0 +1,116 @@
+//! Finding the commands a device knows from its reply to a command like
+//! `help`, to suggest adding them to the command list.
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::{Command, LineEnding};
+
+/// A command that makes a device list the commands it knows, like `help`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Discovery {
+    pub command: String,
+    pub line_ending: LineEnding,
+    /// Send `command` as soon as a connection is made
+    pub on_connect: bool,
+}
+
+impl Default for Discovery {
+    fn default() -> Self {
+        Self {
+            command: String::from("help"),
+            line_ending: LineEnding::default(),
+            on_connect: false,
+        }
+    }
+}
+
+impl Discovery {
+    /// `command` as a text command that captures its reply
+    pub fn to_command(&self) -> Command {
+        Command {
+            cmd: self.command.clone(),
+            multiline: true,
+            line_ending: self.line_ending,
+            capture_reply: true,
+            ..Default::default()
+        }
+    }
+
+    /// A suggested `word` as a command sent like the discovery command
+    pub fn suggested_command(&self, word: String) -> Command {
+        Command {
+            cmd: word,
+            multiline: true,
+            line_ending: self.line_ending,
+            ..Default::default()
+        }
+    }
+
+    /// Commands the reply `lines` seem to list, the first word of each line,
+    /// leaving out those already in `known`.
+    ///
+    /// Headings ending in `:`, list bullets and the echo of `command` itself
+    /// are skipped, so is anything without a letter or digit in it.
+    pub fn suggestions(&self, lines: &[String], known: &[Command]) -> Vec<String> {
+        let mut found: Vec<String> = Vec::new();
+        for line in lines.iter() {
+            let line = line.trim();
+            if line.ends_with(':') {
+                continue;
+            }
+            let line = line.trim_start_matches(['-', '*', '•', '>', ' ', '\t']);
+            let Some(word) = line.split_whitespace().next() else {
+                continue;
+            };
+            let word = word.trim_end_matches([':', ',', ';', '-']);
+            if word == self.command.trim()
+                || !word.chars().any(char::is_alphanumeric)
+                || found.iter().any(|w| w == word)
+                || known.iter().any(|c| c.cmd.trim() == word)
+            {
+                continue;
+            }
+            found.push(String::from(word));
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn the_first_word_of_each_listed_line_is_suggested() {
+        let discovery = Discovery::default();
+        let reply = lines(
+            "help\n\
+             Available commands:\n\
+             \x20 - status   show the state\n\
+             * reset: restart the device\n\
+             > led, on or off\n\
+             ---\n\
+             \n\
+             status again",
+        );
+        assert_eq!(
+            discovery.suggestions(&reply, &[]),
+            ["status", "reset", "led"]
+        );
+    }
+
+    #[test]
+    fn commands_already_in_the_list_are_left_out() {
+        let discovery = Discovery::default();
+        let known = [discovery.suggested_command(String::from("reset"))];
+        let reply = lines("reset\nversion");
+        assert_eq!(discovery.suggestions(&reply, &known), ["version"]);
+        assert_eq!(known[0].line_ending, discovery.line_ending);
+        assert!(!known[0].capture_reply && discovery.to_command().capture_reply);
+    }
+}
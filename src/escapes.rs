@@ -0,0 +1,38 @@
+//! Escapes typed into text fields and command line arguments, for bytes
+//! that are awkward to type, like a tab delimiter or a `\r\n` terminator.
+
+/// Decode `\t`, `\n`, `\r`, `\0`, `\\` and `\xNN` escapes typed by the user
+pub fn decode_escapes(input: &str) -> Result<String, String> {
+    let mut decoded = String::new();
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            decoded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => decoded.push('\t'),
+            Some('n') => decoded.push('\n'),
+            Some('r') => decoded.push('\r'),
+            Some('0') => decoded.push('\0'),
+            Some('\\') => decoded.push('\\'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                let byte = u8::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == 2)
+                    .ok_or_else(|| format!("'\\x{}' needs two hex digits", hex))?;
+                if !byte.is_ascii() {
+                    return Err(format!("'\\x{}' is outside ASCII", hex));
+                }
+                decoded.push(byte as char);
+            }
+            Some(other) => return Err(format!("Unknown escape '\\{}'", other)),
+            None => return Err(String::from("Trailing '\\' needs an escape after it")),
+        }
+    }
+    if decoded.is_empty() {
+        return Err(String::from("Delimiter can't be empty"));
+    }
+    Ok(decoded)
+}
@@ -1,49 +1,318 @@
-use std::{collections::VecDeque, fmt::Display, time::Instant};
+use std::{
+    cell::Cell,
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fmt::Display,
+    fs::File,
+    io::{self, BufWriter, Write},
+    ops::RangeInclusive,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc, RwLock,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
 
 use eframe::egui::{self, Align, CentralPanel, Color32, ScrollArea, TextStyle, TopBottomPanel};
 use egui_plot::{PlotPoint, PlotPoints};
-use serialib::Device;
-use serialport::{FlowControl, Parity};
+use serde::{Deserialize, Serialize};
+use serialib::{
+    modbus::{self, Function},
+    ConnectionState, DemoSettings, Device, Framing, LineLimit, Overlong, Packet, PortCommand,
+    PortLock, ReadOptions, SerialDevices, SerialShared, Source, Timestamping, UsbIdentity,
+    Waveform,
+};
+use serialport::{DataBits, FlowControl, Parity, StopBits};
 use tracing::{info, instrument, span, trace, warn};
 
+use crate::{
+    binary::{BinaryLayout, Endianness, FieldType},
+    capture::{self, Capture, CaptureChannel},
+    checksum::{ChecksumOptions, Scheme},
+    commands::{self, parse_hex_command, Command, LineEnding, NewlineTranslation},
+    discovery::Discovery,
+    escapes::decode_escapes,
+    headless,
+    health::{BaudMismatch, Health, HealthCounts, HealthThresholds, HealthWindow, HEALTH_WINDOW},
+    keep_alive::KeepAlive,
+    log::Log,
+    macros::{Macro, MacroAction, MacroRun, MacroStep},
+    measurement::{GapMode, Measurement, Regression, LOOK_BEHIND, MIN_SAMPLES},
+    nmea::{self, NmeaOptions},
+    parse::{self, Parsed, ParsedPacket, Parser, Reading},
+    raw::{decode_stream, RawStream},
+    rules::{self, ReplyThrottle, Rule, RuleAction},
+    settings,
+    svg::{self, Figure, Marker, Series, Stroke},
+    timefmt,
+    transform::Transform,
+};
+
 const BAUD_RATES: [u32; 20] = [
     50, 75, 110, 134, 150, 200, 300, 600, 1200, 1800, 2400, 4800, 9600, 19200, 38400, 57600,
     115200, 230400, 460800, 500000,
 ];
 
-#[derive(Debug, Default)]
-struct Measurement {
-    values: VecDeque<PlotPoint>,
-    look_behind: usize,
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct HistogramOptions {
+    channel: usize,
+    bins: usize,
 }
 
-impl Measurement {
-    fn new_with_look_behind(look_behind: usize) -> Self {
+impl Default for HistogramOptions {
+    fn default() -> Self {
         Self {
-            values: VecDeque::new(),
-            look_behind,
+            channel: 0,
+            bins: 20,
         }
     }
+}
 
-    fn add(&mut self, measurement: PlotPoint) {
-        if let Some(last) = self.values.back() {
-            if measurement.x < last.x {
-                self.values.clear()
-            }
+/// Most rows the waterfall shows, older rows scroll off the bottom
+const WATERFALL_MAX_ROWS: usize = 512;
+
+/// Colors the waterfall maps values to, from the lowest value to the highest
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum ColorMap {
+    Grayscale,
+    /// Black through red and yellow to white
+    #[default]
+    Heat,
+    /// Blue through cyan, green and yellow to red
+    Rainbow,
+}
+
+impl Display for ColorMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorMap::Grayscale => write!(f, "Grayscale"),
+            ColorMap::Heat => write!(f, "Heat"),
+            ColorMap::Rainbow => write!(f, "Rainbow"),
         }
+    }
+}
 
-        self.values.push_back(measurement);
-        let limit = self.values.back().unwrap().x - (self.look_behind as f64);
-        while let Some(front) = self.values.front() {
-            if front.x >= limit {
-                break;
-            }
-            self.values.pop_front();
+impl ColorMap {
+    const ALL: [ColorMap; 3] = [ColorMap::Grayscale, ColorMap::Heat, ColorMap::Rainbow];
+
+    /// Color of `t`, where 0 is the lowest value and 1 the highest
+    fn color(&self, t: f64) -> Color32 {
+        let stops: &[[u8; 3]] = match self {
+            ColorMap::Grayscale => &[[0, 0, 0], [255, 255, 255]],
+            ColorMap::Heat => &[[0, 0, 0], [200, 0, 0], [255, 210, 0], [255, 255, 255]],
+            ColorMap::Rainbow => &[
+                [20, 40, 200],
+                [0, 200, 220],
+                [40, 200, 60],
+                [240, 220, 0],
+                [220, 30, 20],
+            ],
+        };
+        let scaled = t.clamp(0., 1.) * (stops.len() - 1) as f64;
+        let idx = (scaled as usize).min(stops.len() - 2);
+        let frac = scaled - idx as f64;
+        let [r, g, b] = [0, 1, 2].map(|c| {
+            let (from, to) = (stops[idx][c] as f64, stops[idx + 1][c] as f64);
+            (from + (to - from) * frac).round() as u8
+        });
+        Color32::from_rgb(r, g, b)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct WaterfallOptions {
+    channel: usize,
+    /// Successive samples drawn side by side in one row
+    row_len: usize,
+    color_map: ColorMap,
+}
+
+impl Default for WaterfallOptions {
+    fn default() -> Self {
+        Self {
+            channel: 0,
+            row_len: 64,
+            color_map: ColorMap::default(),
+        }
+    }
+}
+
+/// Texture the waterfall is drawn into, kept between frames
+struct WaterfallTexture(egui::TextureHandle);
+
+impl std::fmt::Debug for WaterfallTexture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("WaterfallTexture")
+            .field(&self.0.id())
+            .finish()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+enum PlotStyle {
+    #[default]
+    /// Connect samples with straight lines
+    Line,
+    /// Draw each sample as a discrete marker
+    Points,
+    /// Hold each sample until the next one arrives
+    Steps,
+}
+
+/// What kind of [`Source`] connecting opens
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+enum SourceKind {
+    #[default]
+    /// The selected serial port
+    Serial,
+    /// A serial port bridged over TCP
+    Tcp,
+    /// Generated data, no device needed
+    Demo,
+    /// A FIFO, character device or file to replay
+    File,
+}
+
+impl Display for SourceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SourceKind::Serial => write!(f, "Serial"),
+            SourceKind::Tcp => write!(f, "TCP"),
+            SourceKind::Demo => write!(f, "Demo"),
+            SourceKind::File => write!(f, "File"),
+        }
+    }
+}
+
+/// Color of channel `idx`, the same one `egui_plot` would pick for the `idx`th item
+fn channel_color(idx: usize) -> Color32 {
+    let golden_ratio = (5_f32.sqrt() - 1.) / 2.;
+    let hue = (idx as f32 * golden_ratio).fract();
+    egui::ecolor::Hsva::new(hue, 0.85, 0.5, 1.).into()
+}
+
+impl Display for PlotStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlotStyle::Line => write!(f, "Line"),
+            PlotStyle::Points => write!(f, "Points"),
+            PlotStyle::Steps => write!(f, "Steps"),
+        }
+    }
+}
+
+/// How the analog channels share the plot
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+enum PlotLayout {
+    #[default]
+    /// Every channel on one y axis
+    Overlaid,
+    /// Each channel in a lane of its own, with its own y axis, stacked on one time axis
+    Stacked,
+}
+
+impl Display for PlotLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlotLayout::Overlaid => write!(f, "Overlaid"),
+            PlotLayout::Stacked => write!(f, "Stacked"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+enum SignalKind {
+    #[default]
+    /// Treat as digital while the channel only holds 0/1 values
+    Auto,
+    /// Always draw on the analog plot
+    Analog,
+    /// Always draw as a logic lane
+    Digital,
+}
+
+impl Display for SignalKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignalKind::Auto => write!(f, "Auto"),
+            SignalKind::Analog => write!(f, "Analog"),
+            SignalKind::Digital => write!(f, "Digital"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+enum DisplayBase {
+    #[default]
+    Decimal,
+    Hexadecimal,
+}
+
+impl Display for DisplayBase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DisplayBase::Decimal => write!(f, "Dec"),
+            DisplayBase::Hexadecimal => write!(f, "Hex"),
+        }
+    }
+}
+
+/// Format a readout, in hex when asked for and the value is a whole number
+fn format_value(value: f64, hex: bool, decimals: usize) -> String {
+    if hex && value.is_finite() && value.fract() == 0. {
+        if value < 0. {
+            format!("-0x{:X}", -value as i64)
+        } else {
+            format!("0x{:X}", value as i64)
+        }
+    } else {
+        format!("{:.*}", decimals, value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct ChannelOptions {
+    kind: SignalKind,
+    /// Base for readouts of integer channels, the plot itself stays numeric
+    base: DisplayBase,
+    /// Multiplier applied to the raw value before plotting
+    gain: f64,
+    /// Added to the raw value after `gain`
+    offset: f64,
+    /// Whether autoscaling the y axis makes room for this channel
+    auto_range: bool,
+    /// Also plot the channel's rate of change
+    derivative: bool,
+    /// Draw the channel, hidden channels keep collecting samples
+    visible: bool,
+    /// Plot the channel on a log10 scale, hiding values that aren't positive
+    log_y: bool,
+}
+
+impl Default for ChannelOptions {
+    fn default() -> Self {
+        Self {
+            kind: SignalKind::default(),
+            base: DisplayBase::default(),
+            gain: 1.,
+            offset: 0.,
+            auto_range: true,
+            derivative: false,
+            visible: true,
+            log_y: false,
         }
     }
+}
 
-    fn plot_values(&self) -> PlotPoints {
-        PlotPoints::Owned(Vec::from_iter(self.values.iter().copied()))
+impl ChannelOptions {
+    /// Convert a raw reading into the channel's engineering units
+    fn scale(&self, raw: f64) -> f64 {
+        raw * self.gain + self.offset
     }
 }
 
@@ -67,7 +336,117 @@ impl Display for Delimiter {
     }
 }
 
-#[derive(Default, PartialEq, Eq, Debug)]
+impl Delimiter {
+    /// The text to split on, with escapes in a custom delimiter decoded
+    fn pattern(&self) -> Result<String, String> {
+        match self {
+            Delimiter::Space => Ok(String::from(" ")),
+            Delimiter::Comma => Ok(String::from(",")),
+            Delimiter::Tab => Ok(String::from("\t")),
+            Delimiter::Other(custom) => decode_escapes(custom),
+        }
+    }
+
+    /// The delimiter the way it's typed, with escapes left in
+    fn typed(&self) -> String {
+        match self {
+            Delimiter::Space => String::from(" "),
+            Delimiter::Comma => String::from(","),
+            Delimiter::Tab => String::from("\\t"),
+            Delimiter::Other(custom) => custom.clone(),
+        }
+    }
+}
+
+/// Parse a column selection like `2, 5` into the source column of each channel.
+///
+/// Columns count from 0 and are separated by commas or spaces, an empty
+/// selection plots every column.
+fn parse_columns(text: &str) -> Result<Vec<usize>, String> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            word.parse::<usize>()
+                .map_err(|_| format!("'{}' is not a column number", word))
+        })
+        .collect()
+}
+
+/// Show control characters in `text` as `\r`, `\n`, `\t`, `\0` or `\xNN` escapes
+fn encode_escapes(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\t' => encoded.push_str("\\t"),
+            '\n' => encoded.push_str("\\n"),
+            '\r' => encoded.push_str("\\r"),
+            '\0' => encoded.push_str("\\0"),
+            '\\' => encoded.push_str("\\\\"),
+            c if c.is_control() && (c as u32) <= 0xFF => {
+                encoded.push_str(&format!("\\x{:02X}", c as u32))
+            }
+            c => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+/// Parse a typed whole number, allowing scientific notation like `1e3`
+fn parse_exact(text: &str, range: &RangeInclusive<usize>) -> Result<f64, String> {
+    let value = text
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| format!("'{}' is not a number", text.trim()))?;
+    if value.fract() != 0. {
+        return Err(format!("{} is not a whole number", value));
+    }
+    if value < *range.start() as f64 || value > *range.end() as f64 {
+        return Err(format!(
+            "{} is outside {}..={}",
+            value,
+            range.start(),
+            range.end()
+        ));
+    }
+    Ok(value)
+}
+
+/// A DragValue that rejects typed values outside `range` instead of clamping them.
+///
+/// The reason for the last rejected value is kept in `errors` under `id` and
+/// shown next to the field until a valid value is entered.
+fn exact_drag_value(
+    ui: &mut egui::Ui,
+    value: &mut usize,
+    range: RangeInclusive<usize>,
+    id: &'static str,
+    errors: &mut HashMap<&'static str, String>,
+) -> egui::Response {
+    let rejected = Cell::new(None);
+    let response = ui.add(
+        egui::DragValue::new(value)
+            .range(range.clone())
+            .update_while_editing(false)
+            .custom_parser(|text| match parse_exact(text, &range) {
+                Ok(value) => Some(value),
+                Err(e) => {
+                    rejected.set(Some(e));
+                    None
+                }
+            }),
+    );
+    if let Some(e) = rejected.take() {
+        errors.insert(id, e);
+    } else if response.changed() {
+        errors.remove(id);
+    }
+    if let Some(e) = errors.get(id) {
+        ui.colored_label(Color32::DARK_RED, e);
+    }
+    response
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 enum Panel {
     #[default]
     /// Configure the Port
@@ -80,6 +459,12 @@ enum Panel {
     Record,
     /// Text view of Serial Monitor
     TextView,
+    /// Latest value of every channel
+    Values,
+    /// Distribution of one channel's values
+    Histogram,
+    /// One channel's samples as rows of colors scrolling over time
+    Waterfall,
     /// Show event logging of the App
     Log,
 }
@@ -102,6 +487,15 @@ impl Display for Panel {
             Panel::TextView => {
                 write!(f, "TextView")
             }
+            Panel::Values => {
+                write!(f, "Values")
+            }
+            Panel::Histogram => {
+                write!(f, "Histogram")
+            }
+            Panel::Waterfall => {
+                write!(f, "Waterfall")
+            }
             Panel::Port => {
                 write!(f, "Port")
             }
@@ -109,376 +503,7274 @@ impl Display for Panel {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 struct TextViewOptions {
     auto_scroll: bool,
     time_stamp: bool,
+    /// Render control characters as escapes instead of raw
+    show_escapes: bool,
+    /// Show bytes still waiting for their terminator under the last line
+    show_partial: bool,
+    /// Stamp lines with the wall-clock time instead of the capture time
+    wall_clock: bool,
+    /// Show the bytes as read instead of split into lines
+    raw_stream: bool,
+    /// Digits after the point of relative time stamps
+    time_decimals: usize,
+    /// KiB of the most recent bytes kept for the raw stream view
+    raw_kib: usize,
 }
 
+/// Most KiB the raw stream view keeps
+const MAX_RAW_KIB: usize = 4 * 1024;
+
 impl Default for TextViewOptions {
     fn default() -> Self {
         Self {
             auto_scroll: true,
             time_stamp: false,
+            show_escapes: false,
+            show_partial: true,
+            wall_clock: false,
+            raw_stream: false,
+            time_decimals: 3,
+            raw_kib: 64,
         }
     }
 }
 
-#[derive(Default, Debug, Clone)]
-struct Command {
-    cmd: String,
-    fmt: bool,
+impl TextViewOptions {
+    /// `self` with a raw stream size from a settings file kept to what the view allows
+    fn clamped(mut self) -> Self {
+        self.raw_kib = self.raw_kib.clamp(1, MAX_RAW_KIB);
+        self
+    }
 }
 
-impl Display for Command {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let format = if self.fmt {
-            String::from("HEX")
-        } else {
-            String::from("ASCII")
-        };
-        write!(f, "Command: '{}', Format: {}", self.cmd, format)
+fn health_color(health: Health) -> Color32 {
+    match health {
+        Health::Good => Color32::DARK_GREEN,
+        Health::Degraded => Color32::GOLD,
+        Health::Bad => Color32::DARK_RED,
     }
 }
 
-#[derive(Default, Debug, PartialEq)]
-struct PlotOptions {
-    delimiter: Delimiter,
-    buffer_size: usize,
-    plot_width: usize,
-    x_axis: [usize; 2],
-    y_axis: [usize; 2],
-    autoscale: bool,
+/// Packet and byte counts since connecting, plus their rates over the last second
+#[derive(Debug)]
+struct Throughput {
+    total_packets: usize,
+    total_bytes: usize,
+    window_start: Instant,
+    window_packets: usize,
+    window_bytes: usize,
+    packet_rate: f64,
+    byte_rate: f64,
 }
 
-#[allow(dead_code)]
-#[derive(Debug)]
-pub struct Symphony {
-    n_items: usize,
-    text_view_options: TextViewOptions,
-    plot_options: PlotOptions,
-    current_port: Device,
-    connected: bool,
-    // NOTE: Maybe use a VecDeque?
-    plot_data: Measurement,
-    raw_data: Vec<u8>,
-    open_panel: Panel,
-    commands: Vec<Command>,
-    log: Vec<String>,
-    absolute_time: Instant,
+impl Default for Throughput {
+    fn default() -> Self {
+        Self {
+            total_packets: 0,
+            total_bytes: 0,
+            window_start: Instant::now(),
+            window_packets: 0,
+            window_bytes: 0,
+            packet_rate: 0.,
+            byte_rate: 0.,
+        }
+    }
 }
 
-impl Symphony {
-    #[instrument]
-    pub fn new() -> Self {
+impl Throughput {
+    fn record(&mut self, bytes: usize) {
+        self.total_packets += 1;
+        self.total_bytes += bytes;
+        self.window_packets += 1;
+        self.window_bytes += bytes;
+    }
+
+    /// Turn the current window into rates once it is a second old
+    fn update_rates(&mut self) {
+        let elapsed = self.window_start.elapsed().as_secs_f64();
+        if elapsed < 1. {
+            return;
+        }
+        self.packet_rate = self.window_packets as f64 / elapsed;
+        self.byte_rate = self.window_bytes as f64 / elapsed;
+        self.window_start = Instant::now();
+        self.window_packets = 0;
+        self.window_bytes = 0;
+    }
+}
+
+/// How many tokens splitting lines on the delimiter gave, and how many were empty
+#[derive(Debug, Default, Clone, Copy)]
+struct TokenCounts {
+    /// Tokens and empty tokens of the last line
+    last: (usize, usize),
+    total: usize,
+    empty: usize,
+}
+
+impl TokenCounts {
+    fn record(&mut self, tokens: usize, empty: usize) {
+        self.last = (tokens, empty);
+        self.total += tokens;
+        self.empty += empty;
+    }
+
+    /// Share of all tokens that were empty, 0 before any line was split
+    fn empty_ratio(&self) -> f64 {
+        match self.total {
+            0 => 0.,
+            total => self.empty as f64 / total as f64,
+        }
+    }
+}
+
+/// Whether and from how many lines the channel count is detected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+struct DetectOptions {
+    enabled: bool,
+    lines: usize,
+}
+
+impl Default for DetectOptions {
+    fn default() -> Self {
         Self {
-            n_items: 0,
-            text_view_options: TextViewOptions::default(),
-            plot_options: PlotOptions::default(),
-            current_port: Device::default(),
-            connected: false,
-            plot_data: Measurement::new_with_look_behind(5),
-            raw_data: Vec::default(),
-            open_panel: Panel::default(),
-            commands: vec![Command::default(), Command::default()],
-            log: Vec::default(),
-            absolute_time: Instant::now(),
+            enabled: false,
+            lines: 20,
         }
     }
+}
 
-    fn draw_plot(&mut self, ui: &mut egui::Ui) {
-        let plot = egui_plot::Plot::new("measurements");
-        // for y in self.include_y.iter() {
-        //     plot = plot.include_y(*y);
-        // }
-        self.plot_data.add(
-            [
-                self.absolute_time.elapsed().as_millis() as f64 * 0.001,
-                (self.absolute_time.elapsed().as_millis() as f64 * 0.001).sin(),
-            ]
-            .into(),
-        );
+/// Channel count taken as the most common column count of recent lines
+#[derive(Debug, Default)]
+struct ChannelDetection {
+    /// Columns of the last lines that parsed, oldest first
+    counts: VecDeque<usize>,
+    /// Most common count, once enough lines were seen
+    detected: Option<usize>,
+    /// Keep `detected` once it is found, instead of following the latest lines
+    locked: bool,
+}
 
-        plot.show(ui, |plot_ui| {
-            plot_ui.line(egui_plot::Line::new(self.plot_data.plot_values()));
-        });
+impl ChannelDetection {
+    /// Note a line with `columns` columns out of the last `window`, returning
+    /// the detected count if it changed
+    fn record(&mut self, columns: usize, window: usize) -> Option<usize> {
+        if self.locked && self.detected.is_some() {
+            return None;
+        }
+        self.counts.push_back(columns);
+        while self.counts.len() > window.max(1) {
+            self.counts.pop_front();
+        }
+        if self.counts.len() < window.max(1) {
+            return None;
+        }
+        let mut tally: BTreeMap<usize, usize> = BTreeMap::new();
+        for count in self.counts.iter() {
+            *tally.entry(*count).or_default() += 1;
+        }
+        // A tie goes to the higher count, so no column that turns up is cut off
+        let (modal, _) = tally
+            .into_iter()
+            .max_by_key(|(count, lines)| (*lines, *count))?;
+        (self.detected != Some(modal)).then(|| {
+            self.detected = Some(modal);
+            modal
+        })
     }
 
-    fn draw_bottom_panel(&mut self, ui: &mut egui::Ui) {
-        ui.separator();
-        ui.horizontal(|ui| {
-            ui.selectable_value(&mut self.open_panel, Panel::Port, "Port");
-            ui.selectable_value(&mut self.open_panel, Panel::Log, "Log");
-            ui.selectable_value(&mut self.open_panel, Panel::Plot, "Plot");
-            ui.selectable_value(&mut self.open_panel, Panel::Commands, "Commands");
-            ui.selectable_value(&mut self.open_panel, Panel::Record, "Record");
-            ui.selectable_value(&mut self.open_panel, Panel::TextView, "TextView");
+    /// Forget what was seen, detecting again from the next lines
+    fn restart(&mut self) {
+        self.counts.clear();
+        self.detected = None;
+    }
+}
+
+/// How close to a cursor, in points, the pointer grabs it
+const CURSOR_GRAB: f32 = 6.;
+
+/// One of the two measurement cursors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CursorId {
+    A,
+    B,
+}
+
+/// Two vertical cursors on the plot, to measure the time and value between them
+#[derive(Debug, Default, Clone, Copy)]
+struct Cursors {
+    enabled: bool,
+    a: f64,
+    b: f64,
+    /// The cursor being dragged
+    dragging: Option<CursorId>,
+    /// The pointer was over a cursor last frame, so the plot doesn't pan
+    hovered: bool,
+}
+
+impl Cursors {
+    fn x(&self, id: CursorId) -> f64 {
+        match id {
+            CursorId::A => self.a,
+            CursorId::B => self.b,
+        }
+    }
+
+    /// Put the cursors a third of the way in from either side of `range`
+    fn place(&mut self, range: &RangeInclusive<f64>) {
+        let third = (range.end() - range.start()) / 3.;
+        self.a = range.start() + third;
+        self.b = range.end() - third;
+    }
+
+    /// Draw the cursors and move the one being dragged with the pointer
+    fn update(&mut self, plot_ui: &mut egui_plot::PlotUi) {
+        let response = plot_ui.response().clone();
+        let transform = *plot_ui.transform();
+        let near = response.hover_pos().and_then(|pos| {
+            [CursorId::A, CursorId::B].into_iter().find(|id| {
+                (transform.position_from_point_x(self.x(*id)) - pos.x).abs() <= CURSOR_GRAB
+            })
         });
-        ui.separator();
-        trace!("{}", self.open_panel);
-        match self.open_panel {
-            Panel::Port => {
-                self.show_port_settings(ui);
-            }
-            Panel::Plot => {
-                self.show_plot_settings(ui);
-            }
-            Panel::Commands => {
-                self.show_commands(ui);
-            }
-            Panel::Record => {
-                self.show_record_settings(ui);
-            }
-            Panel::TextView => {
-                self.show_text_view(ui);
-            }
-            Panel::Log => {
-                self.show_log(ui);
+        if response.drag_started() {
+            self.dragging = near;
+        }
+        if response.drag_stopped() {
+            self.dragging = None;
+        }
+        if let (Some(id), Some(pos)) = (self.dragging, response.interact_pointer_pos()) {
+            let x = transform.value_from_position(pos).x;
+            match id {
+                CursorId::A => self.a = x,
+                CursorId::B => self.b = x,
             }
         }
+        self.hovered = near.is_some() || self.dragging.is_some();
+        for (id, name) in [(CursorId::A, "Cursor A"), (CursorId::B, "Cursor B")] {
+            plot_ui.vline(
+                egui_plot::VLine::new(self.x(id))
+                    .color(Color32::LIGHT_BLUE)
+                    .width(1.5)
+                    .name(name),
+            );
+        }
     }
+}
 
-    fn show_port_settings(&mut self, ui: &mut egui::Ui) {
-        let ports = serialib::get_serial_devices().unwrap();
-        ui.horizontal_wrapped(|ui| {
-            egui::ComboBox::from_label("Select port")
-                .selected_text(self.current_port.path.clone())
-                .show_ui(ui, |ui| {
-                    ports.iter().for_each(|p| {
-                        ui.selectable_value(&mut self.current_port.path, p.clone(), p);
-                    });
-                });
-            egui::ComboBox::from_label("Set Baud Rate")
-                .selected_text(format!("{}", self.current_port.baud_rate))
-                .show_ui(ui, |ui| {
-                    BAUD_RATES.iter().for_each(|b| {
-                        ui.selectable_value(
-                            &mut self.current_port.baud_rate,
-                            *b as usize,
-                            format!("{} ", b),
-                        );
-                    })
-                });
-        });
-        ui.horizontal_wrapped(|ui| {
-            egui::ComboBox::from_label("Choose parity")
-                .selected_text(format!("{}", self.current_port.parity))
-                .show_ui(ui, |ui| {
+/// `time` in seconds with `decimals` digits after the point, truncated like a
+/// clock rather than rounded, so `3.005 s` never reads as `3.5`
+fn format_relative(time: Duration, decimals: usize) -> String {
+    let decimals = decimals.min(9);
+    if decimals == 0 {
+        return time.as_secs().to_string();
+    }
+    let fraction = time.subsec_nanos() / 10_u32.pow(9 - decimals as u32);
+    format!("{}.{:0width$}", time.as_secs(), fraction, width = decimals)
+}
+
+/// A received line, stamped relative to the time origin
+#[derive(Debug)]
+struct TextLine {
+    time: Duration,
+    /// When the packet arrived, in milliseconds since the Unix epoch
+    wall: u128,
+    text: String,
+}
+
+/// What the user can try to get past `error`, if anything
+fn recovery_hint(error: &serialib::Error) -> Option<&'static str> {
+    use serialport::ErrorKind;
+    match error {
+        serialib::Error::NoPortsAvailable(_) => {
+            Some("Plug the device in and check its driver is installed")
+        }
+        serialib::Error::Busy { .. } => {
+            Some("Close the other program or Symphony window using the port")
+        }
+        serialib::Error::Open { source, .. } => match source.kind() {
+            ErrorKind::NoDevice => Some("The device may have been unplugged, pick the port again"),
+            ErrorKind::InvalidInput => Some("Check the device supports these port settings"),
+            ErrorKind::Io(io::ErrorKind::PermissionDenied) => Some(
+                "Close other programs using the port, and check you are allowed to open it (e.g. the dialout group)",
+            ),
+            _ => None,
+        },
+        serialib::Error::Connect { .. } => {
+            Some("Check the address, and that the bridge (e.g. ser2net) is running")
+        }
+        serialib::Error::File { .. } => {
+            Some("Check the path, and that you are allowed to read it")
+        }
+        serialib::Error::OpenTimeout { .. } => Some(
+            "The port didn't respond, replug the device or raise the connect timeout",
+        ),
+        serialib::Error::Read(_) | serialib::Error::Write(_) => {
+            Some("Check the cable, then reconnect")
+        }
+        serialib::Error::Parse { .. } => Some("Hex commands are bytes like '1F 2A', '0x1F 0x2A' or '1F2A'"),
+        serialib::Error::ReceiverClosed => None,
+    }
+}
+
+/// The latest error with the settings it happened under, to paste into a bug report
+#[derive(Debug, Clone, PartialEq)]
+struct ErrorReport {
+    message: String,
+    hint: Option<&'static str>,
+    /// Source, framing and everything else about the port at the time
+    settings: Vec<String>,
+}
+
+impl Display for ErrorReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Error: {}", self.message)?;
+        if let Some(hint) = self.hint {
+            writeln!(f, "Hint: {}", hint)?;
+        }
+        for line in self.settings.iter() {
+            writeln!(f, "{}", line)?;
+        }
+        write!(
+            f,
+            "Symphony {} on {} {}",
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        )
+    }
+}
+
+/// `arg` quoted for pasting into a shell
+fn shell_quote(arg: &str) -> String {
+    if cfg!(windows) {
+        format!("\"{}\"", arg)
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Bytes shown per row of the byte grid
+const GRID_ROW_LEN: usize = 16;
+
+/// Edit the hex bytes in `text` as a grid of byte cells, with their ASCII
+/// alongside. Rewrites `text` as spaced hex bytes whenever a cell changes.
+fn byte_grid(ui: &mut egui::Ui, id: usize, text: &mut String) {
+    // An empty command is an empty grid to add bytes to
+    let parsed = match text.trim().is_empty() {
+        true => Ok(Vec::new()),
+        false => parse_hex_command(text),
+    };
+    let mut bytes = match parsed {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            ui.colored_label(
+                Color32::DARK_RED,
+                format!("Fix the hex to edit it as bytes: {}", e),
+            );
+            return;
+        }
+    };
+    let mut changed = false;
+    egui::Grid::new(("byte grid", id))
+        .striped(true)
+        .spacing([4., 2.])
+        .show(ui, |ui| {
+            for (row, chunk) in bytes.chunks_mut(GRID_ROW_LEN).enumerate() {
+                ui.monospace(format!("{:04X}", row * GRID_ROW_LEN));
+                for byte in chunk.iter_mut() {
+                    changed |= ui
+                        .add(egui::DragValue::new(byte).hexadecimal(2, false, true))
+                        .changed();
+                }
+                for _ in chunk.len()..GRID_ROW_LEN {
+                    ui.label("");
+                }
+                let ascii: String = chunk
+                    .iter()
+                    .map(|b| match b.is_ascii_graphic() || *b == b' ' {
+                        true => *b as char,
+                        false => '.',
+                    })
+                    .collect();
+                ui.monospace(ascii);
+                ui.end_row();
+            }
+        });
+    ui.horizontal(|ui| {
+        if ui.small_button("Add Byte").clicked() {
+            bytes.push(0);
+            changed = true;
+        }
+        if ui
+            .add_enabled(!bytes.is_empty(), egui::Button::new("Remove Byte").small())
+            .clicked()
+        {
+            bytes.pop();
+            changed = true;
+        }
+        ui.label(format!("{} bytes", bytes.len()));
+    });
+    if changed {
+        *text = bytes
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<_>>()
+            .join(" ");
+    }
+}
+
+/// How long the reply to a command is captured for, whichever limit comes first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+struct ReplyWindow {
+    millis: u64,
+    lines: usize,
+}
+
+impl Default for ReplyWindow {
+    fn default() -> Self {
+        Self {
+            millis: 1_000,
+            lines: 10,
+        }
+    }
+}
+
+/// Lines that arrived after sending a command that captures its reply
+#[derive(Debug)]
+struct Reply {
+    command: String,
+    sent_at: Instant,
+    lines: Vec<String>,
+    /// Whether lines are still being added
+    open: bool,
+}
+
+impl Reply {
+    /// Stop capturing once `window` is used up
+    fn close_if_done(&mut self, window: ReplyWindow) {
+        if self.sent_at.elapsed() >= Duration::from_millis(window.millis)
+            || self.lines.len() >= window.lines
+        {
+            self.open = false;
+        }
+    }
+}
+
+/// Lines of multi-line commands still waiting to be written
+#[derive(Debug)]
+struct LineRun {
+    lines: VecDeque<(Vec<u8>, Duration)>,
+    next_at: Instant,
+}
+
+/// A named line configuration that can be applied to `current_port` in one click
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct PortPreset {
+    name: String,
+    baud_rate: usize,
+    data_bits: DataBits,
+    parity: Parity,
+    stop_bits: StopBits,
+    flow_control: FlowControl,
+}
+
+impl PortPreset {
+    fn new(name: &str, baud_rate: usize, parity: Parity) -> Self {
+        Self {
+            name: name.to_string(),
+            baud_rate,
+            data_bits: DataBits::Eight,
+            parity,
+            stop_bits: StopBits::One,
+            flow_control: FlowControl::None,
+        }
+    }
+
+    /// Presets shipped with Symphony, used until the user saves their own
+    fn builtin() -> Vec<Self> {
+        vec![
+            Self::new("Arduino 9600 8N1", 9600, Parity::None),
+            Self::new("115200 8N1", 115200, Parity::None),
+            Self::new("Modbus 19200 8E1", 19200, Parity::Even),
+        ]
+    }
+
+    /// Take the line settings of `device`, keeping the name
+    fn capture(&mut self, device: &Device) {
+        self.baud_rate = device.baud_rate;
+        self.data_bits = device.data_bits;
+        self.parity = device.parity;
+        self.stop_bits = device.stop_bits;
+        self.flow_control = device.flow_control;
+    }
+
+    fn apply(&self, device: &mut Device) {
+        device.baud_rate = self.baud_rate;
+        device.data_bits = self.data_bits;
+        device.parity = self.parity;
+        device.stop_bits = self.stop_bits;
+        device.flow_control = self.flow_control;
+    }
+}
+
+/// Result of listening at one baud rate, sent by the detection thread
+#[derive(Debug)]
+enum BaudProbe {
+    Scored { baud_rate: u32, score: usize },
+    Failed { baud_rate: u32, error: String },
+}
+
+/// A running baud rate detection, see [`Symphony::start_baud_detect`]
+#[derive(Debug)]
+struct BaudDetect {
+    cancel: Arc<AtomicBool>,
+    progress_rx: Receiver<BaudProbe>,
+    tried: usize,
+    /// Best rate so far and its score
+    best: Option<(u32, usize)>,
+}
+
+/// How long "Test" listens on the port
+const PORT_TEST_WINDOW: Duration = Duration::from_secs(1);
+
+/// Longest sample of received text a port test shows
+const PORT_TEST_SAMPLE_LEN: usize = 60;
+
+/// Axis titles with units, like `Voltage (V)`, and how y ticks are labelled
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+struct AxisLabels {
+    x: String,
+    y: String,
+    /// Label y ticks with SI prefixes, like `4.7k` for 4700
+    engineering: bool,
+}
+
+impl Default for AxisLabels {
+    fn default() -> Self {
+        Self {
+            x: String::from("Time (s)"),
+            y: String::new(),
+            engineering: false,
+        }
+    }
+}
+
+/// Decades shown below the top of a log axis when some value in range isn't positive
+const LOG_DECADES: f64 = 3.;
+
+/// `segments` on a log10 scale, split around the samples that have no
+/// logarithm, which are counted in `skipped`
+fn log_scaled(segments: Vec<PlotPoints>, skipped: &mut usize) -> Vec<PlotPoints> {
+    let mut scaled = Vec::new();
+    for segment in segments {
+        let mut run = Vec::new();
+        for point in segment.points() {
+            if point.y > 0. {
+                run.push([point.x, point.y.log10()]);
+                continue;
+            }
+            *skipped += 1;
+            if !run.is_empty() {
+                scaled.push(PlotPoints::from(std::mem::take(&mut run)));
+            }
+        }
+        if !run.is_empty() {
+            scaled.push(PlotPoints::from(run));
+        }
+    }
+    scaled
+}
+
+/// SI prefixes from 10^-12 to 10^12, a factor of 1000 apart
+const SI_PREFIXES: [&str; 9] = ["p", "n", "µ", "m", "", "k", "M", "G", "T"];
+
+/// `value` in engineering notation, like `4.7k` or `-120µ`
+fn engineering(value: f64) -> String {
+    if value == 0. || !value.is_finite() {
+        return format!("{}", value);
+    }
+    let group = (value.abs().log10() / 3.).floor().clamp(-4., 4.);
+    let mantissa = value / 10f64.powf(group * 3.);
+    let mantissa = format!("{:.3}", mantissa);
+    let mantissa = mantissa.trim_end_matches('0').trim_end_matches('.');
+    format!("{}{}", mantissa, SI_PREFIXES[(group + 4.) as usize])
+}
+
+/// Tick label of the log axis mark at `exponent`
+fn log_tick(exponent: f64, engineering_notation: bool) -> String {
+    let value = 10f64.powf(exponent);
+    if engineering_notation {
+        engineering(value)
+    } else if (1e-3..1e5).contains(&value) {
+        format!("{}", (value * 1e3).round() / 1e3)
+    } else {
+        format!("{:.1e}", value)
+    }
+}
+
+/// How long settings must stay unchanged before they are saved
+const AUTOSAVE_DELAY: Duration = Duration::from_secs(3);
+
+/// Commands kept in the quick bar of the Commands panel
+const RECENT_COMMANDS: usize = 5;
+
+/// How often a dropped source is tried again
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a channel can go without a value before it's shown as stalled
+const STALE_AFTER: Duration = Duration::from_secs(2);
+
+/// Amber shown while the port is still being opened
+const CONNECTING_COLOR: Color32 = Color32::from_rgb(255, 191, 0);
+
+/// Longest the app waits on exit for the serial thread to release the port
+const EXIT_JOIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Packets the raw data queue may be set to hold
+const QUEUE_CAPACITY_RANGE: RangeInclusive<usize> = 1..=1_000_000;
+
+/// Milliseconds packets can be set to be merged within
+const MERGE_WINDOW_MS: RangeInclusive<u64> = 0..=5_000;
+
+/// Percent of troubled packets the health thresholds can be set to
+const HEALTH_PERCENT: RangeInclusive<f64> = 0.1..=100.0;
+
+/// KiB the line limit can be set to
+const MAX_LINE_KIB: RangeInclusive<usize> = 1..=16 * 1024;
+
+/// Percent of garbage bytes the baud mismatch hint can be set to warn from
+const GARBAGE_PERCENT: RangeInclusive<f64> = 1.0..=100.0;
+
+/// Milliseconds the serial thread can be set to sleep between idle reads
+const MAX_IDLE_SLEEP_MS: RangeInclusive<u64> = 0..=500;
+
+/// Seconds opening a source can be given
+const CONNECT_TIMEOUT_SECS: RangeInclusive<u64> = 1..=120;
+
+/// Milliseconds a break can be set to last
+const BREAK_MS: RangeInclusive<u64> = 1..=5_000;
+
+/// Seconds without data before the idle warning can be set to
+const IDLE_TIMEOUT_SECS: RangeInclusive<u64> = 1..=3600;
+
+/// Where the window was left, restored on the next start but not from workspaces
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct UiState {
+    open_panel: Panel,
+    text_view: TextViewOptions,
+    histogram: HistogramOptions,
+    waterfall: WaterfallOptions,
+}
+
+/// `T` from `deserializer`, or its default if it doesn't parse, like a panel
+/// saved by a newer version.
+///
+/// For state that isn't worth failing to load every other setting over.
+fn or_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::de::DeserializeOwned + Default,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(serde_json::from_value(value).unwrap_or_default())
+}
+
+/// Everything that is saved between runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct Settings {
+    current_port: Device,
+    presets: Vec<PortPreset>,
+    profiles: SerialDevices,
+    commands: Vec<Command>,
+    macros: Vec<Macro>,
+    rules: Vec<Rule>,
+    /// Connect to `current_port` on startup
+    auto_connect: bool,
+    /// Start the time axis over at t=0 when connecting
+    reset_time_on_connect: bool,
+    /// Throw away the previous capture when connecting
+    clear_on_connect: bool,
+    /// Reconnect after a dropout, keeping the buffer
+    keep_on_reconnect: bool,
+    framing: Framing,
+    channels: Vec<ChannelOptions>,
+    plot_layout: PlotLayout,
+    regression: Regression,
+    transform: Transform,
+    checksum: ChecksumOptions,
+    binary: BinaryLayout,
+    nmea: NmeaOptions,
+    labeled: bool,
+    detect: DetectOptions,
+    /// Source columns plotted as channels, see [`parse_columns`]
+    columns: String,
+    line_width: f32,
+    anti_aliasing: bool,
+    refresh_hz: u32,
+    grid: GridOptions,
+    drain_limit: usize,
+    background_redraw: BackgroundRedraw,
+    decimals: usize,
+    source_kind: SourceKind,
+    /// `host:port` of the TCP bridge
+    tcp_address: String,
+    demo: DemoSettings,
+    /// FIFO, device or file read by the file source
+    file_path: String,
+    /// strftime-style format of wall-clock timestamps
+    timestamp_format: String,
+    /// Whether the plot had the whole window
+    compact: bool,
+    reconnect_by_identity: bool,
+    newline_translation: NewlineTranslation,
+    clear_after_send: bool,
+    modbus_request: modbus::Request,
+    reply_window: ReplyWindow,
+    discovery: Discovery,
+    keep_alive: KeepAlive,
+    /// Axis labels for each port path
+    axis_labels: HashMap<String, AxisLabels>,
+    /// Locked y ranges of channels for each port path
+    range_locks: HashMap<String, BTreeMap<usize, [f64; 2]>>,
+    record: RecordOptions,
+    health_thresholds: HealthThresholds,
+    /// Packets the serial thread may queue ahead of the parser
+    queue_capacity: usize,
+    /// Parsed packets that may queue up ahead of the UI
+    parse_queue_capacity: usize,
+    /// Quiet time before the connection counts as idle
+    idle_timeout_secs: u64,
+    /// Share of garbage bytes from which the baud rate looks wrong
+    garbage_threshold: f64,
+    connect_timeout_secs: u64,
+    /// Longest the serial thread sleeps between reads that brought nothing
+    max_idle_sleep_ms: u64,
+    break_ms: u64,
+    line_limit: LineLimit,
+    merge_window_ms: u64,
+    timestamping: Timestamping,
+    #[serde(deserialize_with = "or_default")]
+    ui: UiState,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            current_port: Device::default(),
+            presets: PortPreset::builtin(),
+            profiles: SerialDevices::default(),
+            commands: Vec::default(),
+            macros: Vec::default(),
+            rules: Vec::default(),
+            auto_connect: false,
+            reset_time_on_connect: true,
+            clear_on_connect: true,
+            keep_on_reconnect: false,
+            framing: Framing::default(),
+            channels: Vec::default(),
+            plot_layout: PlotLayout::default(),
+            regression: Regression::default(),
+            transform: Transform::default(),
+            checksum: ChecksumOptions::default(),
+            binary: BinaryLayout::default(),
+            nmea: NmeaOptions::default(),
+            labeled: false,
+            detect: DetectOptions::default(),
+            columns: String::new(),
+            line_width: 1.5,
+            anti_aliasing: true,
+            refresh_hz: 30,
+            grid: GridOptions::default(),
+            drain_limit: 5_000,
+            background_redraw: BackgroundRedraw::default(),
+            source_kind: SourceKind::default(),
+            tcp_address: String::from("localhost:2000"),
+            file_path: String::new(),
+            demo: DemoSettings::default(),
+            timestamp_format: String::from(timefmt::DEFAULT_FORMAT),
+            compact: false,
+            reconnect_by_identity: false,
+            newline_translation: NewlineTranslation::default(),
+            clear_after_send: false,
+            modbus_request: modbus::Request::default(),
+            reply_window: ReplyWindow::default(),
+            discovery: Discovery::default(),
+            keep_alive: KeepAlive::default(),
+            decimals: 3,
+            axis_labels: HashMap::new(),
+            range_locks: HashMap::new(),
+            record: RecordOptions::default(),
+            health_thresholds: HealthThresholds::default(),
+            queue_capacity: 10_000,
+            parse_queue_capacity: 10_000,
+            idle_timeout_secs: 5,
+            garbage_threshold: 0.3,
+            connect_timeout_secs: 10,
+            max_idle_sleep_ms: 10,
+            break_ms: 250,
+            line_limit: LineLimit::default(),
+            merge_window_ms: 0,
+            timestamping: Timestamping::default(),
+            ui: UiState::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// `self` with the tuning kept to what its controls allow, in case the
+    /// file was edited by hand
+    fn clamped(mut self) -> Self {
+        self.queue_capacity = within(self.queue_capacity, QUEUE_CAPACITY_RANGE);
+        self.idle_timeout_secs = within(self.idle_timeout_secs, IDLE_TIMEOUT_SECS);
+        self.break_ms = within(self.break_ms, BREAK_MS);
+        self.connect_timeout_secs = within(self.connect_timeout_secs, CONNECT_TIMEOUT_SECS);
+        self.parse_queue_capacity = within(self.parse_queue_capacity, QUEUE_CAPACITY_RANGE);
+        self.max_idle_sleep_ms = within(self.max_idle_sleep_ms, MAX_IDLE_SLEEP_MS);
+        self.garbage_threshold = within(self.garbage_threshold * 100., GARBAGE_PERCENT) / 100.;
+        self.line_limit.max_len = within(self.line_limit.max_len / 1024, MAX_LINE_KIB) * 1024;
+        let thresholds = &mut self.health_thresholds;
+        thresholds.degraded = within(thresholds.degraded * 100., HEALTH_PERCENT) / 100.;
+        thresholds.bad =
+            (within(thresholds.bad * 100., HEALTH_PERCENT) / 100.).max(thresholds.degraded);
+        self.merge_window_ms = within(self.merge_window_ms, MERGE_WINDOW_MS);
+        self
+    }
+}
+
+/// `value`, or the end of `range` it's past
+fn within<T: PartialOrd>(value: T, range: RangeInclusive<T>) -> T {
+    let (start, end) = range.into_inner();
+    if value < start {
+        start
+    } else if value > end {
+        end
+    } else {
+        value
+    }
+}
+
+/// Which samples the exports include
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum ExportScope {
+    #[default]
+    /// Everything in the buffer
+    All,
+    /// Only samples inside the plot's current x range
+    Visible,
+}
+
+impl Display for ExportScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportScope::All => write!(f, "All"),
+            ExportScope::Visible => write!(f, "Visible window"),
+        }
+    }
+}
+
+/// File format of the data export
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum DataFormat {
+    #[default]
+    /// One text row per sample
+    Csv,
+    /// Raw `f64`s, see [`capture`]
+    Binary,
+}
+
+impl Display for DataFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataFormat::Csv => write!(f, "CSV"),
+            DataFormat::Binary => write!(f, "Binary"),
+        }
+    }
+}
+
+impl DataFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            DataFormat::Csv => "csv",
+            DataFormat::Binary => capture::EXTENSION,
+        }
+    }
+}
+
+/// First line of exported data files
+const DATA_HEADER: &str = "channel,time,value";
+
+/// Samples from CSV rows of `channel,x,y` after a header line, where a
+/// channel is written as either `3` or `Channel 3`
+fn parse_samples(csv: &str) -> Result<Vec<Measurement>, String> {
+    let mut plot_data: Vec<Measurement> = Vec::new();
+    for (line, row) in csv.lines().enumerate().skip(1) {
+        let parsed = row.trim().split(',').collect::<Vec<_>>();
+        // Columns after the first three, like an export's timestamp, aren't needed
+        let [channel, x, y, ..] = parsed[..] else {
+            return Err(format!("data row {} doesn't have 3 columns", line + 1));
+        };
+        let channel = channel.strip_prefix("Channel ").unwrap_or(channel);
+        let (Ok(channel), Ok(x), Ok(y)) =
+            (channel.parse::<usize>(), x.parse::<f64>(), y.parse::<f64>())
+        else {
+            return Err(format!("data row {} isn't a valid sample", line + 1));
+        };
+        push_loaded(&mut plot_data, channel, x, y);
+    }
+    Ok(plot_data)
+}
+
+/// Samples of every channel in `capture`
+fn capture_samples(capture: &Capture) -> Vec<Measurement> {
+    let mut plot_data = Vec::new();
+    for channel in capture.channels.iter() {
+        for [x, y] in channel.points.iter() {
+            push_loaded(&mut plot_data, channel.index as usize, *x, *y);
+        }
+    }
+    plot_data
+}
+
+/// Add a sample read from a file to `channel`, creating the channels up to it
+fn push_loaded(plot_data: &mut Vec<Measurement>, channel: usize, x: f64, y: f64) {
+    if plot_data.len() <= channel {
+        plot_data.resize_with(channel + 1, || {
+            Measurement::new_with_look_behind(LOOK_BEHIND)
+        });
+    }
+    // Pushed directly so the whole buffer survives, whatever its length
+    let measurement = &mut plot_data[channel];
+    measurement.integer &= y.is_nan() || y.fract() == 0.;
+    measurement.values.push_back(PlotPoint::new(x, y));
+}
+
+/// Version written into new workspace files
+const WORKSPACE_VERSION: u32 = 1;
+
+/// Settings plus the captured samples, for handing a whole capture to someone else.
+///
+/// Fields added later need `#[serde(default)]` so older files keep loading;
+/// `version` only has to change when an existing field changes meaning.
+#[derive(Debug, Serialize, Deserialize)]
+struct Workspace {
+    version: u32,
+    settings: Settings,
+    /// Buffered samples as `channel,x,y` CSV rows
+    data: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct RecordOptions {
+    /// Where the per-channel summary is written
+    stats_path: String,
+    /// Where the raw samples are written
+    data_path: String,
+    /// Where the plot is written as a vector figure
+    svg_path: String,
+    data_format: DataFormat,
+    /// Write a CSV per channel next to the data file instead of one for all
+    split_channels: bool,
+    scope: ExportScope,
+    /// Where workspaces are saved to and loaded from
+    workspace_path: String,
+    /// Channels left out of the exports, new channels are exported by default
+    excluded_channels: HashSet<usize>,
+    summary: SummaryOptions,
+}
+
+impl Default for RecordOptions {
+    fn default() -> Self {
+        Self {
+            stats_path: String::from("statistics.csv"),
+            data_path: String::from("data.csv"),
+            svg_path: String::from("plot.svg"),
+            data_format: DataFormat::default(),
+            split_channels: false,
+            scope: ExportScope::default(),
+            workspace_path: String::from("workspace.json"),
+            excluded_channels: HashSet::new(),
+            summary: SummaryOptions::default(),
+        }
+    }
+}
+
+/// Data quality summary logged every `interval_secs` while connected, for
+/// keeping an eye on long unattended captures
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct SummaryOptions {
+    enabled: bool,
+    interval_secs: u64,
+    /// Also append every summary to `csv_path`
+    csv: bool,
+    csv_path: String,
+}
+
+impl Default for SummaryOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 60,
+            csv: false,
+            csv_path: String::from("summary.csv"),
+        }
+    }
+}
+
+/// Gridlines drawn behind the plot
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct GridOptions {
+    show: bool,
+    /// Closest two gridlines get before the finer ones fade out, in points
+    spacing: f32,
+    /// Draw the finest level of lines between the labelled ones
+    minor: bool,
+}
+
+impl Default for GridOptions {
+    fn default() -> Self {
+        Self {
+            show: true,
+            spacing: 8.,
+            minor: true,
+        }
+    }
+}
+
+impl GridOptions {
+    /// Apply the options to `plot`
+    fn configure<'a>(&self, plot: egui_plot::Plot<'a>) -> egui_plot::Plot<'a> {
+        // egui_plot fades lines in between spacing and this far apart
+        let plot = plot
+            .show_grid(self.show)
+            .grid_spacing(self.spacing..=(self.spacing * 37.5).max(300.));
+        if self.minor {
+            return plot;
+        }
+        let major_only = |input: egui_plot::GridInput| {
+            let marks = egui_plot::log_grid_spacer(10)(input);
+            let finest = marks
+                .iter()
+                .map(|mark| mark.step_size)
+                .reduce(f64::min)
+                .unwrap_or_default();
+            marks
+                .into_iter()
+                .filter(|mark| mark.step_size > finest)
+                .collect()
+        };
+        plot.x_grid_spacer(major_only).y_grid_spacer(major_only)
+    }
+}
+
+#[derive(Default, Debug, PartialEq)]
+struct PlotOptions {
+    delimiter: Delimiter,
+    buffer_size: usize,
+    /// Keep every sample, ignoring `buffer_size` and the look-behind
+    unlimited: bool,
+    plot_width: usize,
+    x_axis: [usize; 2],
+    y_axis: [usize; 2],
+    autoscale: bool,
+    /// How often autoscaling refits the y axis
+    rescale: Rescale,
+    style: PlotStyle,
+    layout: PlotLayout,
+    gaps: GapMode,
+    /// Stroke width of every plotted line, in points
+    line_width: f32,
+    anti_aliasing: bool,
+    /// How often the plot is redrawn while data streams in, 0 for every frame.
+    /// Packets are still read and buffered every frame
+    refresh_hz: u32,
+    grid: GridOptions,
+    /// Decimals shown in readouts, stored and exported values keep full precision
+    decimals: usize,
+    regression: Regression,
+    transform: Transform,
+    checksum: ChecksumOptions,
+    /// Field layout of fixed-width binary frames
+    binary: BinaryLayout,
+    /// Fields of NMEA sentences, when payloads are parsed as them
+    nmea: NmeaOptions,
+    /// Split tokens into `name:value`, plotting every name as its own column
+    labeled: bool,
+    /// Take the channel count from the first lines instead of every column that turns up
+    detect: DetectOptions,
+    /// Columns as typed, see [`parse_columns`]
+    column_text: String,
+    /// Source column of each channel, empty to plot every column as its own channel
+    columns: Vec<usize>,
+    channels: Vec<ChannelOptions>,
+}
+
+/// What to do about a capture that isn't exported when the window closes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum QuitAnswer {
+    Export,
+    Discard,
+    Stay,
+}
+
+/// How often the window redraws while live data streams in and it is in the
+/// background. The serial thread reads at full speed either way.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum BackgroundRedraw {
+    /// As often as in the foreground
+    #[default]
+    Full,
+    /// A few frames a second
+    Reduced,
+    /// Once a second, just enough to keep the queue from the serial thread drained
+    Minimal,
+}
+
+impl Display for BackgroundRedraw {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BackgroundRedraw::Full => write!(f, "Full"),
+            BackgroundRedraw::Reduced => write!(f, "Reduced"),
+            BackgroundRedraw::Minimal => write!(f, "Minimal"),
+        }
+    }
+}
+
+impl BackgroundRedraw {
+    /// Time between frames in the background, `None` to redraw continuously
+    fn interval(&self) -> Option<Duration> {
+        match self {
+            BackgroundRedraw::Full => None,
+            BackgroundRedraw::Reduced => Some(Duration::from_millis(250)),
+            BackgroundRedraw::Minimal => Some(Duration::from_secs(1)),
+        }
+    }
+}
+
+/// How often an autoscaled y axis is refitted to the data
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Rescale {
+    #[default]
+    EveryFrame,
+    /// Once per [`RESCALE_INTERVAL`], so the axis doesn't jitter with every sample
+    Periodic,
+    /// Only when asked to with "Rescale"
+    Manual,
+}
+
+impl Display for Rescale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rescale::EveryFrame => write!(f, "Every Frame"),
+            Rescale::Periodic => write!(f, "Every Second"),
+            Rescale::Manual => write!(f, "On Rescale"),
+        }
+    }
+}
+
+/// How long a periodically rescaled y axis holds still
+const RESCALE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[allow(dead_code)]
+#[derive(Debug)]
+pub struct Symphony {
+    text_view_options: TextViewOptions,
+    plot_options: PlotOptions,
+    record_options: RecordOptions,
+    current_port: Device,
+    presets: Vec<PortPreset>,
+    /// Ports saved with all their settings under a name of their own
+    profiles: SerialDevices,
+    /// Name the current port is saved under
+    profile_name: String,
+    /// Settings requested and reported by the driver for the open port
+    port_info: Option<String>,
+    /// Shown above the log until dismissed
+    last_error: Option<ErrorReport>,
+    /// Name the current line settings are saved under
+    preset_name: String,
+    connected: bool,
+    /// Set while the serial thread is still opening the port
+    connecting: bool,
+    /// Tells other Symphony windows the open port is taken
+    port_lock: Option<PortLock>,
+    /// Connection state, stop flag and drop counter of the current serial thread
+    serial_shared: SerialShared,
+    serial_handle: Option<JoinHandle<()>>,
+    /// Packets of the current session once the parse thread is done with them
+    parsed_rx: Option<Receiver<ParsedPacket>>,
+    /// Parse settings the parse thread of the current session reads
+    parser: Arc<RwLock<Parser>>,
+    command_tx: Option<Sender<PortCommand>>,
+    /// How long "Send Break" holds the line
+    break_duration: Duration,
+    /// Capacity of the queue between the serial thread and the parse thread
+    queue_capacity: usize,
+    /// Capacity of the queue between the parse thread and the GUI
+    parse_queue_capacity: usize,
+    /// Most packets handled in one frame, so a backlog doesn't freeze the UI
+    drain_limit: usize,
+    /// Redraw rate while the window is unfocused or minimized
+    background_redraw: BackgroundRedraw,
+    source_kind: SourceKind,
+    /// `host:port` connected to when `source_kind` is [`SourceKind::Tcp`]
+    tcp_address: String,
+    /// What [`SourceKind::Demo`] generates
+    demo: DemoSettings,
+    /// Path read when `source_kind` is [`SourceKind::File`]
+    file_path: String,
+    /// Format of wall-clock timestamps in the text view and data export, see [`timefmt`]
+    timestamp_format: String,
+    /// Hide the panels and give the plot the whole window
+    compact: bool,
+    /// Look a dropped USB adapter up by its identity instead of its old path
+    reconnect_by_identity: bool,
+    /// Applied to text commands after their line ending, hex commands go out as typed
+    newline_translation: NewlineTranslation,
+    /// Empty a listed command once it's sent, instead of keeping it for resending
+    clear_after_send: bool,
+    /// Fields of the request built in the Commands panel
+    modbus_request: modbus::Request,
+    /// How long replies to commands that capture them are collected
+    reply_window: ReplyWindow,
+    /// Reply to the last command that captures one
+    reply: Option<Reply>,
+    /// Command that asks the device for its commands
+    discovery: Discovery,
+    /// Whether `reply` is the answer to `discovery`, to be turned into suggestions
+    discovering: bool,
+    /// Commands found by discovery, and whether each is ticked to be added
+    suggestions: Vec<(String, bool)>,
+    keep_alive: KeepAlive,
+    /// When anything was last written to the port, the keep-alive waits for quiet
+    last_sent: Instant,
+    /// Keep-alives sent since connecting
+    keep_alives_sent: usize,
+    /// Identity of the USB adapter last connected to, if it was one
+    device_identity: Option<UsbIdentity>,
+    /// Axis labels for each port path, see [`Symphony::axis_labels`]
+    axis_labels: HashMap<String, AxisLabels>,
+    /// Frozen y range of channels for each port path, see [`Symphony::range_locks`]
+    range_locks: HashMap<String, BTreeMap<usize, [f64; 2]>>,
+    /// How the serial thread cuts incoming bytes into packets
+    framing: Framing,
+    timestamping: Timestamping,
+    /// Longest a text packet may get without its terminator
+    line_limit: LineLimit,
+    /// Text packets this close together are joined into one, zero leaves them apart
+    merge_window: Duration,
+    /// Framing the running serial thread was started with
+    session_framing: Framing,
+    /// Drop count as of the last frame, to notice new drops
+    seen_dropped: usize,
+    /// When the serial thread last had to drop packets because the GUI fell behind
+    last_drop: Option<Instant>,
+    /// When the last packet (or the connection) arrived
+    last_packet: Instant,
+    /// How long the device may stay silent before warning about it
+    idle_timeout: Duration,
+    /// How long opening the source may take before it's given up on
+    connect_timeout: Duration,
+    /// Share of unprintable bytes over which a wrong baud rate is suspected
+    garbage_threshold: f64,
+    baud_mismatch: BaudMismatch,
+    health_thresholds: HealthThresholds,
+    health_window: HealthWindow,
+    /// Longest the serial thread sleeps between reads while the source is idle
+    max_idle_sleep: Duration,
+    throughput: Throughput,
+    /// Packets dropped because their checksum didn't match
+    bad_checksums: usize,
+    /// Tokens since connecting that weren't empty but didn't parse as a number
+    parse_errors: usize,
+    /// When the last periodic summary was logged
+    last_summary: Instant,
+    /// What splitting lines on the delimiter gave, to spot a wrong delimiter
+    token_counts: TokenCounts,
+    channel_detection: ChannelDetection,
+    // NOTE: Maybe use a VecDeque?
+    plot_data: Vec<Measurement>,
+    text_data: Vec<TextLine>,
+    /// Names of the source columns of labeled lines, in the order they turned up
+    column_names: Vec<String>,
+    /// Bytes as they arrived before framing, for the raw text view, the
+    /// oldest dropped past `raw_kib` of the text view options
+    raw_data: RawStream,
+    open_panel: Panel,
+    histogram: HistogramOptions,
+    waterfall: WaterfallOptions,
+    waterfall_texture: Option<WaterfallTexture>,
+    commands: Vec<Command>,
+    /// File the command list is imported from and exported to
+    commands_file: String,
+    macros: Vec<Macro>,
+    macro_run: Option<MacroRun>,
+    /// Checked against every received line, see [`Symphony::apply_rules`]
+    rules: Vec<Rule>,
+    /// When each rule, by index in `rules`, last sent its reply
+    rule_replied: ReplyThrottle,
+    /// Times and names of lines marked by rules, drawn on the plot
+    bookmarks: Vec<(f64, String)>,
+    cursors: Cursors,
+    line_run: Option<LineRun>,
+    /// Commands waiting for the user to confirm sending them, by index in `commands`
+    pending_send: Vec<usize>,
+    /// Whether samples arrived since the data was last exported or saved in a workspace
+    unexported: bool,
+    /// Set while asking whether to quit with a capture that isn't exported
+    quit_prompt: bool,
+    /// Set once quitting is confirmed, so the next close goes through
+    quit_confirmed: bool,
+    /// Distinct commands sent lately, most recent first
+    recent_commands: VecDeque<Command>,
+    /// Commands ticked for "Send Selected", by index in `commands`
+    selected_commands: HashSet<usize>,
+    baud_detect: Option<BaudDetect>,
+    /// Bytes a running port test heard, or why it failed
+    port_test: Option<Receiver<Result<Vec<u8>, String>>>,
+    /// What the last port test found, shown next to its button
+    port_test_result: Option<String>,
+    log: Log,
+    /// Why the last value typed into a numeric field was rejected
+    input_errors: HashMap<&'static str, String>,
+    /// Where settings are loaded from and saved to
+    settings_path: Option<PathBuf>,
+    /// Settings as of the last frame, to notice changes
+    last_settings: Settings,
+    /// Settings changed since they were last saved
+    dirty: bool,
+    /// When the settings last changed
+    changed_at: Instant,
+    /// Last title sent to the window, to only update it on changes
+    window_title: String,
+    /// X range the plot showed last frame, for exporting just that window
+    visible_x: Option<RangeInclusive<f64>>,
+    /// Y range the plot showed last frame, in log10 units on a log axis
+    visible_y: Option<RangeInclusive<f64>>,
+    /// Y range autoscaling last fitted for each lane and when, held between rescales
+    autoscale_y: Option<(Vec<Option<[f64; 2]>>, Instant)>,
+    /// Set by "Rescale" to refit the y axis on the next frame
+    rescale_now: bool,
+    /// Samples the log y axis couldn't show last frame, for not being positive
+    log_skipped: usize,
+    /// Origin of the time axis, moved forward by [`Symphony::reset_time`]
+    absolute_time: Instant,
+    /// Origin the serial thread stamps packets against
+    session_start: Instant,
+    reset_time_on_connect: bool,
+    clear_on_connect: bool,
+    keep_on_reconnect: bool,
+    /// When to try the source again after it dropped out
+    reconnect_at: Option<Instant>,
+    /// Times the connection dropped out, marked on the plot
+    dropouts: Vec<f64>,
+    auto_connect: bool,
+    /// Non-blocking message shown in the status bar until dismissed
+    notice: Option<String>,
+    /// When the current pause started, incoming packets are dropped while set
+    paused_at: Option<Instant>,
+    /// Time spent paused since the origin, left out of the capture duration
+    paused_total: Duration,
+}
+
+impl Symphony {
+    #[instrument]
+    pub fn new(settings_path: Option<PathBuf>) -> Self {
+        let now = Instant::now();
+        let loaded = settings_path.as_deref().map(settings::load::<Settings>);
+        let (settings, load_error) = match loaded {
+            Some(Ok(Some(settings))) => (settings.clamped(), None),
+            loaded => (
+                Settings {
+                    commands: vec![Command::default(), Command::default()],
+                    ..Default::default()
+                },
+                loaded.and_then(Result::err),
+            ),
+        };
+        let mut symphony = Self {
+            text_view_options: settings.ui.text_view.clamped(),
+            plot_options: PlotOptions {
+                channels: settings.channels,
+                layout: settings.plot_layout,
+                regression: settings.regression,
+                transform: settings.transform,
+                checksum: settings.checksum,
+                binary: settings.binary,
+                nmea: settings.nmea,
+                labeled: settings.labeled,
+                detect: settings.detect,
+                columns: parse_columns(&settings.columns).unwrap_or_default(),
+                column_text: settings.columns,
+                line_width: settings.line_width,
+                anti_aliasing: settings.anti_aliasing,
+                refresh_hz: settings.refresh_hz,
+                grid: settings.grid,
+                decimals: settings.decimals,
+                ..Default::default()
+            },
+            record_options: settings.record,
+            current_port: settings.current_port,
+            presets: settings.presets,
+            profiles: settings.profiles,
+            profile_name: String::new(),
+            port_info: None,
+            last_error: None,
+            preset_name: String::new(),
+            connected: false,
+            connecting: false,
+            port_lock: None,
+            serial_shared: SerialShared::default(),
+            serial_handle: None,
+            parsed_rx: None,
+            parser: Arc::default(),
+            command_tx: None,
+            break_duration: Duration::from_millis(settings.break_ms),
+            queue_capacity: settings.queue_capacity,
+            parse_queue_capacity: settings.parse_queue_capacity,
+            drain_limit: settings.drain_limit,
+            background_redraw: settings.background_redraw,
+            source_kind: settings.source_kind,
+            tcp_address: settings.tcp_address,
+            file_path: settings.file_path,
+            demo: settings.demo,
+            timestamp_format: settings.timestamp_format,
+            compact: settings.compact,
+            reconnect_by_identity: settings.reconnect_by_identity,
+            newline_translation: settings.newline_translation,
+            clear_after_send: settings.clear_after_send,
+            modbus_request: settings.modbus_request,
+            reply_window: settings.reply_window,
+            reply: None,
+            discovery: settings.discovery,
+            discovering: false,
+            suggestions: Vec::new(),
+            keep_alive: settings.keep_alive,
+            last_sent: Instant::now(),
+            keep_alives_sent: 0,
+            device_identity: None,
+            axis_labels: settings.axis_labels,
+            range_locks: settings.range_locks,
+            timestamping: settings.timestamping,
+            line_limit: settings.line_limit,
+            merge_window: Duration::from_millis(settings.merge_window_ms),
+            session_framing: settings.framing.clone(),
+            framing: settings.framing,
+            seen_dropped: 0,
+            last_drop: None,
+            last_packet: now,
+            garbage_threshold: settings.garbage_threshold,
+            baud_mismatch: BaudMismatch::default(),
+            health_thresholds: settings.health_thresholds,
+            health_window: HealthWindow::default(),
+            idle_timeout: Duration::from_secs(settings.idle_timeout_secs),
+            connect_timeout: Duration::from_secs(settings.connect_timeout_secs),
+            max_idle_sleep: Duration::from_millis(settings.max_idle_sleep_ms),
+            throughput: Throughput::default(),
+            bad_checksums: 0,
+            parse_errors: 0,
+            last_summary: now,
+            token_counts: TokenCounts::default(),
+            channel_detection: ChannelDetection::default(),
+            plot_data: Vec::default(),
+            text_data: Vec::default(),
+            column_names: Vec::new(),
+            raw_data: RawStream::default(),
+            open_panel: settings.ui.open_panel,
+            histogram: settings.ui.histogram,
+            waterfall: settings.ui.waterfall,
+            waterfall_texture: None,
+            commands: settings.commands,
+            commands_file: String::from("commands.json"),
+            macros: settings.macros,
+            macro_run: None,
+            rules: settings.rules,
+            rule_replied: ReplyThrottle::default(),
+            bookmarks: Vec::new(),
+            cursors: Cursors::default(),
+            line_run: None,
+            pending_send: Vec::new(),
+            unexported: false,
+            quit_prompt: false,
+            quit_confirmed: false,
+            recent_commands: VecDeque::new(),
+            selected_commands: HashSet::new(),
+            baud_detect: None,
+            port_test: None,
+            port_test_result: None,
+            log: Log::default(),
+            input_errors: HashMap::new(),
+            settings_path,
+            last_settings: Settings::default(),
+            dirty: false,
+            changed_at: now,
+            window_title: String::new(),
+            visible_x: None,
+            visible_y: None,
+            log_skipped: 0,
+            autoscale_y: None,
+            rescale_now: false,
+            absolute_time: now,
+            session_start: now,
+            reset_time_on_connect: settings.reset_time_on_connect,
+            clear_on_connect: settings.clear_on_connect,
+            keep_on_reconnect: settings.keep_on_reconnect,
+            reconnect_at: None,
+            dropouts: Vec::new(),
+            auto_connect: settings.auto_connect,
+            notice: None,
+            paused_at: None,
+            paused_total: Duration::ZERO,
+        };
+        if let Some(e) = load_error {
+            symphony.keep_unloaded_settings(e);
+        }
+        symphony.last_settings = symphony.settings();
+        if symphony.auto_connect {
+            symphony.auto_connect();
+        }
+        symphony
+    }
+
+    /// Start the time axis over at t=0.
+    ///
+    /// What was captured so far stays, moved back by however long the old
+    /// origin was ago, so it ends up before t=0. Lines in the text view keep
+    /// the times they were stamped with.
+    fn reset_time(&mut self) {
+        let now = Instant::now();
+        let by = now
+            .saturating_duration_since(self.absolute_time)
+            .as_secs_f64();
+        self.absolute_time = now;
+        self.plot_data.iter_mut().for_each(|m| m.shift(by));
+        self.dropouts.iter_mut().for_each(|x| *x -= by);
+        self.bookmarks.iter_mut().for_each(|(x, _)| *x -= by);
+        self.cursors.a -= by;
+        self.cursors.b -= by;
+        self.throughput = Throughput::default();
+        self.paused_total = Duration::ZERO;
+        if self.paused_at.is_some() {
+            self.paused_at = Some(self.absolute_time);
+        }
+        info!("Reset time origin");
+        self.log.push(String::from("Reset time origin"));
+    }
+
+    /// Drop everything captured so far, keeping commands and the log
+    fn clear_capture(&mut self) {
+        self.plot_data.clear();
+        self.column_names.clear();
+        self.text_data.clear();
+        self.raw_data.clear();
+        self.dropouts.clear();
+        self.bookmarks.clear();
+        self.throughput = Throughput::default();
+        self.bad_checksums = 0;
+        self.parse_errors = 0;
+        self.token_counts = TokenCounts::default();
+        self.unexported = false;
+        info!("Cleared previous capture");
+        self.log.push(String::from("Cleared previous capture"));
+    }
+
+    fn toggle_pause(&mut self) {
+        match self.paused_at.take() {
+            Some(paused_at) => {
+                self.paused_total += paused_at.elapsed();
+                info!("Resumed capture");
+                self.log.push(String::from("Resumed capture"));
+            }
+            None => {
+                self.paused_at = Some(Instant::now());
+                info!("Paused capture");
+                self.log.push(String::from("Paused capture"));
+            }
+        }
+    }
+
+    /// How long the capture has been running since the origin, not counting pauses
+    fn capture_duration(&self) -> Duration {
+        let end = self.paused_at.unwrap_or_else(Instant::now);
+        end.saturating_duration_since(self.absolute_time)
+            .saturating_sub(self.paused_total)
+    }
+
+    /// Time of `packet` measured from the current origin
+    fn packet_time(&self, packet: &Packet) -> Duration {
+        let since_session = Duration::from_millis(packet.relative_time as u64);
+        let origin_offset = self
+            .absolute_time
+            .saturating_duration_since(self.session_start);
+        since_session.saturating_sub(origin_offset)
+    }
+
+    /// Framing to read with, with escapes in a custom terminator decoded.
+    ///
+    /// A terminator whose escapes don't parse is used as typed, which only
+    /// shows in bug reports, connecting refuses it, see [`Symphony::missing_source`].
+    fn decoded_framing(&self) -> Framing {
+        match &self.framing {
+            Framing::Custom(terminator) => {
+                Framing::Custom(decode_escapes(terminator).unwrap_or(terminator.clone()))
+            }
+            framing => framing.clone(),
+        }
+    }
+
+    /// How the serial thread, baud detection and port tests open and read the port
+    fn read_options(&self) -> ReadOptions {
+        ReadOptions {
+            connect_timeout: self.connect_timeout,
+            framing: self.decoded_framing(),
+            line_limit: self.line_limit,
+            merge_window: self.merge_window,
+            timestamping: self.timestamping,
+            max_idle_sleep: self.max_idle_sleep,
+        }
+    }
+
+    /// Spawn the serial thread for the source, stopping any previous one.
+    ///
+    /// A `resume` after a dropout keeps the capture and its time axis.
+    fn connect(&mut self, resume: bool) {
+        self.serial_shared.stop.store(true, Ordering::Relaxed);
+        if !resume {
+            self.reconnect_at = None;
+            if self.clear_on_connect {
+                self.clear_capture();
+            }
+            if self.reset_time_on_connect {
+                self.reset_time();
+            }
+        }
+        let (raw_data_tx, raw_data_rx) = mpsc::sync_channel(self.queue_capacity);
+        let (parsed_tx, parsed_rx) = mpsc::sync_channel(self.parse_queue_capacity);
+        let (command_tx, command_rx) = mpsc::channel();
+        // Each thread gets its own flags so a previous one winding down can't touch them
+        self.serial_shared = SerialShared::default();
+        let shared = self.serial_shared.clone();
+        let source = self.source();
+        let options = self.read_options();
+        self.session_framing = options.framing.clone();
+        self.parser = Arc::new(RwLock::new(self.parser_settings()));
+        let parser = self.parser.clone();
+        thread::spawn(move || parse::parse_thread(raw_data_rx, parsed_tx, parser));
+        let t_zero = self.absolute_time;
+        self.session_start = t_zero;
+        self.serial_handle = Some(thread::spawn(move || {
+            serialib::serial_thread(raw_data_tx, command_rx, source, options, t_zero, shared)
+        }));
+        self.parsed_rx = Some(parsed_rx);
+        self.command_tx = Some(command_tx);
+        self.throughput = Throughput::default();
+        self.bad_checksums = 0;
+        self.parse_errors = 0;
+        self.last_summary = Instant::now();
+        self.token_counts = TokenCounts::default();
+        if !self.channel_detection.locked {
+            self.channel_detection.restart();
+        }
+        self.seen_dropped = 0;
+        self.last_drop = None;
+        self.last_packet = Instant::now();
+        self.baud_mismatch = BaudMismatch::default();
+        self.health_window = HealthWindow::default();
+        self.connecting = true;
+    }
+
+    /// Finish connecting once the serial thread has opened the port or given up,
+    /// and notice when an open connection drops out
+    fn step_connection(&mut self) {
+        if self.connected {
+            let closed = self
+                .serial_shared
+                .state
+                .read()
+                .is_ok_and(|state| matches!(*state, ConnectionState::Closed));
+            if closed && !self.serial_shared.stop.load(Ordering::Relaxed) {
+                self.handle_dropout();
+            }
+            return;
+        }
+        if !self.connecting {
+            if self.reconnect_at.is_some_and(|at| Instant::now() >= at) {
+                self.follow_device();
+                self.connect(true);
+            }
+            return;
+        }
+        let state = Arc::clone(&self.serial_shared.state);
+        let Ok(state) = state.read() else {
+            return;
+        };
+        match &*state {
+            ConnectionState::Connecting => return,
+            ConnectionState::Connected(reported) => {
+                let reconnected = self.reconnect_at.take().is_some();
+                if reconnected {
+                    info!("Reconnected to port: {}", self.source_name());
+                    self.log
+                        .push(format!("Reconnected to port: {}", self.source_name()));
+                } else {
+                    info!("Connected to port: {}", self.source_name());
+                    self.log
+                        .push(format!("Connected to port: {}", self.source_name()));
+                }
+                self.port_info = Some(format!("Requested {}\n{}", self.source(), reported));
+                if self.source_kind == SourceKind::Serial {
+                    self.device_identity = UsbIdentity::of(&self.current_port.path);
+                    match PortLock::acquire(&self.current_port.path) {
+                        Ok(lock) => self.port_lock = Some(lock),
+                        Err(e) => warn!("Couldn't lock the port for other windows, because: {}", e),
+                    }
+                }
+                self.connected = true;
+                self.last_sent = Instant::now();
+                self.keep_alives_sent = 0;
+                // A device that came back already had its commands discovered
+                if self.discovery.on_connect && !reconnected {
+                    self.discover_commands();
+                }
+            }
+            ConnectionState::Error(_) if self.reconnect_at.is_some() => {
+                // Still gone, the dropout was already reported
+                self.drop_serial_thread();
+                self.reconnect_at = Some(Instant::now() + RECONNECT_INTERVAL);
+            }
+            ConnectionState::Error(e) => {
+                self.report_error(e);
+                self.notice = Some(format!(
+                    "Couldn't connect to '{}', see Log",
+                    self.source_name()
+                ));
+                self.drop_serial_thread();
+            }
+            ConnectionState::Closed => self.drop_serial_thread(),
+        }
+        self.connecting = false;
+    }
+
+    /// Point the port at wherever the USB adapter that dropped out is now,
+    /// in case it came back under another path
+    fn follow_device(&mut self) {
+        if !self.reconnect_by_identity || self.source_kind != SourceKind::Serial {
+            return;
+        }
+        let Some(identity) = &self.device_identity else {
+            return;
+        };
+        let Some(path) = identity.find() else {
+            return;
+        };
+        if path != self.current_port.path {
+            info!("Found device {} again at {}", identity, path);
+            self.log
+                .push(format!("Found device {} again at {}", identity, path));
+            self.current_port.path = path;
+        }
+    }
+
+    /// The serial thread ended by itself, so the source went away
+    fn handle_dropout(&mut self) {
+        self.drop_serial_thread();
+        self.connected = false;
+        warn!("Lost connection to port: {}", self.source_name());
+        self.log
+            .push(format!("Lost connection to port: {}", self.source_name()));
+        if !self.keep_on_reconnect {
+            return;
+        }
+        let last = self
+            .plot_data
+            .iter()
+            .filter_map(|m| m.values.back())
+            .map(|p| p.x)
+            .reduce(f64::max);
+        if let Some(x) = last {
+            // A NaN breaks every line at the dropout
+            for measurement in self.plot_data.iter_mut() {
+                measurement.add([x, f64::NAN].into());
+            }
+            self.dropouts.push(x);
+        }
+        self.reconnect_at = Some(Instant::now() + RECONNECT_INTERVAL);
+    }
+
+    /// Forget the serial thread, it stops on its own once it sees the stop flag
+    fn drop_serial_thread(&mut self) {
+        self.port_lock = None;
+        self.serial_shared.stop.store(true, Ordering::Relaxed);
+        self.serial_handle = None;
+        self.command_tx = None;
+    }
+
+    fn disconnect(&mut self) {
+        self.drop_serial_thread();
+        self.macro_run = None;
+        self.line_run = None;
+        self.connected = false;
+        self.connecting = false;
+        self.reconnect_at = None;
+        info!("Disconnected from port: {}", self.source_name());
+        self.log
+            .push(format!("Disconnected from port: {}", self.source_name()));
+    }
+
+    /// Stop the serial thread and wait up to `timeout` for it to close the port
+    fn join_serial_thread(&mut self, timeout: Duration) {
+        self.serial_shared.stop.store(true, Ordering::Relaxed);
+        self.port_lock = None;
+        // Dropping the receiver ends the parse thread, whose receiver going
+        // away then also ends a serial thread that is between reads
+        self.parsed_rx = None;
+        self.command_tx = None;
+        let Some(handle) = self.serial_handle.take() else {
+            return;
+        };
+        let started = Instant::now();
+        while !handle.is_finished() {
+            if started.elapsed() >= timeout {
+                warn!("Serial thread did not stop within {:?}", timeout);
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        if handle.join().is_err() {
+            warn!("Serial thread panicked");
+        }
+    }
+
+    /// Queue `command` for the serial thread to write to the port
+    fn send_command(&mut self, command: &Command) -> bool {
+        let translation = match command.fmt {
+            true => NewlineTranslation::None,
+            false => self.newline_translation,
+        };
+        let mut writes = match command.writes() {
+            Ok(writes) => writes
+                .into_iter()
+                .map(|bytes| translation.apply(&bytes))
+                .collect::<Vec<_>>()
+                .into_iter(),
+            Err(e) => {
+                self.report_error(&e);
+                return false;
+            }
+        };
+        let Some(command_tx) = &self.command_tx else {
+            warn!("Couldn't send {}, because: not connected", command);
+            self.log
+                .push(format!("Couldn't send {}, because: not connected", command));
+            return false;
+        };
+        let delay = Duration::from_millis(command.line_delay_ms);
+        // Without pacing every line goes out right away, otherwise the rest waits its turn
+        let now: Vec<Vec<u8>> = match (delay.is_zero(), &mut self.line_run) {
+            (true, None) => writes.by_ref().collect(),
+            (false, None) => writes.next().into_iter().collect(),
+            (_, Some(_)) => Vec::new(),
+        };
+        if now
+            .into_iter()
+            .any(|bytes| command_tx.send(PortCommand::Write(bytes)).is_err())
+        {
+            warn!("Couldn't send {}, because: serial thread stopped", command);
+            self.log.push(format!(
+                "Couldn't send {}, because: serial thread stopped",
+                command
+            ));
+            return false;
+        }
+        let rest: Vec<_> = writes.map(|bytes| (bytes, delay)).collect();
+        if !rest.is_empty() {
+            self.line_run
+                .get_or_insert_with(|| LineRun {
+                    lines: VecDeque::new(),
+                    next_at: Instant::now() + delay,
+                })
+                .lines
+                .extend(rest);
+        }
+        info!("Sending Command {}", command);
+        self.log.push(format!("Sending Command {}", command));
+        self.last_sent = Instant::now();
+        self.remember_command(command);
+        if command.capture_reply {
+            self.reply = Some(Reply {
+                command: command.to_string(),
+                sent_at: Instant::now(),
+                lines: Vec::new(),
+                open: true,
+            });
+        }
+        true
+    }
+
+    /// Add `packet` to the reply being captured, if one still is
+    fn capture_reply(&mut self, packet: &Packet) {
+        let Some(reply) = self.reply.as_mut().filter(|reply| reply.open) else {
+            return;
+        };
+        reply.close_if_done(self.reply_window);
+        if reply.open {
+            reply.lines.push(packet.payload.clone());
+            reply.close_if_done(self.reply_window);
+        }
+    }
+
+    /// Stop capturing a reply once its time is up, even if nothing arrives
+    fn step_reply(&mut self) {
+        if let Some(reply) = self.reply.as_mut().filter(|reply| reply.open) {
+            reply.close_if_done(self.reply_window);
+        }
+        if self.discovering && self.reply.as_ref().is_some_and(|reply| !reply.open) {
+            self.discovering = false;
+            self.suggest_commands();
+        }
+    }
+
+    /// Send the discovery command, its reply is turned into suggested commands
+    fn discover_commands(&mut self) {
+        if self.discovery.command.trim().is_empty() {
+            return;
+        }
+        self.discovering = self.send_command(&self.discovery.to_command());
+        self.suggestions.clear();
+    }
+
+    /// Suggest the commands the reply to discovery lists, leaving out ones
+    /// already in the list
+    fn suggest_commands(&mut self) {
+        let command = self.discovery.to_command().to_string();
+        let Some(reply) = self.reply.as_ref().filter(|reply| reply.command == command) else {
+            // Another command's reply took its place
+            return;
+        };
+        self.suggestions = self
+            .discovery
+            .suggestions(&reply.lines, &self.commands)
+            .into_iter()
+            .map(|word| (word, true))
+            .collect();
+        if self.suggestions.is_empty() {
+            info!("No new commands in the reply to {}", self.discovery.command);
+            self.log.push(format!(
+                "No new commands in the reply to {}",
+                self.discovery.command
+            ));
+        } else {
+            info!(
+                "Found {} commands in the reply to {}",
+                self.suggestions.len(),
+                self.discovery.command
+            );
+            self.log.push(format!(
+                "Found {} commands in the reply to {}",
+                self.suggestions.len(),
+                self.discovery.command
+            ));
+        }
+    }
+
+    /// Put `command` at the front of the quick bar
+    fn remember_command(&mut self, command: &Command) {
+        // One click shouldn't be enough to resend a command that asks for confirmation
+        if command.confirm {
+            return;
+        }
+        self.recent_commands.retain(|c| c != command);
+        self.recent_commands.push_front(command.clone());
+        self.recent_commands.truncate(RECENT_COMMANDS);
+    }
+
+    /// Log `error` along with what the user could do about it
+    fn report_error(&mut self, error: &serialib::Error) {
+        warn!("{}", error);
+        self.log.push(error.to_string());
+        let hint = recovery_hint(error);
+        if let Some(hint) = hint {
+            self.log.push(format!("Hint: {}", hint));
+        }
+        self.last_error = Some(ErrorReport {
+            message: error.to_string(),
+            hint,
+            settings: self.error_settings(),
+        });
+    }
+
+    /// What a bug report needs to know about how the source was set up
+    fn error_settings(&self) -> Vec<String> {
+        let mut settings = vec![
+            format!("Source: {}", self.source()),
+            format!("Framing: {}", self.decoded_framing()),
+            format!(
+                "Max line: {} B ({}), timestamps: {:?}",
+                self.line_limit.max_len, self.line_limit.overlong, self.timestamping
+            ),
+            format!("Connect timeout: {:?}", self.connect_timeout),
+        ];
+        if let Some(identity) = &self.device_identity {
+            settings.push(format!("USB device: {}", identity));
+        }
+        if let Some(info) = &self.port_info {
+            settings.push(format!("Last opened: {}", info.replace('\n', ", ")));
+        }
+        settings
+    }
+
+    /// Ask the serial thread to hold the line in break for `break_duration`
+    fn send_break(&mut self) {
+        let Some(command_tx) = &self.command_tx else {
+            return;
+        };
+        let at = self.capture_duration().as_secs_f64();
+        if command_tx
+            .send(PortCommand::Break(self.break_duration))
+            .is_err()
+        {
+            warn!("Couldn't send break, because: serial thread stopped");
+            self.log.push(String::from(
+                "Couldn't send break, because: serial thread stopped",
+            ));
+            return;
+        }
+        info!(
+            "Sent break for {} ms at {:.3} s",
+            self.break_duration.as_millis(),
+            at
+        );
+        self.log.push(format!(
+            "Sent break for {} ms at {:.3} s",
+            self.break_duration.as_millis(),
+            at
+        ));
+    }
+
+    fn start_macro(&mut self, macro_idx: usize) {
+        info!("Running macro '{}'", self.macros[macro_idx].name);
+        self.log
+            .push(format!("Running macro '{}'", self.macros[macro_idx].name));
+        self.macro_run = Some(MacroRun::new(macro_idx));
+    }
+
+    fn abort_macro(&mut self) {
+        if let Some(run) = self.macro_run.take() {
+            info!("Aborted macro '{}'", self.macros[run.macro_idx].name);
+            self.log.push(format!(
+                "Aborted macro '{}'",
+                self.macros[run.macro_idx].name
+            ));
+        }
+    }
+
+    /// Listen at every rate in [`BAUD_RATES`] on a background thread
+    fn start_baud_detect(&mut self) {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (progress_tx, progress_rx) = mpsc::channel();
+        let device = self.current_port.clone();
+        let options = self.read_options();
+        let thread_cancel = cancel.clone();
+        thread::spawn(move || {
+            for baud_rate in BAUD_RATES {
+                if thread_cancel.load(Ordering::Relaxed) {
+                    break;
+                }
+                let probe_device = device.clone().baud_rate(baud_rate as usize);
+                let window = Duration::from_millis(500);
+                let probe = match serialib::score_baud_rate(&probe_device, window, &options) {
+                    Ok(score) => BaudProbe::Scored { baud_rate, score },
+                    Err(e) => BaudProbe::Failed {
+                        baud_rate,
+                        error: e.to_string(),
+                    },
+                };
+                if progress_tx.send(probe).is_err() {
+                    break;
+                }
+            }
+        });
+        self.baud_detect = Some(BaudDetect {
+            cancel,
+            progress_rx,
+            tried: 0,
+            best: None,
+        });
+        info!("Detecting baud rate on port: {}", self.current_port.path);
+        self.log.push(format!(
+            "Detecting baud rate on port: {}",
+            self.current_port.path
+        ));
+    }
+
+    fn cancel_baud_detect(&mut self) {
+        if let Some(detect) = self.baud_detect.take() {
+            detect.cancel.store(true, Ordering::Relaxed);
+            info!("Cancelled baud rate detection");
+            self.log.push(String::from("Cancelled baud rate detection"));
+        }
+    }
+
+    /// Log what the detection thread found and pick the best rate once it is done
+    fn step_baud_detect(&mut self) {
+        let Some(detect) = &mut self.baud_detect else {
+            return;
+        };
+        let finished = loop {
+            match detect.progress_rx.try_recv() {
+                Ok(BaudProbe::Scored { baud_rate, score }) => {
+                    detect.tried += 1;
+                    if detect.best.is_none_or(|(_, best)| score > best) {
+                        detect.best = Some((baud_rate, score));
+                    }
+                    info!("Baud rate {}: {} readable lines", baud_rate, score);
+                    self.log
+                        .push(format!("Baud rate {}: {} readable lines", baud_rate, score));
+                }
+                Ok(BaudProbe::Failed { baud_rate, error }) => {
+                    detect.tried += 1;
+                    warn!("Error probing baud rate {}, because: {}", baud_rate, error);
+                    self.log.push(format!(
+                        "Error probing baud rate {}, because: {}",
+                        baud_rate, error
+                    ));
+                }
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+        if !finished {
+            return;
+        }
+        match detect.best {
+            Some((baud_rate, score)) if score > 0 => {
+                self.current_port.baud_rate = baud_rate as usize;
+                info!("Detected baud rate: {}", baud_rate);
+                self.log.push(format!("Detected baud rate: {}", baud_rate));
+            }
+            _ => {
+                warn!("No baud rate produced readable lines");
+                self.log
+                    .push(String::from("No baud rate produced readable lines"));
+            }
+        }
+        self.baud_detect = None;
+    }
+
+    /// Listen on the selected port for [`PORT_TEST_WINDOW`] on a background
+    /// thread, without connecting
+    fn start_port_test(&mut self) {
+        let (result_tx, result_rx) = mpsc::channel();
+        let device = self.current_port.clone();
+        let options = self.read_options();
+        thread::spawn(move || {
+            let heard =
+                serialib::listen(&device, PORT_TEST_WINDOW, &options).map_err(|e| e.to_string());
+            let _ = result_tx.send(heard);
+        });
+        self.port_test = Some(result_rx);
+        self.port_test_result = None;
+        info!("Testing port: {}", self.current_port.path);
+        self.log
+            .push(format!("Testing port: {}", self.current_port.path));
+    }
+
+    /// Report what the port test heard once it is done
+    fn step_port_test(&mut self) {
+        let Some(result_rx) = &self.port_test else {
+            return;
+        };
+        let heard = match result_rx.try_recv() {
+            Ok(heard) => heard,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => Err(String::from("the test thread stopped")),
+        };
+        self.port_test = None;
+        let path = self.current_port.path.clone();
+        match heard {
+            Ok(bytes) if bytes.is_empty() => {
+                warn!("Port test of {}: nothing arrived", path);
+                self.log
+                    .push(format!("Port test of {}: nothing arrived", path));
+                self.port_test_result = Some(String::from("Nothing arrived"));
+            }
+            Ok(bytes) => {
+                let lines = bytes
+                    .split(|b| *b == b'\n')
+                    .filter(|l| !l.is_empty())
+                    .count();
+                let text = encode_escapes(&String::from_utf8_lossy(&bytes));
+                let sample: String = text.chars().take(PORT_TEST_SAMPLE_LEN).collect();
+                let summary = format!("{} bytes, {} lines: {}", bytes.len(), lines, sample);
+                info!("Port test of {}: {}", path, summary);
+                self.log.push(format!("Port test of {}: {}", path, summary));
+                self.port_test_result = Some(summary);
+            }
+            Err(e) => {
+                warn!("Error testing port {}, because: {}", path, e);
+                self.log
+                    .push(format!("Error testing port {}, because: {}", path, e));
+                self.port_test_result = Some(format!("Failed: {}", e));
+            }
+        }
+    }
+
+    /// Write the next waiting line of a multi-line command once its delay is up
+    fn step_lines(&mut self) {
+        let Some(run) = &mut self.line_run else {
+            return;
+        };
+        if Instant::now() < run.next_at {
+            return;
+        }
+        let Some((bytes, _)) = run.lines.pop_front() else {
+            self.line_run = None;
+            return;
+        };
+        // The pause before a line is the one its own command asked for
+        match run.lines.front() {
+            Some((_, delay)) => run.next_at = Instant::now() + *delay,
+            None => self.line_run = None,
+        }
+        let sent = self
+            .command_tx
+            .as_ref()
+            .is_some_and(|tx| tx.send(PortCommand::Write(bytes)).is_ok());
+        self.last_sent = Instant::now();
+        if !sent {
+            self.line_run = None;
+            warn!("Couldn't send the rest of the lines, because: serial thread stopped");
+            self.log.push(String::from(
+                "Couldn't send the rest of the lines, because: serial thread stopped",
+            ));
+        }
+    }
+
+    /// Send the keep-alive once nothing was written for its interval.
+    ///
+    /// Lines of a multi-line command still waiting go first, their pacing
+    /// isn't interrupted. Every send is counted rather than logged, it would
+    /// drown out everything else in the log.
+    fn step_keep_alive(&mut self) {
+        if !self.connected || self.line_run.is_some() {
+            return;
+        }
+        // The Commands panel points out what's wrong with a payload that isn't sent
+        let Some(bytes) = self.keep_alive.due(self.last_sent.elapsed()) else {
+            return;
+        };
+        self.last_sent = Instant::now();
+        let sent = self
+            .command_tx
+            .as_ref()
+            .is_some_and(|tx| tx.send(PortCommand::Write(bytes)).is_ok());
+        if sent {
+            self.keep_alives_sent += 1;
+        } else {
+            warn!("Couldn't send the keep-alive, because: serial thread stopped");
+            self.log.push(String::from(
+                "Couldn't send the keep-alive, because: serial thread stopped",
+            ));
+        }
+    }
+
+    /// Send the next macro step once the previous step's delay has passed
+    fn step_macro(&mut self) {
+        let Some(run) = &mut self.macro_run else {
+            return;
+        };
+        let macro_idx = run.macro_idx;
+        match run.next(&self.macros) {
+            MacroAction::Wait => {}
+            MacroAction::Send(command) => {
+                if !self.send_command(&command) {
+                    self.abort_macro();
+                }
+            }
+            MacroAction::Finished => {
+                info!("Finished macro '{}'", self.macros[macro_idx].name);
+                self.log
+                    .push(format!("Finished macro '{}'", self.macros[macro_idx].name));
+                self.macro_run = None;
+            }
+        }
+    }
+
+    fn title(&self) -> String {
+        if self.connected && self.source_kind != SourceKind::Serial {
+            format!("Symphony — {}", self.source_name())
+        } else if self.connected {
+            format!(
+                "Symphony — {} @ {}",
+                self.current_port.path, self.current_port.baud_rate
+            )
+        } else {
+            String::from("Symphony")
+        }
+    }
+
+    fn settings(&self) -> Settings {
+        Settings {
+            current_port: self.current_port.clone(),
+            presets: self.presets.clone(),
+            profiles: self.profiles.clone(),
+            commands: self.commands.clone(),
+            macros: self.macros.clone(),
+            rules: self.rules.clone(),
+            auto_connect: self.auto_connect,
+            reset_time_on_connect: self.reset_time_on_connect,
+            clear_on_connect: self.clear_on_connect,
+            keep_on_reconnect: self.keep_on_reconnect,
+            framing: self.framing.clone(),
+            channels: self.plot_options.channels.clone(),
+            plot_layout: self.plot_options.layout,
+            regression: self.plot_options.regression,
+            transform: self.plot_options.transform.clone(),
+            checksum: self.plot_options.checksum.clone(),
+            binary: self.plot_options.binary.clone(),
+            nmea: self.plot_options.nmea.clone(),
+            labeled: self.plot_options.labeled,
+            detect: self.plot_options.detect,
+            columns: self.plot_options.column_text.clone(),
+            line_width: self.plot_options.line_width,
+            anti_aliasing: self.plot_options.anti_aliasing,
+            refresh_hz: self.plot_options.refresh_hz,
+            grid: self.plot_options.grid,
+            drain_limit: self.drain_limit,
+            background_redraw: self.background_redraw,
+            source_kind: self.source_kind,
+            tcp_address: self.tcp_address.clone(),
+            file_path: self.file_path.clone(),
+            demo: self.demo.clone(),
+            timestamp_format: self.timestamp_format.clone(),
+            compact: self.compact,
+            reconnect_by_identity: self.reconnect_by_identity,
+            newline_translation: self.newline_translation,
+            clear_after_send: self.clear_after_send,
+            modbus_request: self.modbus_request.clone(),
+            reply_window: self.reply_window,
+            discovery: self.discovery.clone(),
+            keep_alive: self.keep_alive.clone(),
+            decimals: self.plot_options.decimals,
+            axis_labels: self.axis_labels.clone(),
+            range_locks: self.range_locks.clone(),
+            record: self.record_options.clone(),
+            health_thresholds: self.health_thresholds,
+            queue_capacity: self.queue_capacity,
+            parse_queue_capacity: self.parse_queue_capacity,
+            idle_timeout_secs: self.idle_timeout.as_secs(),
+            garbage_threshold: self.garbage_threshold,
+            connect_timeout_secs: self.connect_timeout.as_secs(),
+            max_idle_sleep_ms: self.max_idle_sleep.as_millis() as u64,
+            break_ms: self.break_duration.as_millis() as u64,
+            line_limit: self.line_limit,
+            merge_window_ms: self.merge_window.as_millis() as u64,
+            timestamping: self.timestamping,
+            ui: UiState {
+                open_panel: self.open_panel,
+                text_view: self.text_view_options.clone(),
+                histogram: self.histogram,
+                waterfall: self.waterfall,
+            },
+        }
+    }
+
+    /// Replace the current configuration with `settings`
+    fn apply_settings(&mut self, settings: Settings) {
+        let settings = settings.clamped();
+        self.current_port = settings.current_port;
+        self.presets = settings.presets;
+        self.profiles = settings.profiles;
+        self.commands = settings.commands;
+        self.selected_commands.clear();
+        self.macros = settings.macros;
+        self.rules = settings.rules;
+        self.rule_replied.clear();
+        self.auto_connect = settings.auto_connect;
+        self.reset_time_on_connect = settings.reset_time_on_connect;
+        self.clear_on_connect = settings.clear_on_connect;
+        self.keep_on_reconnect = settings.keep_on_reconnect;
+        self.framing = settings.framing;
+        self.plot_options.channels = settings.channels;
+        self.plot_options.layout = settings.plot_layout;
+        self.plot_options.regression = settings.regression;
+        self.plot_options.transform = settings.transform;
+        self.plot_options.checksum = settings.checksum;
+        self.plot_options.binary = settings.binary;
+        self.plot_options.nmea = settings.nmea;
+        self.plot_options.labeled = settings.labeled;
+        self.plot_options.detect = settings.detect;
+        self.plot_options.columns = parse_columns(&settings.columns).unwrap_or_default();
+        self.plot_options.column_text = settings.columns;
+        self.plot_options.line_width = settings.line_width;
+        self.plot_options.anti_aliasing = settings.anti_aliasing;
+        self.plot_options.refresh_hz = settings.refresh_hz;
+        self.plot_options.grid = settings.grid;
+        self.plot_options.decimals = settings.decimals;
+        self.drain_limit = settings.drain_limit;
+        self.background_redraw = settings.background_redraw;
+        self.source_kind = settings.source_kind;
+        self.tcp_address = settings.tcp_address;
+        self.file_path = settings.file_path;
+        self.demo = settings.demo;
+        self.timestamp_format = settings.timestamp_format;
+        self.compact = settings.compact;
+        self.reconnect_by_identity = settings.reconnect_by_identity;
+        self.newline_translation = settings.newline_translation;
+        self.clear_after_send = settings.clear_after_send;
+        self.modbus_request = settings.modbus_request;
+        self.reply_window = settings.reply_window;
+        self.discovery = settings.discovery;
+        self.keep_alive = settings.keep_alive;
+        self.axis_labels = settings.axis_labels;
+        self.range_locks = settings.range_locks;
+        self.record_options = settings.record;
+        self.health_thresholds = settings.health_thresholds;
+        self.queue_capacity = settings.queue_capacity;
+        self.parse_queue_capacity = settings.parse_queue_capacity;
+        self.idle_timeout = Duration::from_secs(settings.idle_timeout_secs);
+        self.garbage_threshold = settings.garbage_threshold;
+        self.connect_timeout = Duration::from_secs(settings.connect_timeout_secs);
+        self.max_idle_sleep = Duration::from_millis(settings.max_idle_sleep_ms);
+        self.break_duration = Duration::from_millis(settings.break_ms);
+        self.line_limit = settings.line_limit;
+        self.merge_window = Duration::from_millis(settings.merge_window_ms);
+        self.timestamping = settings.timestamping;
+    }
+
+    fn save_workspace(&self, path: &str) -> Result<(), String> {
+        let mut data = String::from("channel,x,y\n");
+        for (idx, measurement) in self.plot_data.iter().enumerate() {
+            for point in measurement.values.iter() {
+                data.push_str(&format!("{},{},{}\n", idx, point.x, point.y));
+            }
+        }
+        let workspace = Workspace {
+            version: WORKSPACE_VERSION,
+            settings: self.settings(),
+            data,
+        };
+        let json = serde_json::to_string_pretty(&workspace).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| e.to_string())
+    }
+
+    /// Restore the settings and samples saved in `path`, replacing the current capture
+    fn load_workspace(&mut self, path: &str) -> Result<(), String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let workspace: Workspace = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
+        if workspace.version > WORKSPACE_VERSION {
+            return Err(format!(
+                "it was saved by a newer Symphony (version {})",
+                workspace.version
+            ));
+        }
+        let plot_data = parse_samples(&workspace.data)?;
+        if self.connected || self.connecting {
+            self.disconnect();
+        }
+        self.apply_settings(workspace.settings);
+        self.show_samples(plot_data);
+        Ok(())
+    }
+
+    /// Replace the plot with samples written by [`Symphony::export_data`]
+    fn load_data(&mut self, path: &str) -> Result<(), String> {
+        let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+        let plot_data = if capture::is_capture(&bytes) {
+            capture_samples(&Capture::read(&bytes)?)
+        } else {
+            let contents = String::from_utf8(bytes)
+                .map_err(|_| String::from("it is neither CSV text nor a capture file"))?;
+            if !contents
+                .lines()
+                .next()
+                .is_some_and(|header| header.starts_with(DATA_HEADER))
+            {
+                return Err(format!(
+                    "it doesn't start with the '{}' header",
+                    DATA_HEADER
+                ));
+            }
+            parse_samples(&contents)?
+        };
+        if self.connected || self.connecting {
+            self.disconnect();
+        }
+        self.show_samples(plot_data);
+        Ok(())
+    }
+
+    /// Plot `plot_data` instead of the current capture
+    fn show_samples(&mut self, mut plot_data: Vec<Measurement>) {
+        let regression = self.plot_options.regression;
+        plot_data.iter_mut().for_each(|m| m.regression = regression);
+        self.plot_data = plot_data;
+        self.column_names.clear();
+        self.text_data.clear();
+        self.dropouts.clear();
+        self.bookmarks.clear();
+    }
+
+    /// Load every `.csv` file dropped onto the window
+    fn load_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped = ctx.input(|i| i.raw.dropped_files.clone());
+        for path in dropped.into_iter().filter_map(|file| file.path) {
+            let name = path.display().to_string();
+            let result = if path
+                .extension()
+                .is_some_and(|ext| ext == "csv" || ext == capture::EXTENSION)
+            {
+                self.load_data(&name)
+            } else {
+                Err(format!(
+                    "only .csv and .{} files can be dropped",
+                    capture::EXTENSION
+                ))
+            };
+            match result {
+                Ok(()) => {
+                    info!("Loaded data from {}", name);
+                    self.log.push(format!("Loaded data from {}", name));
+                }
+                Err(e) => {
+                    warn!("Error loading {}, because: {}", name, e);
+                    self.log
+                        .push(format!("Error loading {}, because: {}", name, e));
+                    self.notice = Some(format!("Couldn't load {}, see Log", name));
+                }
+            }
+        }
+    }
+
+    /// Write the settings to `settings_path`, returning whether that worked
+    fn save_settings(&mut self) -> bool {
+        let Some(path) = &self.settings_path else {
+            warn!("Couldn't save settings, because: no settings location");
+            return false;
+        };
+        if let Err(e) = settings::save(path, &self.settings()) {
+            warn!("Couldn't save settings, because: {}", e);
+            self.log
+                .push(format!("Couldn't save settings, because: {}", e));
+            return false;
+        }
+        true
+    }
+
+    /// Start on the defaults without saving over a settings file that didn't
+    /// load. It's kept under another name, or if that fails not saved this session.
+    fn keep_unloaded_settings(&mut self, error: anyhow::Error) {
+        warn!("Couldn't load settings, because: {}", error);
+        self.log
+            .push(format!("Couldn't load settings, because: {}", error));
+        let Some(path) = &self.settings_path else {
+            return;
+        };
+        match settings::back_up(path) {
+            Ok(backup) => {
+                info!("Kept the settings that didn't load at {}", backup.display());
+                self.log.push(format!(
+                    "Kept the settings that didn't load at {}",
+                    backup.display()
+                ));
+                self.notice = Some(format!(
+                    "The settings didn't load, the old file is kept at {}",
+                    backup.display()
+                ));
+            }
+            Err(e) => {
+                warn!("Couldn't keep the settings aside, because: {}", e);
+                self.log
+                    .push(format!("Couldn't keep the settings aside, because: {}", e));
+                self.settings_path = None;
+                self.notice = Some(String::from(
+                    "The settings didn't load and won't be saved this session, see Log",
+                ));
+            }
+        }
+    }
+
+    /// Save the settings once they have stopped changing for [`AUTOSAVE_DELAY`]
+    fn autosave(&mut self) {
+        let current = self.settings();
+        if current != self.last_settings {
+            self.last_settings = current;
+            self.dirty = true;
+            self.changed_at = Instant::now();
+        }
+        if self.dirty && self.changed_at.elapsed() >= AUTOSAVE_DELAY {
+            if self.save_settings() {
+                trace!("Saved settings");
+                self.dirty = false;
+            } else {
+                // Try again after another delay rather than every frame
+                self.changed_at = Instant::now();
+            }
+        }
+    }
+
+    /// What connecting opens
+    fn source(&self) -> Source {
+        match self.source_kind {
+            SourceKind::Serial => Source::Serial(self.current_port.clone()),
+            SourceKind::Tcp => Source::Tcp(self.tcp_address.clone()),
+            SourceKind::Demo => Source::Demo(self.demo.clone()),
+            SourceKind::File => Source::File(PathBuf::from(&self.file_path)),
+        }
+    }
+
+    /// Why the selected source can't be opened yet, `None` once it names
+    /// something to open and the framing can split what it sends
+    fn missing_source(&self) -> Option<String> {
+        if let Framing::Custom(terminator) = &self.framing {
+            if terminator.is_empty() {
+                return Some(String::from("Enter a terminator first"));
+            }
+            if let Err(e) = decode_escapes(terminator) {
+                return Some(format!("Invalid terminator: {}", e));
+            }
+        }
+        let (name, missing) = match self.source_kind {
+            SourceKind::Serial => (&self.current_port.path, "Select a port first"),
+            SourceKind::Tcp => (&self.tcp_address, "Enter an address first"),
+            SourceKind::File => (&self.file_path, "Enter a file path first"),
+            SourceKind::Demo => return None,
+        };
+        name.trim().is_empty().then(|| String::from(missing))
+    }
+
+    /// Port path, TCP address or file path of the selected source
+    fn source_name(&self) -> &str {
+        match self.source_kind {
+            SourceKind::Serial => &self.current_port.path,
+            SourceKind::Tcp => &self.tcp_address,
+            SourceKind::Demo => "Demo",
+            SourceKind::File => &self.file_path,
+        }
+    }
+
+    /// Axis labels for the selected port
+    fn axis_labels(&self) -> AxisLabels {
+        self.axis_labels
+            .get(self.source_name())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Locked y ranges of the channels of the selected port
+    fn range_locks(&self) -> BTreeMap<usize, [f64; 2]> {
+        self.range_locks
+            .get(self.source_name())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Y range autoscaling makes room for, the union of every auto-ranged
+    /// analog channel's values within `x` or its locked range
+    fn autoscale_range(&self, analog: &[usize], x: &RangeInclusive<f64>) -> Option<[f64; 2]> {
+        let locks = self.range_locks();
+        let (lo, hi) = analog
+            .iter()
+            .filter(|idx| {
+                self.plot_options
+                    .channels
+                    .get(**idx)
+                    .is_none_or(|channel| channel.auto_range)
+            })
+            .filter_map(|idx| {
+                locks.get(idx).copied().or_else(|| {
+                    self.plot_data[*idx]
+                        .stats_within(x)
+                        .map(|stats| [stats.min, stats.max])
+                })
+            })
+            .fold(
+                (f64::INFINITY, f64::NEG_INFINITY),
+                |(lo, hi), [min, max]| (lo.min(min), hi.max(max)),
+            );
+        if lo > hi {
+            return None;
+        }
+        // Leave a little room above and below, and some height for a flat line
+        let margin = match hi - lo {
+            0. => 0.5,
+            span => span * 0.05,
+        };
+        Some([lo - margin, hi + margin])
+    }
+
+    /// Y range of each of `lanes` to show with autoscaling on, refitted as
+    /// often as `rescale` says
+    fn autoscale_bounds(&mut self, lanes: &[Vec<usize>]) -> Vec<Option<[f64; 2]>> {
+        let due = match (self.plot_options.rescale, &self.autoscale_y) {
+            (_, None) | (Rescale::EveryFrame, _) => true,
+            // Lanes came or went, or had nothing to fit yet
+            (_, Some((ranges, _)))
+                if ranges.len() != lanes.len() || ranges.iter().all(Option::is_none) =>
+            {
+                true
+            }
+            (Rescale::Periodic, Some((_, at))) => at.elapsed() >= RESCALE_INTERVAL,
+            (Rescale::Manual, Some(_)) => false,
+        };
+        let requested = std::mem::take(&mut self.rescale_now);
+        if due || requested {
+            let visible = self
+                .visible_x
+                .clone()
+                .unwrap_or(f64::NEG_INFINITY..=f64::INFINITY);
+            let ranges = lanes
+                .iter()
+                .map(|channels| self.autoscale_range(channels, &visible))
+                .collect();
+            self.autoscale_y = Some((ranges, Instant::now()));
+        }
+        self.autoscale_y
+            .as_ref()
+            .map(|(ranges, _)| ranges.clone())
+            .unwrap_or_default()
+    }
+
+    /// Parse settings from the Plot panel, for the parse thread
+    fn parser_settings(&self) -> Parser {
+        Parser {
+            framing: self.session_framing.clone(),
+            delimiter: self.plot_options.delimiter.pattern().ok(),
+            transform: self.plot_options.transform.clone(),
+            checksum: self.plot_options.checksum.clone(),
+            binary: self.plot_options.binary.clone(),
+            nmea: self.plot_options.nmea.clone(),
+            labeled: self.plot_options.labeled,
+        }
+    }
+
+    /// Move up to `drain_limit` parsed packets into the plot, returning
+    /// whether the limit was hit and more are probably waiting
+    fn read_packets(&mut self) -> bool {
+        // Settings changed in the Plot panel apply to the packets parsed from now on
+        let settings = self.parser_settings();
+        if self.parser.read().is_ok_and(|parser| *parser != settings) {
+            if let Ok(mut parser) = self.parser.write() {
+                *parser = settings;
+            }
+        }
+        let Some(parsed_rx) = &self.parsed_rx else {
+            return false;
+        };
+        let limit = self.drain_limit.max(1);
+        let packets: Vec<ParsedPacket> = parsed_rx.try_iter().take(limit).collect();
+        let backlog = packets.len() == limit;
+        if !packets.is_empty() {
+            self.last_packet = Instant::now();
+        }
+        packets
+            .iter()
+            .for_each(|parsed| self.throughput.record(parsed.packet.payload.len()));
+        self.throughput.update_rates();
+        let stream = self.serial_shared.take_stream();
+        // Binary framings are mostly unprintable by design
+        let text = !matches!(
+            self.session_framing,
+            Framing::Fixed { .. } | Framing::ModbusRtu
+        );
+        if text && self.source_kind == SourceKind::Serial {
+            self.baud_mismatch.record(&stream, self.garbage_threshold);
+        }
+        // Rules keep answering the device while the display is paused
+        packets
+            .iter()
+            .for_each(|parsed| self.apply_rules(&parsed.packet));
+        packets
+            .iter()
+            .for_each(|parsed| self.capture_reply(&parsed.packet));
+        if self.paused_at.is_none() {
+            packets
+                .into_iter()
+                .for_each(|parsed| self.add_parsed(parsed));
+            self.raw_data
+                .push(&stream, self.text_view_options.raw_kib * 1024);
+        } else {
+            // So the text after resuming starts on a character
+            self.raw_data.skip(&stream);
+        }
+        backlog
+    }
+
+    fn health_counts(&self) -> HealthCounts {
+        HealthCounts {
+            packets: self.throughput.total_packets,
+            parse_errors: self.parse_errors,
+            bad_checksums: self.bad_checksums,
+            dropped: self.seen_dropped,
+        }
+    }
+
+    /// Overall health of the connection over the health window, with how
+    /// every diagnostic it is made of rates
+    fn health(&self) -> (Health, Vec<(Health, String)>) {
+        let delta = self.health_window.delta(self.health_counts());
+        let mut parts = delta.rate(&self.health_thresholds);
+        if self.source_kind == SourceKind::Serial {
+            parts.extend(self.baud_mismatch.rate(self.garbage_threshold));
+        }
+        parts.push(Health::idle(self.last_packet.elapsed(), self.idle_timeout));
+        (Health::overall(&parts), parts)
+    }
+
+    /// Log a summary of the data quality once every summary interval while connected
+    fn step_summary(&mut self) {
+        let options = &self.record_options.summary;
+        let interval = Duration::from_secs(options.interval_secs.max(1));
+        if !options.enabled || !self.connected || self.last_summary.elapsed() < interval {
+            return;
+        }
+        self.last_summary = Instant::now();
+        let means: Vec<(usize, Option<f64>)> = self
+            .plot_data
+            .iter()
+            .enumerate()
+            .map(|(idx, data)| (idx, data.stats().map(|stats| stats.mean)))
+            .collect();
+        let decimals = self.plot_options.decimals;
+        let channels: Vec<String> = means
+            .iter()
+            .map(|(idx, mean)| match mean {
+                Some(mean) => format!("channel {} mean {:.*}", idx, decimals, mean),
+                None => format!("channel {} empty", idx),
+            })
+            .collect();
+        let mut line = format!(
+            "Summary: {} packets, {} parse errors, {} bad checksums",
+            self.throughput.total_packets, self.parse_errors, self.bad_checksums
+        );
+        if !channels.is_empty() {
+            line = format!("{}, {}", line, channels.join(", "));
+        }
+        info!("{}", line);
+        self.log.push(line);
+        if self.record_options.summary.csv {
+            let path = self.record_options.summary.csv_path.clone();
+            if let Err(e) = self.append_summary(&path, &means) {
+                warn!("Error writing the summary to {}, because: {}", path, e);
+                self.log.push(format!(
+                    "Error writing the summary to {}, because: {}",
+                    path, e
+                ));
+            }
+        }
+    }
+
+    /// Append one row per channel to the summary CSV at `path`, with a header
+    /// if the file is new
+    fn append_summary(&self, path: &str, means: &[(usize, Option<f64>)]) -> io::Result<()> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        let new = file.metadata()?.len() == 0;
+        let mut file = BufWriter::new(file);
+        if new {
+            writeln!(
+                file,
+                "timestamp,time,packets,parse_errors,bad_checksums,channel,mean"
+            )?;
+        }
+        let elapsed = self.capture_duration().as_secs_f64();
+        let wall = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        let timestamp = timefmt::format_or_default(&self.timestamp_format, wall.as_millis());
+        let prefix = format!(
+            "\"{}\",{},{},{},{}",
+            timestamp.replace('"', "\"\""),
+            elapsed,
+            self.throughput.total_packets,
+            self.parse_errors,
+            self.bad_checksums
+        );
+        if means.is_empty() {
+            writeln!(file, "{},,", prefix)?;
+        }
+        for (idx, mean) in means {
+            let mean = mean.map(|mean| mean.to_string()).unwrap_or_default();
+            writeln!(file, "{},Channel {},{}", prefix, idx, mean)?;
+        }
+        file.flush()
+    }
+
+    /// Carry out the action of every rule `packet` matches
+    fn apply_rules(&mut self, packet: &Packet) {
+        for idx in rules::matching(&self.rules, &packet.payload) {
+            let rule = self.rules[idx].clone();
+            match rule.action {
+                RuleAction::Log => {
+                    info!("Rule '{}' matched: {}", rule.pattern, packet.payload);
+                    self.log.push(format!(
+                        "Rule '{}' matched: {}",
+                        rule.pattern, packet.payload
+                    ));
+                }
+                RuleAction::Bookmark => {
+                    let x = self.packet_time(packet).as_secs_f64();
+                    self.bookmarks.push((x, rule.pattern));
+                }
+                RuleAction::Send => {
+                    if !self.rule_replied.allow(idx, Instant::now()) {
+                        warn!(
+                            "Rule '{}' held back a reply, it just sent one",
+                            rule.pattern
+                        );
+                        self.log.push(format!(
+                            "Rule '{}' held back a reply, it just sent one",
+                            rule.pattern
+                        ));
+                        continue;
+                    }
+                    self.send_command(&rule.reply);
+                }
+            }
+        }
+    }
+
+    /// Notice the serial thread dropping packets since the last frame
+    fn check_lag(&mut self) {
+        let dropped = self.serial_shared.dropped.load(Ordering::Relaxed);
+        if dropped <= self.seen_dropped {
+            return;
+        }
+        if !self.is_lagging() {
+            warn!("Receiver lagging, {} packets dropped so far", dropped);
+            self.log.push(format!(
+                "Receiver lagging, {} packets dropped so far",
+                dropped
+            ));
+        }
+        self.seen_dropped = dropped;
+        self.last_drop = Some(Instant::now());
+    }
+
+    /// Count the Modbus frames the serial thread dropped for a bad CRC since the last frame
+    fn check_bad_frames(&mut self) {
+        let bad = self.serial_shared.bad_frames.swap(0, Ordering::Relaxed);
+        if bad == 0 {
+            return;
+        }
+        self.bad_checksums += bad;
+        self.log
+            .push(format!("Dropped {} Modbus frames with a bad CRC", bad));
+    }
+
+    /// Warn about text packets the serial thread cut off at the line limit
+    fn check_overlong(&mut self) {
+        let cut = self.serial_shared.overlong.swap(0, Ordering::Relaxed);
+        if cut == 0 {
+            return;
+        }
+        let fate = match self.line_limit.overlong {
+            Overlong::Emit => "passed on as they were",
+            Overlong::Drop => "dropped",
+        };
+        let message = format!(
+            "{} packets reached {} bytes without a terminator and were {}",
+            cut, self.line_limit.max_len, fate
+        );
+        warn!("{}", message);
+        self.log.push(message);
+    }
+
+    /// Whether packets were dropped recently enough that the plot is known to have gaps
+    fn is_lagging(&self) -> bool {
+        self.last_drop
+            .is_some_and(|at| at.elapsed() < Duration::from_secs(2))
+    }
+
+    /// Put the readings of a packet the parse thread is done with into channels
+    fn add_parsed(&mut self, parsed: ParsedPacket) {
+        let ParsedPacket { packet, parsed } = parsed;
+        let time = self.packet_time(&packet);
+        self.text_data.push(TextLine {
+            time,
+            wall: packet.absolute_time,
+            text: packet.payload,
+        });
+        let x = time.as_secs_f64();
+        let readings = match parsed {
+            Parsed::Line(readings) => {
+                let empty = readings.iter().filter(|r| **r == Reading::Empty).count();
+                self.token_counts.record(readings.len(), empty);
+                self.detect_channels(&readings);
+                readings
+            }
+            Parsed::Labeled(tokens) => self.labeled_columns(tokens),
+            Parsed::Fields(readings) => readings,
+            Parsed::BadChecksum(kind) => {
+                self.bad_checksums += 1;
+                self.log
+                    .push(format!("Dropped a {} with a bad checksum", kind));
+                return;
+            }
+            Parsed::Exception(e) => {
+                warn!("Modbus exception, because: {}", e);
+                self.log.push(format!("Modbus exception, because: {}", e));
+                return;
+            }
+            Parsed::Ignored => return,
+        };
+        let detected = match self.plot_options.detect.enabled {
+            true => self.channel_detection.detected,
+            false => None,
+        };
+        for (column, reading) in readings.into_iter().enumerate() {
+            // Columns past the detected count are stray, like a line run into the next
+            if detected.is_some_and(|count| column >= count) {
+                break;
+            }
+            // Columns that aren't selected aren't plotted, a selected one the line lacks is skipped
+            let Some(idx) = self.channel_of(column) else {
+                continue;
+            };
+            match reading {
+                Reading::Number { value, integer } => self.add_sample(idx, x, value, integer),
+                Reading::Invalid | Reading::Empty => {
+                    self.parse_errors += usize::from(reading == Reading::Invalid);
+                    // A column that is missing its value leaves a gap in a known channel
+                    if let Some(measurement) = self.plot_data.get_mut(idx) {
+                        measurement.add([x, f64::NAN].into());
+                    }
+                }
+                Reading::Absent => {}
+            }
+        }
+    }
+
+    /// Readings of a labeled line by source column, giving every name that
+    /// wasn't seen before the next free column
+    fn labeled_columns(&mut self, tokens: Vec<(String, Reading)>) -> Vec<Reading> {
+        let mut readings = Vec::new();
+        for (name, reading) in tokens {
+            let column = match self.column_names.iter().position(|n| *n == name) {
+                Some(column) => column,
+                None => {
+                    info!("Plotting '{}' as column {}", name, self.column_names.len());
+                    self.log.push(format!(
+                        "Plotting '{}' as column {}",
+                        name,
+                        self.column_names.len()
+                    ));
+                    self.column_names.push(name);
+                    self.column_names.len() - 1
+                }
+            };
+            if readings.len() <= column {
+                readings.resize(column + 1, Reading::Absent);
+            }
+            readings[column] = reading;
+        }
+        readings
+    }
+
+    /// Count the columns of a line that parsed towards the detected channel
+    /// count, and make that many channels once it is known
+    fn detect_channels(&mut self, readings: &[Reading]) {
+        if !self.plot_options.detect.enabled || readings.contains(&Reading::Invalid) {
+            return;
+        }
+        // Trailing empty tokens, like from a delimiter at the end of the line, aren't columns
+        let Some(last) = readings
+            .iter()
+            .rposition(|reading| matches!(reading, Reading::Number { .. }))
+        else {
+            return;
+        };
+        let window = self.plot_options.detect.lines;
+        let Some(count) = self.channel_detection.record(last + 1, window) else {
+            return;
+        };
+        let channels = match self.plot_options.columns.is_empty() {
+            true => count,
+            false => self
+                .plot_options
+                .columns
+                .iter()
+                .filter(|column| **column < count)
+                .count(),
+        };
+        self.ensure_channels(channels);
+        self.plot_data.truncate(channels);
+        info!("Detected {} channels", channels);
+        self.log.push(format!("Detected {} channels", channels));
+    }
+
+    /// NMEA field channel `idx` plots, when sentences are parsed
+    fn nmea_field(&self, idx: usize) -> Option<nmea::Field> {
+        if !self.plot_options.nmea.enabled {
+            return None;
+        }
+        let column = self.plot_options.columns.get(idx).copied().unwrap_or(idx);
+        self.plot_options.nmea.fields.get(column).copied()
+    }
+
+    /// Name of channel `idx` in the plot legend
+    fn line_name(&self, idx: usize) -> String {
+        if let Some(name) = self.column_name(idx) {
+            return String::from(name);
+        }
+        match self.nmea_field(idx) {
+            Some(field) => field.to_string(),
+            None => format!("Channel {}", idx),
+        }
+    }
+
+    /// Name the labeled lines gave the source column of channel `idx`
+    fn column_name(&self, idx: usize) -> Option<&str> {
+        if !self.plot_options.labeled {
+            return None;
+        }
+        let column = match self.plot_options.columns.is_empty() {
+            true => idx,
+            false => *self.plot_options.columns.get(idx)?,
+        };
+        self.column_names.get(column).map(String::as_str)
+    }
+
+    /// Channel the source `column` is plotted as, `None` if it isn't selected
+    fn channel_of(&self, column: usize) -> Option<usize> {
+        match self.plot_options.columns.is_empty() {
+            true => Some(column),
+            false => self.plot_options.columns.iter().position(|c| *c == column),
+        }
+    }
+
+    /// Name of channel `idx` in tables and exports, with its source column if
+    /// columns are selected
+    fn channel_label(&self, idx: usize) -> String {
+        if let Some(name) = self.column_name(idx) {
+            return format!("Channel {} ({})", idx, name);
+        }
+        if let Some(field) = self.nmea_field(idx) {
+            return format!("Channel {} ({})", idx, field);
+        }
+        match self.plot_options.columns.get(idx) {
+            Some(column) => format!("Channel {} (column {})", idx, column),
+            None => format!("Channel {}", idx),
+        }
+    }
+
+    /// Scale `value` for channel `idx` and add it at `x`, creating the channel if needed
+    fn add_sample(&mut self, idx: usize, x: f64, value: f64, integer: bool) {
+        self.unexported = true;
+        self.ensure_channels(idx + 1);
+        self.plot_data[idx].max_samples = self.plot_options.buffer_size;
+        self.plot_data[idx].unlimited = self.plot_options.unlimited;
+        let value = self.plot_options.channels[idx].scale(value);
+        self.plot_data[idx].integer &= integer;
+        self.plot_data[idx].add([x, value].into());
+    }
+
+    /// Make sure there are at least `count` channels, with options for each
+    fn ensure_channels(&mut self, count: usize) {
+        if self.plot_data.len() < count {
+            let regression = self.plot_options.regression;
+            self.plot_data.resize_with(count, || Measurement {
+                regression,
+                ..Measurement::new_with_look_behind(LOOK_BEHIND)
+            });
+        }
+        if self.plot_options.channels.len() < count {
+            self.plot_options
+                .channels
+                .resize_with(count, ChannelOptions::default);
+        }
+    }
+
+    /// Whether readouts for `channel` should be shown in hex
+    fn shows_hex(&self, channel: usize) -> bool {
+        let base = self
+            .plot_options
+            .channels
+            .get(channel)
+            .map(|c| c.base)
+            .unwrap_or_default();
+        base == DisplayBase::Hexadecimal && self.plot_data[channel].integer
+    }
+
+    fn is_digital(&self, channel: usize) -> bool {
+        let kind = self
+            .plot_options
+            .channels
+            .get(channel)
+            .map(|c| c.kind)
+            .unwrap_or_default();
+        match kind {
+            SignalKind::Auto => self.plot_data[channel].is_boolean(),
+            SignalKind::Analog => false,
+            SignalKind::Digital => true,
+        }
+    }
+
+    /// Whether channel `idx` is drawn at all
+    fn is_shown(&self, idx: usize) -> bool {
+        self.plot_options
+            .channels
+            .get(idx)
+            .is_none_or(|channel| channel.visible)
+    }
+
+    /// Analog channels that are drawn, in order
+    fn shown_analog(&self) -> Vec<usize> {
+        (0..self.plot_data.len())
+            .filter(|idx| self.is_shown(*idx) && !self.is_digital(*idx))
+            .collect()
+    }
+
+    /// Whether the lane of `channels` has a log y axis, which takes every one
+    /// of them asking for it since they share the axis
+    fn log_y(&self, channels: &[usize]) -> bool {
+        !channels.is_empty()
+            && channels.iter().all(|idx| {
+                self.plot_options
+                    .channels
+                    .get(*idx)
+                    .is_some_and(|channel| channel.log_y)
+            })
+    }
+
+    /// The analog channels drawn in each lane, all in one lane unless stacked
+    fn lanes(&self, analog: &[usize]) -> Vec<Vec<usize>> {
+        match self.plot_options.layout {
+            PlotLayout::Stacked if !analog.is_empty() => {
+                analog.iter().map(|idx| vec![*idx]).collect()
+            }
+            _ => vec![analog.to_vec()],
+        }
+    }
+
+    fn draw_plot(&mut self, ui: &mut egui::Ui) {
+        let (digital, analog): (Vec<usize>, Vec<usize>) = (0..self.plot_data.len())
+            .filter(|idx| self.is_shown(*idx))
+            .partition(|idx| self.is_digital(*idx));
+        let lanes = self.lanes(&analog);
+        let labels = self.axis_labels();
+        let autoscale_y = match self.plot_options.autoscale {
+            true => self.autoscale_bounds(&lanes),
+            false => vec![None; lanes.len()],
+        };
+        let height = match digital.is_empty() {
+            true => ui.available_height(),
+            false => 0.65 * ui.available_height(),
+        } / lanes.len() as f32;
+        let mut skipped = 0;
+        let mut cursors = self.cursors;
+        let mut hovered = false;
+        let mut shown: Option<egui_plot::PlotBounds> = None;
+        let last = lanes.len() - 1;
+        for (lane, (channels, autoscale_y)) in lanes.iter().zip(autoscale_y).enumerate() {
+            let log = self.log_y(channels);
+            let y_label = match (self.plot_options.layout, channels.as_slice()) {
+                (PlotLayout::Stacked, [idx]) => self.line_name(*idx),
+                _ => labels.y.clone(),
+            };
+            let mut plot = self
+                .analog_plot(lane, log, &labels)
+                .height(height)
+                .y_axis_label(y_label);
+            // The lanes share one time axis, labelled under the bottom one
+            if lane == last {
+                plot = plot.x_axis_label(labels.x.clone());
+            }
+            let autoscale_y = match (log, autoscale_y) {
+                (true, Some([_, hi])) if hi <= 0. => None,
+                (true, Some([lo, hi])) => {
+                    let top = hi.log10();
+                    let bottom = match lo > 0. {
+                        true => lo.log10(),
+                        false => top - LOG_DECADES,
+                    };
+                    Some([bottom, top])
+                }
+                (_, range) => range,
+            };
+            let response = plot.show(ui, |plot_ui| {
+                if cursors.enabled {
+                    cursors.update(plot_ui);
+                    hovered |= cursors.hovered;
+                }
+                self.draw_markers(plot_ui);
+                self.draw_analog(plot_ui, channels, log, &mut skipped);
+                if let Some([lo, hi]) = autoscale_y {
+                    let bounds = plot_ui.plot_bounds();
+                    let follow_x = plot_ui.auto_bounds().x;
+                    plot_ui.set_plot_bounds(egui_plot::PlotBounds::from_min_max(
+                        [bounds.min()[0], lo],
+                        [bounds.max()[0], hi],
+                    ));
+                    plot_ui.set_auto_bounds([follow_x, false].into());
+                }
+            });
+            let bounds = *response.transform.bounds();
+            match shown.as_mut() {
+                Some(shown) => shown.merge_y(&bounds),
+                None => shown = Some(bounds),
+            }
+        }
+        cursors.hovered = hovered;
+        if let Some(bounds) = shown {
+            self.visible_x = Some(bounds.min()[0]..=bounds.max()[0]);
+            self.visible_y = Some(bounds.min()[1]..=bounds.max()[1]);
+        }
+        self.cursors = cursors;
+        self.log_skipped = skipped;
+
+        if !digital.is_empty() {
+            self.draw_logic_lanes(ui, &digital);
+        }
+    }
+
+    /// An empty plot for analog lane `lane`, reading out values the way the
+    /// channels want them
+    fn analog_plot(&self, lane: usize, log: bool, labels: &AxisLabels) -> egui_plot::Plot<'static> {
+        let hex: Vec<bool> = (0..self.plot_data.len())
+            .map(|idx| self.shows_hex(idx))
+            .collect();
+        let decimals = self.plot_options.decimals;
+        let mut plot = egui_plot::Plot::new(("measurements", lane))
+            .link_axis("time", true, false)
+            .link_cursor("time", true, false)
+            .label_formatter(move |name, point| {
+                let channel = name
+                    .strip_prefix("Channel ")
+                    .and_then(|idx| idx.parse::<usize>().ok());
+                let hex = channel.is_some_and(|idx| hex.get(idx) == Some(&true));
+                let value = match log {
+                    true => 10f64.powf(point.y),
+                    false => point.y,
+                };
+                let y = format_value(value, hex, decimals);
+                if name.is_empty() {
+                    format!("x = {:.3}\ny = {}", point.x, y)
+                } else {
+                    format!("{}\nx = {:.3}\ny = {}", name, point.x, y)
+                }
+            });
+        match (log, labels.engineering) {
+            (true, engineering) => {
+                plot = plot.y_axis_formatter(move |mark, _| log_tick(mark.value, engineering))
+            }
+            (false, true) => plot = plot.y_axis_formatter(|mark, _| engineering(mark.value)),
+            (false, false) => {}
+        }
+        // Dragging a cursor mustn't pan the plot along with it
+        if self.cursors.hovered {
+            plot = plot.allow_drag(false);
+        }
+        self.plot_options.grid.configure(plot)
+    }
+
+    /// Dropouts and bookmarks, as vertical lines across the plot
+    fn draw_markers(&self, plot_ui: &mut egui_plot::PlotUi) {
+        for x in self.dropouts.iter() {
+            plot_ui.vline(
+                egui_plot::VLine::new(*x)
+                    .color(Color32::GRAY)
+                    .style(egui_plot::LineStyle::dashed_loose())
+                    .name("Dropout"),
+            );
+        }
+        for (x, name) in self.bookmarks.iter() {
+            plot_ui.vline(
+                egui_plot::VLine::new(*x)
+                    .color(Color32::GOLD)
+                    .style(egui_plot::LineStyle::dotted_dense())
+                    .name(name),
+            );
+        }
+    }
+
+    /// Draw the analog `channels` in the plot style, counting the samples a
+    /// `log` axis can't show in `skipped`
+    fn draw_analog(
+        &self,
+        plot_ui: &mut egui_plot::PlotUi,
+        channels: &[usize],
+        log: bool,
+        skipped: &mut usize,
+    ) {
+        let scale = |segments: Vec<PlotPoints>, skipped: &mut usize| match log {
+            true => log_scaled(segments, skipped),
+            false => segments,
+        };
+        let style = self.plot_options.style;
+        let gaps = self.plot_options.gaps;
+        let width = self.plot_options.line_width;
+        for idx in channels.iter() {
+            let data = &self.plot_data[*idx];
+            let name = self.line_name(*idx);
+            if !data.reference.is_empty() {
+                for points in scale(vec![data.reference_values()], skipped) {
+                    plot_ui.line(
+                        egui_plot::Line::new(points)
+                            .width(width)
+                            .color(Color32::from_gray(160).gamma_multiply(0.5))
+                            .name(format!("{} reference", name)),
+                    );
+                }
+            }
+            // Each segment is its own item, so they need the channel color spelled out
+            let color = channel_color(*idx);
+            match style {
+                PlotStyle::Line => {
+                    scale(data.plot_values(gaps), skipped)
+                        .into_iter()
+                        .for_each(|points| {
+                            plot_ui.line(
+                                egui_plot::Line::new(points)
+                                    .width(width)
+                                    .color(color)
+                                    .name(&name),
+                            )
+                        })
+                }
+                PlotStyle::Points => {
+                    scale(data.plot_values(gaps), skipped)
+                        .into_iter()
+                        .for_each(|points| {
+                            plot_ui.points(
+                                egui_plot::Points::new(points)
+                                    .radius(2.)
+                                    .color(color)
+                                    .name(&name),
+                            )
+                        })
+                }
+                PlotStyle::Steps => {
+                    scale(data.step_values(gaps), skipped)
+                        .into_iter()
+                        .for_each(|points| {
+                            plot_ui.line(
+                                egui_plot::Line::new(points)
+                                    .width(width)
+                                    .color(color)
+                                    .name(&name),
+                            )
+                        })
+                }
+            }
+            let derivative = self
+                .plot_options
+                .channels
+                .get(*idx)
+                .is_some_and(|channel| channel.derivative);
+            if derivative {
+                for points in scale(data.derivative_values(gaps), skipped) {
+                    plot_ui.line(
+                        egui_plot::Line::new(points)
+                            .width(width)
+                            .color(color)
+                            .style(egui_plot::LineStyle::dashed_dense())
+                            .name(format!("{} d/dt", name)),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Draw digital channels as stacked high/low lanes under the analog plot
+    fn draw_logic_lanes(&self, ui: &mut egui::Ui, channels: &[usize]) {
+        const LANE_HEIGHT: f64 = 1.5;
+        let gaps = self.plot_options.gaps;
+        let width = self.plot_options.line_width;
+        egui_plot::Plot::new("logic lanes")
+            .link_axis("time", true, false)
+            .show_y(false)
+            .allow_drag([true, false])
+            .allow_zoom([true, false])
+            .y_axis_formatter(|_, _| String::new())
+            .include_y(-0.25)
+            .include_y(channels.len() as f64 * LANE_HEIGHT)
+            .show(ui, |plot_ui| {
+                let left = plot_ui.plot_bounds().min()[0];
+                for (lane, idx) in channels.iter().enumerate() {
+                    let base = lane as f64 * LANE_HEIGHT;
+                    let name = self.line_name(*idx);
+                    let color = channel_color(*idx);
+                    for points in self.plot_data[*idx].lane_values(base, gaps) {
+                        plot_ui.line(
+                            egui_plot::Line::new(points)
+                                .width(width)
+                                .color(color)
+                                .name(&name),
+                        );
+                    }
+                    plot_ui.text(
+                        egui_plot::Text::new(PlotPoint::new(left, base + 0.5), name)
+                            .anchor(egui::Align2::LEFT_CENTER),
+                    );
+                }
+            });
+    }
+
+    fn draw_bottom_panel(&mut self, ui: &mut egui::Ui) {
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.open_panel, Panel::Port, "Port");
+            ui.selectable_value(&mut self.open_panel, Panel::Log, "Log");
+            ui.selectable_value(&mut self.open_panel, Panel::Plot, "Plot");
+            ui.selectable_value(&mut self.open_panel, Panel::Commands, "Commands");
+            ui.selectable_value(&mut self.open_panel, Panel::Record, "Record");
+            ui.selectable_value(&mut self.open_panel, Panel::TextView, "TextView");
+            ui.selectable_value(&mut self.open_panel, Panel::Values, "Values");
+            ui.selectable_value(&mut self.open_panel, Panel::Histogram, "Histogram");
+            ui.selectable_value(&mut self.open_panel, Panel::Waterfall, "Waterfall");
+        });
+        ui.separator();
+        trace!("{}", self.open_panel);
+        match self.open_panel {
+            Panel::Port => {
+                self.show_port_settings(ui);
+            }
+            Panel::Plot => {
+                self.show_plot_settings(ui);
+            }
+            Panel::Commands => {
+                self.show_commands(ui);
+            }
+            Panel::Record => {
+                self.show_record_settings(ui);
+            }
+            Panel::TextView => {
+                self.show_text_view(ui);
+            }
+            Panel::Values => {
+                self.show_values(ui);
+            }
+            Panel::Histogram => {
+                self.show_histogram(ui);
+            }
+            Panel::Waterfall => {
+                self.show_waterfall(ui);
+            }
+            Panel::Log => {
+                self.show_log(ui);
+            }
+        }
+    }
+
+    fn show_port_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Source")
+                .selected_text(format!("{}", self.source_kind))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.source_kind, SourceKind::Serial, "Serial");
+                    ui.selectable_value(&mut self.source_kind, SourceKind::Tcp, "TCP")
+                        .on_hover_text("A serial port bridged over the network, e.g. ser2net");
+                    ui.selectable_value(&mut self.source_kind, SourceKind::Demo, "Demo")
+                        .on_hover_text("Generated data, to try Symphony without a device");
+                    ui.selectable_value(&mut self.source_kind, SourceKind::File, "File")
+                        .on_hover_text(
+                            "A FIFO or device another tool writes to, or a file to replay",
+                        );
+                });
+            match self.source_kind {
+                SourceKind::Tcp => {
+                    let label = ui.label("Address");
+                    ui.text_edit_singleline(&mut self.tcp_address)
+                        .labelled_by(label.id)
+                        .on_hover_text("host:port of the bridge");
+                }
+                SourceKind::File => {
+                    let label = ui.label("Path");
+                    ui.text_edit_singleline(&mut self.file_path)
+                        .labelled_by(label.id)
+                        .on_hover_text(
+                        "A FIFO (e.g. from mkfifo) is read live, a regular file is replayed once",
+                    );
+                }
+                _ => {}
+            }
+        });
+        match self.source_kind {
+            SourceKind::Serial => self.show_serial_settings(ui),
+            SourceKind::Demo => self.show_demo_settings(ui),
+            SourceKind::Tcp | SourceKind::File => {}
+        }
+        ui.horizontal_wrapped(|ui| {
+            let label = ui.label("Queue Capacity");
+            ui.add(egui::DragValue::new(&mut self.queue_capacity).range(QUEUE_CAPACITY_RANGE))
+                .labelled_by(label.id)
+                .on_hover_text("Packets read but not parsed yet, more are dropped");
+            let label = ui.label("Parse Queue");
+            ui.add(
+                egui::DragValue::new(&mut self.parse_queue_capacity).range(QUEUE_CAPACITY_RANGE),
+            )
+            .labelled_by(label.id)
+            .on_hover_text(
+                "Packets parsed but not plotted yet, more wait in the queue before it. \
+                     Applies on the next connect",
+            );
+            let label = ui.label("Packets per Frame");
+            ui.add(egui::DragValue::new(&mut self.drain_limit).range(1..=1_000_000))
+                .labelled_by(label.id)
+                .on_hover_text("Lower keeps the UI responsive while catching up on a backlog");
+            let label = ui.label("Idle Warning");
+            let mut idle_secs = self.idle_timeout.as_secs();
+            if ui
+                .add(
+                    egui::DragValue::new(&mut idle_secs)
+                        .range(IDLE_TIMEOUT_SECS)
+                        .suffix(" s"),
+                )
+                .labelled_by(label.id)
+                .changed()
+            {
+                self.idle_timeout = Duration::from_secs(idle_secs);
+            }
+            let label = ui.label("Health Degraded From");
+            let thresholds = &mut self.health_thresholds;
+            let mut degraded_percent = thresholds.degraded * 100.;
+            let mut bad_percent = thresholds.bad * 100.;
+            let degraded = ui
+                .add(
+                    egui::DragValue::new(&mut degraded_percent)
+                        .range(HEALTH_PERCENT)
+                        .speed(0.1)
+                        .suffix(" %"),
+                )
+                .labelled_by(label.id)
+                .on_hover_text("Share of packets with parse errors, bad checksums or drops");
+            let label = ui.label("Bad From");
+            let bad = ui
+                .add(
+                    egui::DragValue::new(&mut bad_percent)
+                        .range(HEALTH_PERCENT)
+                        .speed(0.1)
+                        .suffix(" %"),
+                )
+                .labelled_by(label.id);
+            if degraded.changed() || bad.changed() {
+                thresholds.degraded = degraded_percent / 100.;
+                thresholds.bad = (bad_percent / 100.).max(thresholds.degraded);
+            }
+            let label = ui.label("Garbage Warning");
+            let mut garbage_percent = self.garbage_threshold * 100.;
+            if ui
+                .add(
+                    egui::DragValue::new(&mut garbage_percent)
+                        .range(GARBAGE_PERCENT)
+                        .suffix(" %"),
+                )
+                .labelled_by(label.id)
+                .on_hover_text(
+                    "Hint at a wrong baud rate once this share of the received bytes \
+                     stays unprintable",
+                )
+                .changed()
+            {
+                self.garbage_threshold = garbage_percent / 100.;
+            }
+            let label = ui.label("Connect Timeout");
+            let mut connect_secs = self.connect_timeout.as_secs();
+            if ui
+                .add(
+                    egui::DragValue::new(&mut connect_secs)
+                        .range(CONNECT_TIMEOUT_SECS)
+                        .suffix(" s"),
+                )
+                .labelled_by(label.id)
+                .on_hover_text("How long opening the port may take before giving up on it")
+                .changed()
+            {
+                self.connect_timeout = Duration::from_secs(connect_secs);
+            }
+            let label = ui.label("Idle Sleep");
+            let mut idle_sleep_ms = self.max_idle_sleep.as_millis() as u64;
+            if ui
+                .add(
+                    egui::DragValue::new(&mut idle_sleep_ms)
+                        .range(MAX_IDLE_SLEEP_MS)
+                        .suffix(" ms"),
+                )
+                .labelled_by(label.id)
+                .on_hover_text(
+                    "Longest pause between reads while no data arrives, to save CPU. \
+                     The first packet after a quiet spell can be this late, 0 never pauses. \
+                     Applies on the next connect",
+                )
+                .changed()
+            {
+                self.max_idle_sleep = Duration::from_millis(idle_sleep_ms);
+            }
+            egui::ComboBox::from_label("In Background")
+                .selected_text(self.background_redraw.to_string())
+                .show_ui(ui, |ui| {
+                    for redraw in [
+                        BackgroundRedraw::Full,
+                        BackgroundRedraw::Reduced,
+                        BackgroundRedraw::Minimal,
+                    ] {
+                        ui.selectable_value(
+                            &mut self.background_redraw,
+                            redraw,
+                            redraw.to_string(),
+                        );
+                    }
+                })
+                .response
+                .on_hover_text(
+                    "Redraw less often while the window is unfocused or minimized, to save power. \
+                     Reading from the port doesn't slow down",
+                );
+        });
+        ui.horizontal_wrapped(|ui| {
+            egui::ComboBox::from_label("Line Framing")
+                .selected_text(format!("{}", self.framing))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.framing, Framing::Auto, "Auto")
+                        .on_hover_text("CRLF if the line has it, otherwise double NUL");
+                    ui.selectable_value(&mut self.framing, Framing::Crlf, "CRLF");
+                    ui.selectable_value(&mut self.framing, Framing::Lf, "LF");
+                    ui.selectable_value(&mut self.framing, Framing::Nul, "NUL");
+                    ui.selectable_value(
+                        &mut self.framing,
+                        Framing::Custom(String::default()),
+                        "Custom",
+                    );
+                    ui.selectable_value(
+                        &mut self.framing,
+                        Framing::Fixed {
+                            length: self.plot_options.binary.size(),
+                            sync: None,
+                        },
+                        "Fixed",
+                    )
+                    .on_hover_text("Binary frames, decoded with the layout in the Plot panel");
+                    ui.selectable_value(&mut self.framing, Framing::ModbusRtu, "Modbus RTU")
+                        .on_hover_text(
+                            "Replies of a Modbus device, one channel per coil or register. \
+                             Frames with a bad CRC are dropped",
+                        );
+                });
+            match self.framing {
+                Framing::Custom(ref mut terminator) => {
+                    let label = ui.label("Terminator: ");
+                    ui.text_edit_singleline(terminator)
+                        .labelled_by(label.id)
+                        .on_hover_text(
+                        "Any sequence of bytes, even one split over two reads. \
+                         Supports \\t, \\n, \\r, \\0, \\\\ and \\xNN escapes",
+                    );
+                    match decode_escapes(terminator) {
+                        Ok(decoded) => {
+                            let bytes: Vec<String> =
+                                decoded.bytes().map(|b| format!("0x{:02X}", b)).collect();
+                            ui.label(format!("Bytes: {}", bytes.join(" ")));
+                        }
+                        Err(e) => {
+                            ui.colored_label(Color32::DARK_RED, e);
+                        }
+                    }
+                }
+                Framing::Fixed {
+                    ref mut length,
+                    ref mut sync,
+                } => {
+                    let label = ui.label("Frame Length");
+                    ui.add(egui::DragValue::new(length).range(1..=4096).suffix(" B"))
+                        .labelled_by(label.id);
+                    let mut use_sync = sync.is_some();
+                    if ui
+                        .checkbox(&mut use_sync, "Sync Byte")
+                        .on_hover_text(
+                            "Every frame starts with this byte, skip ahead to it when frames drift",
+                        )
+                        .changed()
+                    {
+                        *sync = use_sync.then_some(0xAA);
+                    }
+                    if let Some(sync) = sync {
+                        ui.add(
+                            egui::DragValue::new(sync)
+                                .hexadecimal(2, false, true)
+                                .prefix("0x"),
+                        )
+                        .on_hover_text("Value of the sync byte");
+                    }
+                }
+                _ => {}
+            }
+            if !matches!(self.framing, Framing::Fixed { .. } | Framing::ModbusRtu) {
+                let label = ui.label("Max Line");
+                let mut max_kib = self.line_limit.max_len / 1024;
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut max_kib)
+                            .range(MAX_LINE_KIB)
+                            .suffix(" KiB"),
+                    )
+                    .labelled_by(label.id)
+                    .on_hover_text(
+                        "Longest a packet may get while its terminator doesn't turn up, \
+                         in case the device never sends one",
+                    )
+                    .changed()
+                {
+                    self.line_limit.max_len = max_kib * 1024;
+                }
+                egui::ComboBox::from_id_source("Overlong")
+                    .selected_text(self.line_limit.overlong.to_string())
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(
+                            &mut self.line_limit.overlong,
+                            Overlong::Emit,
+                            "Emit",
+                        )
+                        .on_hover_text("Pass the bytes so far on as a packet");
+                        ui.selectable_value(
+                            &mut self.line_limit.overlong,
+                            Overlong::Drop,
+                            "Drop",
+                        )
+                        .on_hover_text("Throw the bytes so far away");
+                    });
+                let label = ui.label("Merge Within");
+                let mut merge_ms = self.merge_window.as_millis() as u64;
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut merge_ms)
+                            .range(MERGE_WINDOW_MS)
+                            .suffix(" ms"),
+                    )
+                    .labelled_by(label.id)
+                    .on_hover_text(
+                        "Join packets that arrive this close together into one, for devices \
+                         that send a character at a time. 0 keeps every packet apart",
+                    )
+                    .changed()
+                {
+                    self.merge_window = Duration::from_millis(merge_ms);
+                }
+            }
+            let mut per_byte = self.timestamping == Timestamping::PerByte;
+            if ui
+                .checkbox(&mut per_byte, "Per-byte timestamps")
+                .on_hover_text("Stamp packets with when their first byte arrived instead of when they were complete, at some extra cost")
+                .changed()
+            {
+                self.timestamping = if per_byte {
+                    Timestamping::PerByte
+                } else {
+                    Timestamping::PerLine
+                };
+            }
+            if self.connected {
+                ui.label("(applies on next connect)");
+            }
+        });
+        if self.connected {
+            self.show_read_diagnostics(ui);
+        }
+        let (response, col) = if self.connecting {
+            (String::from("Connecting..."), CONNECTING_COLOR)
+        } else if self.connected || self.reconnect_at.is_some() {
+            (String::from("Disconnect"), Color32::DARK_RED)
+        } else {
+            (String::from("Connect"), Color32::DARK_GREEN)
+        };
+        // Disconnecting always works, connecting needs something to connect to
+        let missing = match self.connected || self.reconnect_at.is_some() {
+            true => None,
+            false => self.missing_source(),
+        };
+        let response = ui
+            .horizontal(|ui| {
+                let response = ui
+                    .add_enabled(
+                        !self.connecting && missing.is_none(),
+                        egui::Button::new(response).fill(col),
+                    )
+                    .on_hover_text("Shortcut: K")
+                    .on_disabled_hover_text(missing.as_deref().unwrap_or("Connecting..."));
+                if let Some(missing) = &missing {
+                    let color = ui.visuals().warn_fg_color;
+                    ui.colored_label(color, missing);
+                }
+                if self.connecting {
+                    ui.spinner();
+                }
+                ui.checkbox(&mut self.reset_time_on_connect, "Reset time on connect");
+                ui.checkbox(&mut self.clear_on_connect, "Clear on connect")
+                    .on_hover_text("Start every connection with empty plots and text view");
+                ui.checkbox(&mut self.keep_on_reconnect, "Reconnect on dropout")
+                    .on_hover_text(
+                        "Keep trying a source that went away, and carry on the same plot once it is back",
+                    );
+                if self.keep_on_reconnect && self.source_kind == SourceKind::Serial {
+                    ui.checkbox(&mut self.reconnect_by_identity, "Reconnect by device identity")
+                        .on_hover_text(
+                            "Find a USB adapter by its VID, PID and serial number, even if it comes back under another path",
+                        );
+                }
+                if ui.button("Reset Time").clicked() {
+                    self.reset_time();
+                }
+                ui.checkbox(&mut self.auto_connect, "Connect on startup")
+                    .on_hover_text("Open this port automatically next time Symphony starts");
+                let pause = if self.paused_at.is_some() {
+                    "Resume"
+                } else {
+                    "Pause"
+                };
+                if ui
+                    .button(pause)
+                    .on_hover_text("Shortcut: Space")
+                    .clicked()
+                {
+                    self.toggle_pause();
+                }
+                ui.separator();
+                let unsupported = self.serial_shared.break_unsupported.load(Ordering::Relaxed);
+                let button = ui
+                    .add_enabled(
+                        self.connected && !unsupported,
+                        egui::Button::new("Send Break"),
+                    )
+                    .on_hover_text("Hold the line low to signal a break")
+                    .on_disabled_hover_text(if unsupported {
+                        "This port doesn't support sending a break"
+                    } else {
+                        "Connect first"
+                    });
+                if button.clicked() {
+                    self.send_break();
+                }
+                let mut break_ms = self.break_duration.as_millis() as u64;
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut break_ms)
+                            .range(BREAK_MS)
+                            .suffix(" ms"),
+                    )
+                    .changed()
+                {
+                    self.break_duration = Duration::from_millis(break_ms);
+                }
+                response
+            })
+            .inner;
+        if response.clicked() {
+            self.toggle_connection();
+        };
+    }
+
+    /// Disconnect when connected or retrying, connect otherwise
+    fn toggle_connection(&mut self) {
+        if self.connecting {
+            return;
+        }
+        info!("{:?}", &self.plot_options);
+        if self.connected || self.reconnect_at.is_some() {
+            self.disconnect();
+        } else {
+            self.try_connect();
+        }
+    }
+
+    /// Act on the global keyboard shortcuts pressed this frame.
+    ///
+    /// Shortcuts are single keys, so they are skipped entirely while a text
+    /// field (command, port path, address, format, ...) has focus and the key
+    /// is typed into it instead. They come back as soon as the field loses focus.
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        // Can't be typed, so it works even while a text field has focus
+        if ctx.input(|i| i.key_pressed(egui::Key::F11)) {
+            self.compact = !self.compact;
+        }
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let (connect, pause) = ctx.input(|i| {
+            let plain = i.modifiers.is_none();
+            (
+                plain && i.key_pressed(egui::Key::K),
+                plain && i.key_pressed(egui::Key::Space),
+            )
+        });
+        if connect {
+            self.toggle_connection();
+        }
+        if pause {
+            self.toggle_pause();
+        }
+    }
+
+    fn show_demo_settings(&mut self, ui: &mut egui::Ui) {
+        let demo = &mut self.demo;
+        ui.horizontal_wrapped(|ui| {
+            egui::ComboBox::from_label("Waveform")
+                .selected_text(format!("{}", demo.waveform))
+                .show_ui(ui, |ui| {
+                    for waveform in [Waveform::Sine, Waveform::Ramp, Waveform::Noise] {
+                        ui.selectable_value(&mut demo.waveform, waveform, waveform.to_string());
+                    }
+                });
+            let label = ui.label("Frequency");
+            ui.add(
+                egui::DragValue::new(&mut demo.frequency)
+                    .range(0.01..=100.)
+                    .speed(0.1)
+                    .suffix(" Hz"),
+            )
+            .labelled_by(label.id);
+            let label = ui.label("Channels");
+            ui.add(egui::DragValue::new(&mut demo.channels).range(1..=16))
+                .labelled_by(label.id);
+            let label = ui.label("Sample Rate");
+            ui.add(
+                egui::DragValue::new(&mut demo.sample_rate)
+                    .range(1..=10_000)
+                    .suffix(" /s"),
+            )
+            .labelled_by(label.id)
+            .on_hover_text("Space separated lines, so keep the Space delimiter");
+            if self.connected {
+                ui.label("(applies on next connect)");
+            }
+        });
+    }
+
+    /// Line settings that only apply to a real serial port
+    fn show_serial_settings(&mut self, ui: &mut egui::Ui) {
+        let ports = serialib::get_serial_devices();
+        ui.horizontal_wrapped(|ui| {
+            let ports = match ports {
+                Ok(ports) => ports,
+                Err(e) => {
+                    let hint = recovery_hint(&e).unwrap_or_default();
+                    ui.colored_label(Color32::DARK_RED, e.to_string())
+                        .on_hover_text(hint);
+                    Vec::new()
+                }
+            };
+            egui::ComboBox::from_label("Select port")
+                .selected_text(self.current_port.path.clone())
+                .show_ui(ui, |ui| {
+                    ports.iter().for_each(|p| {
+                        ui.selectable_value(&mut self.current_port.path, p.clone(), p);
+                    });
+                });
+            egui::ComboBox::from_label("Set Baud Rate")
+                .selected_text(format!("{}", self.current_port.baud_rate))
+                .show_ui(ui, |ui| {
+                    BAUD_RATES.iter().for_each(|b| {
+                        ui.selectable_value(
+                            &mut self.current_port.baud_rate,
+                            *b as usize,
+                            format!("{} ", b),
+                        );
+                    })
+                });
+            match &self.baud_detect {
+                Some(detect) => {
+                    ui.spinner();
+                    ui.label(format!("Detecting {}/{}", detect.tried, BAUD_RATES.len()));
+                    if ui.button("Cancel").clicked() {
+                        self.cancel_baud_detect();
+                    }
+                }
+                None => {
+                    let idle = !self.connected && !self.current_port.path.is_empty();
+                    if ui
+                        .add_enabled(idle, egui::Button::new("Auto-detect baud"))
+                        .on_hover_text("Listen at every baud rate and pick the one with the most readable lines")
+                        .on_disabled_hover_text("Select a port and disconnect first")
+                        .clicked()
+                    {
+                        self.start_baud_detect();
+                    }
+                    if self.port_test.is_some() {
+                        ui.spinner();
+                        ui.label("Testing");
+                    } else if ui
+                        .add_enabled(idle, egui::Button::new("Test"))
+                        .on_hover_text(
+                            "Listen for a second with these settings, without connecting",
+                        )
+                        .on_disabled_hover_text("Select a port and disconnect first")
+                        .clicked()
+                    {
+                        self.start_port_test();
+                    }
+                    if let Some(result) = &self.port_test_result {
+                        ui.label(result);
+                    }
+                }
+            }
+        });
+        self.show_presets(ui);
+        self.show_profiles(ui);
+        ui.horizontal_wrapped(|ui| {
+            egui::ComboBox::from_label("Choose parity")
+                .selected_text(format!("{}", self.current_port.parity))
+                .show_ui(ui, |ui| {
                     ui.selectable_value(&mut self.current_port.parity, Parity::None, "None");
                     ui.selectable_value(&mut self.current_port.parity, Parity::Odd, "Odd");
                     ui.selectable_value(&mut self.current_port.parity, Parity::Even, "Even");
                 });
-            egui::ComboBox::from_label("Flow Control")
-                .selected_text(format!("{}", self.current_port.flow_control))
+            egui::ComboBox::from_label("Flow Control")
+                .selected_text(format!("{}", self.current_port.flow_control))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(
+                        &mut self.current_port.flow_control,
+                        FlowControl::None,
+                        "None",
+                    );
+                    ui.selectable_value(
+                        &mut self.current_port.flow_control,
+                        FlowControl::Software,
+                        "Software",
+                    );
+                    ui.selectable_value(
+                        &mut self.current_port.flow_control,
+                        FlowControl::Hardware,
+                        "Hardware",
+                    );
+                });
+            egui::ComboBox::from_label("Data Bits")
+                .selected_text(format!("{}", self.current_port.data_bits))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.current_port.data_bits, DataBits::Five, "5");
+                    ui.selectable_value(&mut self.current_port.data_bits, DataBits::Six, "6");
+                    ui.selectable_value(&mut self.current_port.data_bits, DataBits::Seven, "7");
+                    ui.selectable_value(&mut self.current_port.data_bits, DataBits::Eight, "8");
+                });
+            egui::ComboBox::from_label("Stop Bits")
+                .selected_text(format!("{}", self.current_port.stop_bits))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(
+                        &mut self.current_port.stop_bits,
+                        serialport::StopBits::One,
+                        "One",
+                    );
+                    ui.selectable_value(
+                        &mut self.current_port.stop_bits,
+                        serialport::StopBits::Two,
+                        "Two",
+                    );
+                });
+            let label = ui.label("Timeout");
+            let mut timeout_ms = self.current_port.timeout.as_millis() as u64;
+            if ui
+                .add(
+                    egui::DragValue::new(&mut timeout_ms)
+                        .range(serialib::MIN_TIMEOUT.as_millis() as u64..=60_000)
+                        .suffix(" ms"),
+                )
+                .labelled_by(label.id)
+                .on_hover_text("How long each read waits for data")
+                .changed()
+            {
+                self.current_port.timeout = Duration::from_millis(timeout_ms);
+            }
+        });
+    }
+
+    /// Show which terminator the last read was split on and what its bytes were
+    fn show_read_diagnostics(&self, ui: &mut egui::Ui) {
+        let Ok(diagnostics) = self.serial_shared.diagnostics.read() else {
+            return;
+        };
+        let hex = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .map(|b| format!("{:02X}", b))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        egui::CollapsingHeader::new("Framing Diagnostics").show(ui, |ui| {
+            if diagnostics.sample.is_empty() {
+                ui.label("Nothing read yet");
+                return;
+            }
+            let terminator = if diagnostics.terminator.is_empty() {
+                String::from("none (fixed-width frames)")
+            } else {
+                let text = String::from_utf8_lossy(&diagnostics.terminator);
+                format!(
+                    "{} ({})",
+                    encode_escapes(&text),
+                    hex(&diagnostics.terminator)
+                )
+            };
+            ui.label(format!("Last read split on: {}", terminator));
+            ui.label(format!("Raw bytes: {}", hex(&diagnostics.sample)))
+                .on_hover_text(encode_escapes(&String::from_utf8_lossy(
+                    &diagnostics.sample,
+                )));
+            let tally = diagnostics.tally();
+            ui.label(format!(
+                "Last {} bytes: \\r {}, \\n {}, \\0 {}, other non-printable {}",
+                tally.total,
+                tally.carriage_returns,
+                tally.line_feeds,
+                tally.nuls,
+                tally.non_printable
+            ))
+            .on_hover_text(
+                "Set the delimiter to whichever of \\r, \\n or \\r\\n matches these counts. \
+                 Many \\0 or non-printable bytes usually mean a wrong baud rate or binary data",
+            );
+        });
+    }
+
+    fn show_presets(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            let mut chosen = None;
+            egui::ComboBox::from_label("Preset")
+                .selected_text(self.preset_name.clone())
+                .show_ui(ui, |ui| {
+                    for (idx, preset) in self.presets.iter().enumerate() {
+                        if ui
+                            .selectable_label(preset.name == self.preset_name, &preset.name)
+                            .clicked()
+                        {
+                            chosen = Some(idx);
+                        }
+                    }
+                });
+            if let Some(idx) = chosen {
+                let preset = &self.presets[idx];
+                preset.apply(&mut self.current_port);
+                self.preset_name = preset.name.clone();
+                info!("Applied preset: {}", preset.name);
+                self.log.push(format!("Applied preset: {}", preset.name));
+            }
+            let label = ui.label("Name: ");
+            ui.text_edit_singleline(&mut self.preset_name)
+                .labelled_by(label.id);
+            let name = self.preset_name.trim().to_string();
+            let existing = self.presets.iter().position(|p| p.name == name);
+            if ui
+                .add_enabled(!name.is_empty(), egui::Button::new("Save Preset"))
+                .on_hover_text("Store the current line settings under this name")
+                .clicked()
+            {
+                match existing {
+                    Some(idx) => self.presets[idx].capture(&self.current_port),
+                    None => {
+                        let mut preset = PortPreset::new(&name, 0, Parity::None);
+                        preset.capture(&self.current_port);
+                        self.presets.push(preset);
+                    }
+                }
+                info!("Saved preset: {}", name);
+                self.log.push(format!("Saved preset: {}", name));
+            }
+            if ui
+                .add_enabled(existing.is_some(), egui::Button::new("Delete Preset"))
+                .clicked()
+            {
+                if let Some(idx) = existing {
+                    self.presets.remove(idx);
+                    info!("Deleted preset: {}", name);
+                    self.log.push(format!("Deleted preset: {}", name));
+                }
+            }
+            ui.separator();
+            if ui
+                .add_enabled(
+                    self.source_kind == SourceKind::Serial,
+                    egui::Button::new("Copy Command Line"),
+                )
+                .on_hover_text(
+                    "Copy a command that captures from this port to CSV without the window",
+                )
+                .clicked()
+            {
+                self.copy_command_line(ui.ctx());
+            }
+        });
+    }
+
+    /// Save, load and delete whole devices, the port included, under a name
+    fn show_profiles(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            let mut chosen = None;
+            egui::ComboBox::from_label("Profile")
+                .selected_text(self.profile_name.clone())
+                .show_ui(ui, |ui| {
+                    for (name, device) in self.profiles.profiles() {
+                        let notes = self.profiles.notes(name);
+                        let hover = match notes.is_empty() {
+                            true => device.to_string(),
+                            false => format!("{}\n{}", device, notes),
+                        };
+                        if ui
+                            .selectable_label(name == self.profile_name, name)
+                            .on_hover_text(hover)
+                            .clicked()
+                        {
+                            chosen = Some(name.to_string());
+                        }
+                    }
+                });
+            if let Some(name) = chosen {
+                if let Some(device) = self.profiles.get(&name) {
+                    self.current_port = device.clone();
+                    info!("Loaded profile: {}", name);
+                    self.log.push(format!("Loaded profile: {}", name));
+                    self.profile_name = name;
+                }
+            }
+            let label = ui.label("Name: ");
+            ui.text_edit_singleline(&mut self.profile_name)
+                .labelled_by(label.id);
+            let name = self.profile_name.trim().to_string();
+            let existing = self.profiles.get(&name).is_some();
+            if ui
+                .add_enabled(!name.is_empty(), egui::Button::new("Save Profile"))
+                .on_hover_text("Store the port and all its settings under this name")
+                .clicked()
+            {
+                self.profiles.save(&name, self.current_port.clone());
+                info!("Saved profile: {}", name);
+                self.log.push(format!("Saved profile: {}", name));
+            }
+            if ui
+                .add_enabled(existing, egui::Button::new("Delete Profile"))
+                .clicked()
+                && self.profiles.remove(&name)
+            {
+                info!("Deleted profile: {}", name);
+                self.log.push(format!("Deleted profile: {}", name));
+            }
+        });
+        if let Some(notes) = self.profiles.notes_mut(self.profile_name.trim()) {
+            ui.horizontal(|ui| {
+                let label = ui.label("Notes");
+                ui.add(
+                    egui::TextEdit::multiline(notes)
+                        .desired_rows(2)
+                        .hint_text("Wiring, pinout, quirks of this device"),
+                )
+                .labelled_by(label.id)
+                .on_hover_text("Saved with the profile");
+            });
+        }
+    }
+
+    /// Put a command line capturing like this session without the window on
+    /// the clipboard, see [`headless`].
+    ///
+    /// It carries the port, framing, delimiter and labeled tokens. Transforms,
+    /// checksums and the plot have no headless counterpart.
+    fn copy_command_line(&mut self, ctx: &egui::Context) {
+        if self.source_kind != SourceKind::Serial {
+            self.notice = Some(String::from(
+                "Only serial ports can be captured from the command line",
+            ));
+            return;
+        }
+        let capture = headless::Args {
+            device: self.current_port.clone(),
+            framing: self.framing.clone(),
+            delimiter: self.plot_options.delimiter.typed(),
+            labeled: self.plot_options.labeled,
+            duration: None,
+        };
+        let args = match capture.to_args() {
+            Ok(args) => args,
+            Err(e) => {
+                self.notice = Some(format!("Can't copy a command line, because: {}", e));
+                return;
+            }
+        };
+        let program = std::env::current_exe()
+            .map(|exe| exe.display().to_string())
+            .unwrap_or_else(|_| String::from("symphony"));
+        let command = std::iter::once(program)
+            .chain(args)
+            .map(|arg| shell_quote(&arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+        ctx.copy_text(command.clone());
+        info!("Copied command line: {}", command);
+        self.log.push(format!("Copied command line: {}", command));
+    }
+
+    /// Validate the source and start a serial thread opening it
+    fn try_connect(&mut self) -> bool {
+        if let Some(missing) = self.missing_source() {
+            warn!("Couldn't connect, because: {}", missing);
+            self.log
+                .push(format!("Couldn't connect, because: {}", missing));
+            return false;
+        }
+        if self.source_kind != SourceKind::Serial {
+            self.port_info = None;
+            self.connect(false);
+            return true;
+        }
+        if let Err(e) = self.current_port.validate() {
+            warn!("Invalid port settings: {}", e);
+            self.log.push(format!("Invalid port settings: {}", e));
+            return false;
+        }
+        if let Some(warning) = self.current_port.warning() {
+            warn!("{}", warning);
+            self.log.push(warning);
+        }
+        if let Some(holder) = PortLock::holder(&self.current_port.path) {
+            warn!(
+                "Port {} looks busy, {} has it open",
+                self.current_port.path, holder
+            );
+            self.log.push(format!(
+                "Port {} looks busy, {} has it open",
+                self.current_port.path, holder
+            ));
+        }
+        self.port_info = None;
+        self.connect(false);
+        true
+    }
+
+    /// Connect to the last used port if it is plugged in, leaving a notice if not
+    fn auto_connect(&mut self) {
+        let path = self.source_name().to_owned();
+        let present = self.source_kind != SourceKind::Serial
+            || serialib::get_serial_devices().is_ok_and(|ports| ports.contains(&path));
+        if path.is_empty() || !present {
+            let notice = format!("Last used port '{}' isn't available", path);
+            info!("{}", notice);
+            self.log.push(notice.clone());
+            self.notice = Some(notice);
+            return;
+        }
+        if !self.try_connect() {
+            self.notice = Some(format!("Couldn't reconnect to '{}', see Log", path));
+        }
+    }
+
+    fn show_text_view(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Serial Monitor");
+            ui.separator();
+            ui.checkbox(&mut self.text_view_options.wall_clock, "Wall Clock")
+                .on_hover_text("Time stamps show the time of day (UTC) instead of the capture time");
+            ui.label("Format");
+            ui.add(egui::TextEdit::singleline(&mut self.timestamp_format).desired_width(120.))
+                .on_hover_text(
+                    "%Y %y %m %d %H %M %S, %s for Unix seconds, %.3f or %3f for milliseconds, %F for %Y-%m-%d, %T for %H:%M:%S and %% for %. Other specifiers aren't supported. Also used for data exports",
+                );
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis();
+            match timefmt::render(&self.timestamp_format, now) {
+                Ok(preview) => {
+                    ui.weak(preview);
+                }
+                Err(e) => {
+                    ui.colored_label(Color32::DARK_RED, format!("Invalid format, {}", e))
+                        .on_hover_text(format!("Using {} instead", timefmt::DEFAULT_FORMAT));
+                }
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.checkbox(&mut self.text_view_options.auto_scroll, "AutoScroll");
+                if self.text_view_options.time_stamp && !self.text_view_options.wall_clock {
+                    ui.add(
+                        egui::DragValue::new(&mut self.text_view_options.time_decimals)
+                            .range(0..=9),
+                    )
+                    .on_hover_text("Digits after the point of time stamps, in seconds");
+                }
+                ui.checkbox(&mut self.text_view_options.time_stamp, "Time Stamps");
+                ui.checkbox(&mut self.text_view_options.show_escapes, "Show Escapes")
+                    .on_hover_text("Show control characters as \\r, \\n, \\t or \\xNN");
+                ui.checkbox(
+                    &mut self.text_view_options.show_partial,
+                    "Show Partial Line",
+                )
+                .on_hover_text("Show text that hasn't ended in a delimiter yet, like a prompt");
+                if self.text_view_options.raw_stream {
+                    ui.add(
+                        egui::DragValue::new(&mut self.text_view_options.raw_kib)
+                            .range(1..=MAX_RAW_KIB)
+                            .suffix(" KiB"),
+                    )
+                    .on_hover_text("How much of the stream to keep, the oldest bytes go first");
+                }
+                ui.checkbox(&mut self.text_view_options.raw_stream, "Raw Stream")
+                    .on_hover_text(format!(
+                        "Show the last {} KiB exactly as read, without splitting it into lines",
+                        self.text_view_options.raw_kib
+                    ));
+            })
+        });
+        ui.add_space(10.);
+        if self.text_view_options.raw_stream {
+            let row_height = ui.text_style_height(&TextStyle::Monospace);
+            ScrollArea::both()
+                .stick_to_bottom(self.text_view_options.auto_scroll)
+                .auto_shrink(false)
+                .show_rows(ui, row_height, self.raw_data.row_count(), |ui, rows| {
+                    for idx in rows {
+                        let row = self.raw_data.row(idx);
+                        let body = match self.text_view_options.show_escapes {
+                            true => encode_escapes(&row),
+                            false => String::from(row.trim_end_matches(['\r', '\n'])),
+                        };
+                        ui.add(egui::Label::new(egui::RichText::new(body).monospace()).extend());
+                    }
+                });
+            return;
+        }
+        let text_style = TextStyle::Body;
+        let row_height = ui.text_style_height(&text_style);
+        let partial = match self.text_view_options.show_partial && self.connected {
+            true => self
+                .serial_shared
+                .partial
+                .read()
+                .map(|bytes| decode_stream(&bytes))
+                .unwrap_or_default(),
+            false => String::new(),
+        };
+        let rows = self.text_data.len() + usize::from(!partial.is_empty());
+
+        ScrollArea::vertical()
+            .max_width(f32::INFINITY)
+            .stick_to_bottom(self.text_view_options.auto_scroll)
+            .auto_shrink(false)
+            .show_rows(ui, row_height, rows, |ui, row_range| {
+                let lines = row_range.start.min(self.text_data.len())
+                    ..row_range.end.min(self.text_data.len());
+                let shows_partial = row_range.end > self.text_data.len();
+                for line in &self.text_data[lines] {
+                    let body = match self.text_view_options.show_escapes {
+                        true => encode_escapes(&line.text),
+                        false => line.text.clone(),
+                    };
+                    let text = match self.text_view_options.time_stamp {
+                        true if self.text_view_options.wall_clock => format!(
+                            "[{}] {}",
+                            timefmt::format_or_default(&self.timestamp_format, line.wall),
+                            body
+                        ),
+                        true => format!(
+                            "[{}] {}",
+                            format_relative(line.time, self.text_view_options.time_decimals),
+                            body
+                        ),
+                        false => body,
+                    };
+                    if self.text_view_options.auto_scroll {
+                        ui.scroll_to_cursor(Some(Align::TOP));
+                    }
+                    ui.label(text);
+                }
+                if shows_partial {
+                    let body = match self.text_view_options.show_escapes {
+                        true => encode_escapes(&partial),
+                        false => partial.clone(),
+                    };
+                    ui.label(egui::RichText::new(body).italics())
+                        .on_hover_text("Still waiting for a delimiter");
+                }
+            });
+    }
+
+    /// Switch the cursors on and show the time and values between them
+    fn show_cursors(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut self.cursors.enabled, "Cursors")
+                .on_hover_text("Two lines to drag across the plot, measuring between them")
+                .changed()
+                && self.cursors.enabled
+            {
+                if let Some(range) = &self.visible_x {
+                    self.cursors.place(range);
+                }
+            }
+            if !self.cursors.enabled {
+                return;
+            }
+            if ui
+                .button("Reset")
+                .on_hover_text("Bring both cursors back into view")
+                .clicked()
+            {
+                if let Some(range) = &self.visible_x {
+                    self.cursors.place(range);
+                }
+            }
+            let dx = self.cursors.b - self.cursors.a;
+            ui.label(format!("Δx = {:.6} s", dx));
+            if dx != 0. {
+                ui.label(format!("1/Δx = {:.3} Hz", 1. / dx.abs()));
+            }
+        });
+        if !self.cursors.enabled {
+            return;
+        }
+        let decimals = self.plot_options.decimals;
+        egui::Grid::new("cursor values")
+            .num_columns(4)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Channel");
+                ui.label("A");
+                ui.label("B");
+                ui.label("Δy");
+                ui.end_row();
+                for (idx, data) in self.plot_data.iter().enumerate() {
+                    if self.is_digital(idx) {
+                        continue;
+                    }
+                    let hex = self.shows_hex(idx);
+                    let a = data.value_at(self.cursors.a);
+                    let b = data.value_at(self.cursors.b);
+                    let show = |value: Option<f64>| match value {
+                        Some(value) => format_value(value, hex, decimals),
+                        None => String::from("-"),
+                    };
+                    ui.label(self.line_name(idx));
+                    ui.label(show(a));
+                    ui.label(show(b));
+                    ui.label(show(a.zip(b).map(|(a, b)| b - a)));
+                    ui.end_row();
+                }
+            });
+    }
+
+    /// Switch channel count detection on, lock it, or start it over
+    fn show_channel_detection(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let detect = &mut self.plot_options.detect;
+            ui.checkbox(&mut detect.enabled, "Detect Channel Count")
+                .on_hover_text(
+                    "Use the most common column count of recent lines that parsed, \
+                     ignoring stray columns past it",
+                );
+            if !detect.enabled {
+                return;
+            }
+            ui.label("from");
+            ui.add(
+                egui::DragValue::new(&mut detect.lines)
+                    .range(1..=1_000)
+                    .suffix(" lines"),
+            );
+            let detection = &mut self.channel_detection;
+            match detection.detected {
+                Some(count) => ui.label(format!("Detected: {} columns", count)),
+                None => ui.label(format!(
+                    "Detecting, {} of {} lines",
+                    detection.counts.len(),
+                    detect.lines
+                )),
+            };
+            ui.checkbox(&mut detection.locked, "Lock")
+                .on_hover_text("Keep the detected count, even across connects");
+            if ui.button("Re-detect").clicked() {
+                detection.restart();
+            }
+        });
+    }
+
+    /// How lines split on the delimiter, a high share of empty tokens means
+    /// the delimiter probably doesn't match the data
+    fn show_token_counts(&self, ui: &mut egui::Ui) {
+        let counts = self.token_counts;
+        if counts.total == 0 {
+            return;
+        }
+        ui.horizontal(|ui| {
+            let (tokens, empty) = counts.last;
+            ui.label(format!("Last line: {} tokens, {} empty", tokens, empty));
+            let ratio = counts.empty_ratio();
+            let text = format!("{:.0}% of all tokens empty", ratio * 100.);
+            if ratio > 0.25 {
+                ui.colored_label(ui.visuals().warn_fg_color, text)
+                    .on_hover_text(
+                        "The delimiter probably doesn't match the data, see the text view",
+                    );
+            } else {
+                ui.label(text);
+            }
+        });
+    }
+
+    fn show_plot_settings(&mut self, ui: &mut egui::Ui) {
+        let x_range = self.plot_options.x_axis;
+        let y_range = self.plot_options.y_axis;
+        ui.horizontal_wrapped(|ui| {
+            let errors = &mut self.input_errors;
+            ui.label("Set Buffer Size").on_hover_text(format!(
+                "Most samples kept per channel, 0 for no limit. Samples older than {} s are dropped \
+                 as well, so whichever limit is reached first decides what stays",
+                LOOK_BEHIND
+            ));
+            ui.add_enabled_ui(!self.plot_options.unlimited, |ui| {
+                exact_drag_value(
+                    ui,
+                    &mut self.plot_options.buffer_size,
+                    0..=100_000,
+                    "buffer size",
+                    errors,
+                );
+            });
+            ui.checkbox(&mut self.plot_options.unlimited, "Unlimited")
+                .on_hover_text(
+                    "Keep every sample of every channel, bounded only by memory. \
+                     Long plots are thinned out for drawing, the buffer keeps everything",
+                );
+            if self.plot_options.unlimited {
+                let samples: usize = self.plot_data.iter().map(|m| m.values.len()).sum();
+                let megabytes = (samples * std::mem::size_of::<PlotPoint>()) as f64 / 1e6;
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!(
+                        "{} samples kept in {:.1} MB, memory use grows with every sample",
+                        samples, megabytes
+                    ),
+                );
+            } else if (1..MIN_SAMPLES).contains(&self.plot_options.buffer_size) {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("Keep at least {} samples to see a line", MIN_SAMPLES),
+                );
+            }
+            ui.add_space(15.);
+            ui.label("Range for X-axis");
+            // Min
+            exact_drag_value(
+                ui,
+                &mut self.plot_options.x_axis[0],
+                0..=x_range[1],
+                "x min",
+                errors,
+            );
+            // Max
+            exact_drag_value(
+                ui,
+                &mut self.plot_options.x_axis[1],
+                0..=self.plot_options.plot_width,
+                "x max",
+                errors,
+            );
+        });
+        ui.horizontal_wrapped(|ui| {
+            let errors = &mut self.input_errors;
+            ui.label("Set Plot Width ");
+            // A buffer size of 0 keeps every sample, so it doesn't cap the width either
+            let max_width = match self.plot_options.buffer_size {
+                0 => 100_000,
+                size => size,
+            };
+            exact_drag_value(
+                ui,
+                &mut self.plot_options.plot_width,
+                0..=max_width,
+                "plot width",
+                errors,
+            );
+            if (1..MIN_SAMPLES).contains(&self.plot_options.plot_width) {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!(
+                        "A width under {} samples shows next to nothing",
+                        MIN_SAMPLES
+                    ),
+                );
+            }
+            ui.add_space(15.);
+            ui.label("Range for Y-axis");
+            // Min
+            exact_drag_value(
+                ui,
+                &mut self.plot_options.y_axis[0],
+                0..=y_range[1],
+                "y min",
+                errors,
+            );
+            // Max
+            exact_drag_value(
+                ui,
+                &mut self.plot_options.y_axis[1],
+                0..=self.plot_options.plot_width,
+                "y max",
+                errors,
+            );
+            ui.add_space(15.);
+            ui.checkbox(&mut self.plot_options.autoscale, "Autoscale Y")
+                .on_hover_text(
+                    "Fit the y axis to the channels set to Auto Y, \
+                     a locked channel counts with its locked range",
+                );
+            ui.add_enabled_ui(self.plot_options.autoscale, |ui| {
+                egui::ComboBox::from_id_source("rescale")
+                    .selected_text(self.plot_options.rescale.to_string())
+                    .show_ui(ui, |ui| {
+                        for rescale in [Rescale::EveryFrame, Rescale::Periodic, Rescale::Manual] {
+                            ui.selectable_value(
+                                &mut self.plot_options.rescale,
+                                rescale,
+                                rescale.to_string(),
+                            );
+                        }
+                    })
+                    .response
+                    .on_hover_text("Hold the axes still between refits while data streams in");
+                if ui.button("Rescale").clicked() {
+                    self.rescale_now = true;
+                }
+            });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Select Delimiter");
+            egui::ComboBox::from_label("Delimiter")
+                .selected_text(format!("{}", self.plot_options.delimiter))
                 .show_ui(ui, |ui| {
                     ui.selectable_value(
-                        &mut self.current_port.flow_control,
-                        FlowControl::None,
+                        &mut self.plot_options.delimiter,
+                        Delimiter::Space,
+                        "Space",
+                    );
+                    ui.selectable_value(&mut self.plot_options.delimiter, Delimiter::Tab, "Tab");
+                    ui.selectable_value(
+                        &mut self.plot_options.delimiter,
+                        Delimiter::Comma,
+                        "Comma",
+                    );
+                    ui.selectable_value(
+                        &mut self.plot_options.delimiter,
+                        Delimiter::Other(String::default()),
+                        "Other",
+                    );
+                });
+            if let Delimiter::Other(ref mut custom) = self.plot_options.delimiter {
+                ui.label("Custom Delimiter: ");
+                ui.text_edit_singleline(custom)
+                    .on_hover_text("Supports \\t, \\n, \\r, \\0, \\\\ and \\xNN escapes");
+                match decode_escapes(custom) {
+                    Ok(decoded) => {
+                        let bytes: Vec<String> =
+                            decoded.bytes().map(|b| format!("0x{:02X}", b)).collect();
+                        ui.label(format!("Bytes: {}", bytes.join(" ")));
+                    }
+                    Err(e) => {
+                        ui.colored_label(Color32::DARK_RED, e);
+                    }
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Columns");
+            let response = ui
+                .text_edit_singleline(&mut self.plot_options.column_text)
+                .on_hover_text(
+                    "Source columns to plot, counting from 0, like '2, 5'. \
+                     The first one becomes channel 0. Leave empty to plot every column",
+                );
+            match parse_columns(&self.plot_options.column_text) {
+                Ok(columns) => {
+                    if response.changed() {
+                        self.plot_options.columns = columns;
+                    }
+                }
+                Err(e) => {
+                    ui.colored_label(Color32::DARK_RED, e);
+                }
+            }
+        });
+        self.show_token_counts(ui);
+        self.show_channel_detection(ui);
+        self.show_cursors(ui);
+
+        ui.horizontal(|ui| {
+            ui.label("Select Render Style");
+            egui::ComboBox::from_label("Style")
+                .selected_text(format!("{}", self.plot_options.style))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.plot_options.style, PlotStyle::Line, "Line");
+                    ui.selectable_value(&mut self.plot_options.style, PlotStyle::Points, "Points");
+                    ui.selectable_value(&mut self.plot_options.style, PlotStyle::Steps, "Steps");
+                });
+            egui::ComboBox::from_label("Layout")
+                .selected_text(format!("{}", self.plot_options.layout))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(
+                        &mut self.plot_options.layout,
+                        PlotLayout::Overlaid,
+                        "Overlaid",
+                    )
+                    .on_hover_text("Every analog channel on one y axis");
+                    ui.selectable_value(
+                        &mut self.plot_options.layout,
+                        PlotLayout::Stacked,
+                        "Stacked",
+                    )
+                    .on_hover_text(
+                        "Each analog channel in a lane of its own, autoscaled or locked on its own",
+                    );
+                });
+            egui::ComboBox::from_label("Gaps")
+                .selected_text(format!("{}", self.plot_options.gaps))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.plot_options.gaps, GapMode::Break, "Break")
+                        .on_hover_text("Leave missing values out of the line");
+                    ui.selectable_value(&mut self.plot_options.gaps, GapMode::Connect, "Connect")
+                        .on_hover_text("Draw straight across missing values");
+                });
+            ui.add(
+                egui::Slider::new(&mut self.plot_options.line_width, 0.5..=6.).text("Line Width"),
+            );
+            ui.checkbox(&mut self.plot_options.anti_aliasing, "Anti-aliasing")
+                .on_hover_text("Smoother lines, turn off on slow hardware");
+            ui.add(
+                egui::DragValue::new(&mut self.plot_options.refresh_hz)
+                    .range(0..=240)
+                    .prefix("Refresh ")
+                    .suffix(" Hz"),
+            )
+            .on_hover_text(
+                "How often the plot is redrawn while data streams in, 0 for as often as \
+                 the display allows. Data is still read at full rate in between",
+            );
+            ui.add(egui::Slider::new(&mut self.plot_options.decimals, 0..=10).text("Decimals"))
+                .on_hover_text("Only changes readouts, exports keep full precision");
+        });
+        ui.horizontal(|ui| {
+            let grid = &mut self.plot_options.grid;
+            ui.checkbox(&mut grid.show, "Grid");
+            ui.add_enabled(
+                grid.show,
+                egui::Slider::new(&mut grid.spacing, 2.0..=100.).text("Grid Spacing"),
+            )
+            .on_hover_text("Roughly how close gridlines and ticks may get, in points");
+            ui.add_enabled(
+                grid.show,
+                egui::Checkbox::new(&mut grid.minor, "Minor Lines"),
+            )
+            .on_hover_text("Show the finest lines between the labelled ones");
+        });
+
+        ui.horizontal(|ui| {
+            let mut labels = self.axis_labels();
+            ui.label("X Axis Label");
+            let x = ui.text_edit_singleline(&mut labels.x);
+            ui.label("Y Axis Label");
+            let y = ui
+                .text_edit_singleline(&mut labels.y)
+                .on_hover_text("Include the units, like 'Voltage (V)'");
+            let engineering = ui
+                .checkbox(&mut labels.engineering, "Engineering Notation")
+                .on_hover_text("Label y ticks with SI prefixes, like 4.7k or 120µ");
+            if x.changed() || y.changed() || engineering.changed() {
+                self.axis_labels
+                    .insert(self.source_name().to_owned(), labels);
+            }
+            ui.label("(saved for this port)");
+            if self.log_skipped > 0 {
+                let color = ui.visuals().warn_fg_color;
+                ui.colored_label(
+                    color,
+                    format!("{} samples that aren't positive hidden", self.log_skipped),
+                );
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Preprocess Payloads");
+            egui::ComboBox::from_label("Transform")
+                .selected_text(format!("{}", self.plot_options.transform))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(
+                        &mut self.plot_options.transform,
+                        Transform::Identity,
                         "None",
                     );
+                    ui.selectable_value(&mut self.plot_options.transform, Transform::Trim, "Trim")
+                        .on_hover_text("Strip surrounding whitespace");
                     ui.selectable_value(
-                        &mut self.current_port.flow_control,
-                        FlowControl::Software,
-                        "Software",
+                        &mut self.plot_options.transform,
+                        Transform::DropMatching(String::default()),
+                        "Drop matching",
+                    )
+                    .on_hover_text("Skip payloads containing some text");
+                    ui.selectable_value(
+                        &mut self.plot_options.transform,
+                        Transform::VerifyChecksum,
+                        "Verify checksum",
+                    )
+                    .on_hover_text("Keep 'data*HH' payloads whose XOR checksum matches");
+                });
+            if let Transform::DropMatching(ref mut pattern) = self.plot_options.transform {
+                ui.label("Drop payloads containing: ");
+                ui.text_edit_singleline(pattern);
+            }
+        });
+
+        if let Framing::Fixed { length, sync } = self.framing {
+            self.show_binary_layout(ui, length.saturating_sub(usize::from(sync.is_some())));
+        }
+
+        self.show_nmea_fields(ui);
+        ui.checkbox(&mut self.plot_options.labeled, "Labeled Values")
+            .on_hover_text(
+                "Tokens like temp:21.5 as the Arduino Serial Plotter sends them, every name \
+                 becomes a channel named after it. Tokens without a name keep their position",
+            );
+
+        ui.horizontal(|ui| {
+            ui.label("Verify Checksum");
+            let checksum = &mut self.plot_options.checksum;
+            egui::ComboBox::from_label("Scheme")
+                .selected_text(format!("{}", checksum.scheme))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut checksum.scheme, Scheme::None, "None");
+                    ui.selectable_value(&mut checksum.scheme, Scheme::Xor, "XOR");
+                    ui.selectable_value(&mut checksum.scheme, Scheme::Sum, "Sum");
+                    ui.selectable_value(&mut checksum.scheme, Scheme::Crc16, "CRC16");
+                });
+            if checksum.scheme != Scheme::None {
+                ui.label("Checksum field from end");
+                ui.add(egui::DragValue::new(&mut checksum.field_from_end).range(1..=64))
+                    .on_hover_text("1 is the last field, covering every field before it");
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Out of order samples");
+            let regression = self.plot_options.regression;
+            egui::ComboBox::from_label("Regression")
+                .selected_text(format!("{}", self.plot_options.regression))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(
+                        &mut self.plot_options.regression,
+                        Regression::Clear,
+                        "Clear",
+                    )
+                    .on_hover_text("Start over, as if the device restarted");
+                    ui.selectable_value(
+                        &mut self.plot_options.regression,
+                        Regression::Sort,
+                        "Sort",
+                    )
+                    .on_hover_text("Insert the sample in time order");
+                    ui.selectable_value(
+                        &mut self.plot_options.regression,
+                        Regression::Ignore,
+                        "Ignore",
+                    )
+                    .on_hover_text("Drop the sample");
+                });
+            if regression != self.plot_options.regression {
+                let regression = self.plot_options.regression;
+                self.plot_data
+                    .iter_mut()
+                    .for_each(|m| m.regression = regression);
+            }
+        });
+
+        let references: Vec<Option<bool>> = (0..self.plot_options.channels.len())
+            .map(|idx| self.plot_data.get(idx).map(|m| !m.reference.is_empty()))
+            .collect();
+        let mut reference_action = None;
+        let locks = self.range_locks();
+        let mut lock_action = None;
+        let autoscale = self.plot_options.autoscale;
+        let labels: Vec<String> = (0..self.plot_options.channels.len())
+            .map(|idx| self.channel_label(idx))
+            .collect();
+        self.plot_options
+            .channels
+            .iter_mut()
+            .enumerate()
+            .for_each(|(idx, channel)| {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut channel.visible, &labels[idx])
+                        .on_hover_text("Draw this channel, hidden ones keep collecting samples");
+                    egui::ComboBox::from_id_source(("signal kind", idx))
+                        .selected_text(format!("{}", channel.kind))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut channel.kind, SignalKind::Auto, "Auto");
+                            ui.selectable_value(&mut channel.kind, SignalKind::Analog, "Analog");
+                            ui.selectable_value(&mut channel.kind, SignalKind::Digital, "Digital");
+                        });
+                    egui::ComboBox::from_id_source(("display base", idx))
+                        .selected_text(format!("{}", channel.base))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut channel.base, DisplayBase::Decimal, "Dec");
+                            ui.selectable_value(&mut channel.base, DisplayBase::Hexadecimal, "Hex");
+                        });
+                    ui.label("Gain");
+                    ui.add(egui::DragValue::new(&mut channel.gain).speed(0.01));
+                    ui.label("Offset");
+                    ui.add(egui::DragValue::new(&mut channel.offset).speed(0.01));
+                    ui.checkbox(&mut channel.derivative, "d/dt")
+                        .on_hover_text("Also plot the rate of change per second, dashed");
+                    ui.checkbox(&mut channel.log_y, "Log Y").on_hover_text(
+                        "Plot on a log scale, for signals spanning orders of magnitude. \
+                         Channels sharing an axis need it all set, so stack them to mix",
                     );
+                    ui.add_enabled_ui(autoscale, |ui| {
+                        ui.checkbox(&mut channel.auto_range, "Auto Y")
+                            .on_hover_text(
+                                "Autoscaling makes room for this channel, or fits its lane when stacked",
+                            );
+                        match locks.get(&idx) {
+                            Some([lo, hi]) => {
+                                if ui
+                                    .button("Unlock Range")
+                                    .on_hover_text(format!("Locked to {} to {}", lo, hi))
+                                    .clicked()
+                                {
+                                    lock_action = Some((idx, false));
+                                }
+                            }
+                            None => {
+                                if ui
+                                    .add_enabled(
+                                        channel.auto_range && references[idx].is_some(),
+                                        egui::Button::new("Lock Range"),
+                                    )
+                                    .on_hover_text(
+                                        "Keep the range this channel has now, \
+                                         however its values change later",
+                                    )
+                                    .clicked()
+                                {
+                                    lock_action = Some((idx, true));
+                                }
+                            }
+                        }
+                    });
+                    if let Some(has_reference) = references[idx] {
+                        if ui.button("Capture Reference").clicked() {
+                            reference_action = Some((idx, true));
+                        }
+                        if ui
+                            .add_enabled(has_reference, egui::Button::new("Clear Reference"))
+                            .clicked()
+                        {
+                            reference_action = Some((idx, false));
+                        }
+                    }
+                });
+            });
+        match reference_action {
+            Some((idx, true)) => self.plot_data[idx].capture_reference(),
+            Some((idx, false)) => self.plot_data[idx].reference.clear(),
+            None => {}
+        }
+        if let Some((idx, lock)) = lock_action {
+            let visible = self
+                .visible_x
+                .clone()
+                .unwrap_or(f64::NEG_INFINITY..=f64::INFINITY);
+            let range = self.plot_data[idx]
+                .stats_within(&visible)
+                .map(|stats| [stats.min, stats.max]);
+            let locks = self
+                .range_locks
+                .entry(self.source_name().to_owned())
+                .or_default();
+            match (lock, range) {
+                (true, Some(range)) => {
+                    locks.insert(idx, range);
+                }
+                (true, None) => {}
+                (false, _) => {
+                    locks.remove(&idx);
+                }
+            }
+        }
+        self.show_stats_table(ui);
+    }
+
+    /// Switch to parsing NMEA sentences and pick the field of every channel
+    fn show_nmea_fields(&mut self, ui: &mut egui::Ui) {
+        let nmea = &mut self.plot_options.nmea;
+        ui.horizontal_wrapped(|ui| {
+            ui.checkbox(&mut nmea.enabled, "NMEA Sentences")
+                .on_hover_text(
+                    "Parse GGA, RMC and VTG sentences from a GPS module, checking their checksum. \
+                 Other lines are ignored",
+                );
+            if !nmea.enabled {
+                return;
+            }
+            let mut remove = None;
+            for (idx, field) in nmea.fields.iter_mut().enumerate() {
+                egui::ComboBox::from_id_source(("nmea field", idx))
+                    .selected_text(field.to_string())
+                    .show_ui(ui, |ui| {
+                        for kind in nmea::Field::ALL {
+                            ui.selectable_value(field, kind, kind.to_string());
+                        }
+                    });
+                if ui.small_button("✖").on_hover_text("Remove field").clicked() {
+                    remove = Some(idx);
+                }
+            }
+            if let Some(idx) = remove {
+                nmea.fields.remove(idx);
+            }
+            if ui.button("Add Field").clicked() {
+                nmea.fields.push(nmea::Field::default());
+            }
+        });
+    }
+
+    /// Edit the fields binary frames are decoded into, `available` bytes follow the sync byte
+    fn show_binary_layout(&mut self, ui: &mut egui::Ui, available: usize) {
+        let layout = &mut self.plot_options.binary;
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Binary Layout");
+            let mut remove = None;
+            for (idx, field) in layout.fields.iter_mut().enumerate() {
+                egui::ComboBox::from_id_source(("binary field", idx))
+                    .selected_text(format!("{}", field))
+                    .show_ui(ui, |ui| {
+                        for kind in FieldType::ALL {
+                            ui.selectable_value(field, kind, format!("{}", kind));
+                        }
+                    });
+                if ui.small_button("✖").on_hover_text("Remove field").clicked() {
+                    remove = Some(idx);
+                }
+            }
+            if let Some(idx) = remove {
+                layout.fields.remove(idx);
+            }
+            if ui.button("Add Field").clicked() {
+                layout.fields.push(FieldType::default());
+            }
+            egui::ComboBox::from_label("Byte Order")
+                .selected_text(format!("{}", layout.endianness))
+                .show_ui(ui, |ui| {
                     ui.selectable_value(
-                        &mut self.current_port.flow_control,
-                        FlowControl::Hardware,
-                        "Hardware",
+                        &mut layout.endianness,
+                        Endianness::Little,
+                        "Little endian",
                     );
+                    ui.selectable_value(&mut layout.endianness, Endianness::Big, "Big endian");
                 });
-            egui::ComboBox::from_label("Stop Bits")
-                .selected_text(format!("{}", self.current_port.stop_bits))
+            let size = layout.size();
+            if size > available {
+                ui.colored_label(
+                    Color32::DARK_RED,
+                    format!(
+                        "Fields need {} bytes but frames only have {}",
+                        size, available
+                    ),
+                );
+            } else {
+                ui.label(format!("{} of {} bytes used", size, available));
+            }
+        });
+    }
+
+    fn show_stats_table(&self, ui: &mut egui::Ui) {
+        if self.plot_data.is_empty() {
+            return;
+        }
+        let decimals = self.plot_options.decimals;
+        // Loaded data has no live origin to measure ages against
+        let live = self.connected || self.reconnect_at.is_some();
+        let now = self.absolute_time.elapsed().as_secs_f64();
+        egui::Grid::new("statistics")
+            .striped(true)
+            .num_columns(7)
+            .show(ui, |ui| {
+                [
+                    "Channel",
+                    "Count",
+                    "Min",
+                    "Max",
+                    "Mean",
+                    "Std Dev",
+                    "Last Update",
+                ]
+                .iter()
+                .for_each(|header| {
+                    ui.strong(*header);
+                });
+                ui.end_row();
+                for (idx, data) in self.plot_data.iter().enumerate() {
+                    ui.label(self.channel_label(idx));
+                    let Some(stats) = data.stats() else {
+                        ui.end_row();
+                        continue;
+                    };
+                    let hex = self.shows_hex(idx);
+                    ui.label(format!("{}", stats.count));
+                    ui.label(format_value(stats.min, hex, decimals));
+                    ui.label(format_value(stats.max, hex, decimals));
+                    ui.label(format_value(stats.mean, false, decimals));
+                    ui.label(format_value(stats.std_dev, false, decimals));
+                    match data.latest() {
+                        Some(latest) if live => {
+                            let age = (now - latest.x).max(0.);
+                            let text = format!("{:.1} s ago", age);
+                            if age > STALE_AFTER.as_secs_f64() {
+                                ui.colored_label(ui.visuals().warn_fg_color, text)
+                                    .on_hover_text(
+                                        "No new value for a while, the sensor may have stalled",
+                                    );
+                            } else {
+                                ui.label(text);
+                            }
+                        }
+                        Some(latest) => {
+                            ui.label(format!("at {:.3} s", latest.x));
+                        }
+                        None => {
+                            ui.label("-");
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn show_values(&self, ui: &mut egui::Ui) {
+        if self.plot_data.is_empty() {
+            ui.label("No values parsed yet");
+            return;
+        }
+        ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("values")
+                .striped(true)
+                .num_columns(3)
+                .spacing([40., 8.])
+                .show(ui, |ui| {
+                    for (idx, data) in self.plot_data.iter().enumerate() {
+                        ui.label(egui::RichText::new(self.channel_label(idx)).size(24.));
+                        let Some(latest) = data.latest() else {
+                            ui.end_row();
+                            continue;
+                        };
+                        ui.label(
+                            egui::RichText::new(format_value(
+                                latest.y,
+                                self.shows_hex(idx),
+                                self.plot_options.decimals,
+                            ))
+                            .size(40.)
+                            .monospace()
+                            .color(channel_color(idx)),
+                        );
+                        ui.label(format!("at {:.3} s", latest.x));
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    fn show_histogram(&mut self, ui: &mut egui::Ui) {
+        if self.plot_data.is_empty() {
+            ui.label("No values parsed yet");
+            return;
+        }
+        let options = &mut self.histogram;
+        options.channel = options.channel.min(self.plot_data.len() - 1);
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Channel")
+                .selected_text(format!("Channel {}", options.channel))
                 .show_ui(ui, |ui| {
-                    ui.selectable_value(
-                        &mut self.current_port.stop_bits,
-                        serialport::StopBits::One,
-                        "One",
-                    );
-                    ui.selectable_value(
-                        &mut self.current_port.stop_bits,
-                        serialport::StopBits::Two,
-                        "Two",
-                    );
+                    for idx in 0..self.plot_data.len() {
+                        ui.selectable_value(&mut options.channel, idx, format!("Channel {}", idx));
+                    }
                 });
+            ui.add(egui::Slider::new(&mut options.bins, 1..=200).text("Bins"));
         });
-        let (response, col) = if self.connected {
-            (String::from("Disconnect"), Color32::DARK_RED)
+        let (channel, bins) = (options.channel, options.bins);
+        let Some(histogram) = self.plot_data[channel].histogram(bins) else {
+            ui.label("Channel has no values yet");
+            return;
+        };
+        let count: usize = histogram.counts.iter().sum();
+        let hex = self.shows_hex(channel);
+        ui.label(format!(
+            "{} values in {} bins, bin width {}",
+            count,
+            histogram.counts.len(),
+            format_value(histogram.width, false, self.plot_options.decimals)
+        ))
+        .on_hover_text(format!(
+            "Starting at {}",
+            format_value(histogram.min, hex, self.plot_options.decimals)
+        ));
+        // A zero width bar wouldn't show, so a single value gets a unit wide one
+        let bar_width = if histogram.width > 0. {
+            histogram.width
         } else {
-            (String::from("Connect"), Color32::DARK_GREEN)
+            1.
         };
-        let response = ui.add(egui::Button::new(response).fill(col));
-        if response.clicked() {
-            info!("{:?}", &self.plot_options);
-            // TODO: Connect to port here
-            let port = self.current_port.open();
-            match port {
-                Ok(port) => {
-                    self.connected = !self.connected;
-                    // Have some kind of function to get connection status. A Lock
-                    info!("Connected to port: {:?}", port);
-                    self.log.push(format!("Connected to port: {:?}", port));
+        let bars = histogram
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(bin, count)| {
+                let center = histogram.min + (bin as f64 + 0.5) * histogram.width;
+                egui_plot::Bar::new(center, *count as f64).width(bar_width)
+            })
+            .collect();
+        egui_plot::Plot::new("histogram")
+            .y_axis_label("Count")
+            .show(ui, |plot_ui| {
+                plot_ui.bar_chart(
+                    egui_plot::BarChart::new(bars)
+                        .color(channel_color(channel))
+                        .name(format!("Channel {}", channel)),
+                );
+            });
+    }
+
+    /// Draw the newest rows of `row_len` samples of one channel as a heatmap,
+    /// newest at the top, with the capture time on the y axis
+    fn show_waterfall(&mut self, ui: &mut egui::Ui) {
+        if self.plot_data.is_empty() {
+            ui.label("No values parsed yet");
+            return;
+        }
+        let options = &mut self.waterfall;
+        options.channel = options.channel.min(self.plot_data.len() - 1);
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Channel")
+                .selected_text(format!("Channel {}", options.channel))
+                .show_ui(ui, |ui| {
+                    for idx in 0..self.plot_data.len() {
+                        ui.selectable_value(&mut options.channel, idx, format!("Channel {}", idx));
+                    }
+                });
+            ui.add(egui::Slider::new(&mut options.row_len, 2..=1024).text("Samples per Row"));
+            egui::ComboBox::from_label("Colors")
+                .selected_text(options.color_map.to_string())
+                .show_ui(ui, |ui| {
+                    for map in ColorMap::ALL {
+                        ui.selectable_value(&mut options.color_map, map, map.to_string());
+                    }
+                });
+        });
+        let WaterfallOptions {
+            channel,
+            row_len,
+            color_map,
+        } = *options;
+        let points: Vec<PlotPoint> = self.plot_data[channel]
+            .values
+            .iter()
+            .filter(|p| !p.y.is_nan())
+            .copied()
+            .collect();
+        let rows: Vec<&[PlotPoint]> = points.chunks_exact(row_len).collect();
+        let rows = &rows[rows.len().saturating_sub(WATERFALL_MAX_ROWS)..];
+        let (Some(first), Some(last)) = (rows.first(), rows.last()) else {
+            ui.label(format!("Waiting for {} samples to fill a row", row_len));
+            return;
+        };
+        let (min, max) = rows
+            .iter()
+            .flat_map(|row| row.iter())
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), p| {
+                (min.min(p.y), max.max(p.y))
+            });
+        let span = max - min;
+        let hex = self.shows_hex(channel);
+        let decimals = self.plot_options.decimals;
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} rows, {} to {}",
+                rows.len(),
+                format_value(min, hex, decimals),
+                format_value(max, hex, decimals)
+            ));
+            for step in 0..=10 {
+                ui.colored_label(color_map.color(step as f64 / 10.), "■");
+            }
+        });
+        let mut image = egui::ColorImage::new([row_len, rows.len()], Color32::BLACK);
+        // The top row of the image is the newest
+        for (line, row) in rows.iter().rev().enumerate() {
+            for (column, point) in row.iter().enumerate() {
+                let t = if span > 0. {
+                    (point.y - min) / span
+                } else {
+                    0.5
+                };
+                image[(column, line)] = color_map.color(t);
+            }
+        }
+        let texture = match &mut self.waterfall_texture {
+            Some(WaterfallTexture(texture)) => {
+                texture.set(image, egui::TextureOptions::NEAREST);
+                texture.id()
+            }
+            None => {
+                let texture =
+                    ui.ctx()
+                        .load_texture("waterfall", image, egui::TextureOptions::NEAREST);
+                let id = texture.id();
+                self.waterfall_texture = Some(WaterfallTexture(texture));
+                id
+            }
+        };
+        // Each row stands at the time its first sample arrived
+        let (start, end) = (first[0].x, last[0].x);
+        let row_height = match rows.len() {
+            1 => 1.,
+            count => (end - start) / (count - 1) as f64,
+        };
+        let height = end - start + row_height;
+        let labels = self.axis_labels();
+        egui_plot::Plot::new("waterfall")
+            .x_axis_label("Sample in row")
+            .y_axis_label(labels.x)
+            .show(ui, |plot_ui| {
+                plot_ui.image(
+                    egui_plot::PlotImage::new(
+                        texture,
+                        PlotPoint::new(row_len as f64 / 2., start - row_height / 2. + height / 2.),
+                        [row_len as f32, height as f32],
+                    )
+                    .name(format!("Channel {}", channel)),
+                );
+            });
+    }
+
+    fn show_commands(&mut self, ui: &mut egui::Ui) {
+        if !self.recent_commands.is_empty() {
+            let mut resend = None;
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Recent");
+                for command in self.recent_commands.iter() {
+                    if ui
+                        .small_button(command.summary())
+                        .on_hover_text(command.to_string())
+                        .clicked()
+                    {
+                        resend = Some(command.clone());
+                    }
+                }
+            });
+            if let Some(command) = resend {
+                self.send_command(&command);
+            }
+            ui.separator();
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Add Command").clicked() {
+                self.commands.push(Command::default());
+            }
+            ui.separator();
+            let label = ui.label("Command file");
+            ui.text_edit_singleline(&mut self.commands_file)
+                .labelled_by(label.id);
+            if ui.button("Import").clicked() {
+                self.import_commands();
+            }
+            if ui.button("Export").clicked() {
+                self.export_commands();
+            }
+            ui.separator();
+            egui::ComboBox::from_label("Newlines")
+                .selected_text(self.newline_translation.to_string())
+                .show_ui(ui, |ui| {
+                    for translation in NewlineTranslation::ALL {
+                        ui.selectable_value(
+                            &mut self.newline_translation,
+                            translation,
+                            translation.to_string(),
+                        );
+                    }
+                })
+                .response
+                .on_hover_text(
+                    "Rewrite the newlines of text commands as they are sent, \
+                     after their line ending. Hex commands go out as typed",
+                );
+            ui.checkbox(&mut self.clear_after_send, "Clear After Send")
+                .on_hover_text(
+                    "Empty a command once it's sent, instead of keeping it to send again",
+                );
+            ui.separator();
+            if ui
+                .add_enabled(
+                    !self.selected_commands.is_empty(),
+                    egui::Button::new("Send Selected"),
+                )
+                .on_hover_text("Send every ticked command, top to bottom")
+                .clicked()
+            {
+                let mut selected: Vec<usize> = self.selected_commands.iter().copied().collect();
+                selected.sort_unstable();
+                self.request_send(selected);
+            }
+        });
+        let mut send = None;
+        let selected_commands = &mut self.selected_commands;
+        self.commands.iter_mut().enumerate().for_each(|(idx, c)| {
+            ui.horizontal_wrapped(|ui| {
+                let mut selected = selected_commands.contains(&idx);
+                let select = ui.checkbox(&mut selected, "");
+                let name = ui.label(format!("Command {}", idx));
+                // The label comes after the checkbox, so screen readers are pointed at it
+                if select.labelled_by(name.id).changed() {
+                    if selected {
+                        selected_commands.insert(idx);
+                    } else {
+                        selected_commands.remove(&idx);
+                    }
+                }
+                // Enter sends like in a terminal, Shift+Enter starts a new line of a multi-line command
+                let entered = if c.multiline {
+                    let field = ui
+                        .add(
+                            egui::TextEdit::multiline(&mut c.cmd)
+                                .desired_rows(3)
+                                .return_key(egui::KeyboardShortcut::new(
+                                    egui::Modifiers::SHIFT,
+                                    egui::Key::Enter,
+                                )),
+                        )
+                        .labelled_by(name.id);
+                    field.has_focus()
+                        && ui.input(|i| i.modifiers.is_none() && i.key_pressed(egui::Key::Enter))
+                } else {
+                    let field = ui.text_edit_singleline(&mut c.cmd).labelled_by(name.id);
+                    let entered =
+                        field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if entered {
+                        // Stay in the field for the next command
+                        field.request_focus();
+                    }
+                    entered
+                };
+                if entered {
+                    send = Some(idx);
+                }
+                ui.toggle_value(&mut c.fmt, "ASCII/HEX");
+                ui.add_enabled(
+                    c.fmt && !c.multiline,
+                    egui::Checkbox::new(&mut c.byte_grid, "Byte Grid"),
+                )
+                .on_hover_text("Edit the hex command byte by byte");
+                ui.checkbox(&mut c.confirm, "Confirm")
+                    .on_hover_text("Ask before sending this command");
+                ui.checkbox(&mut c.multiline, "Multi-line")
+                    .on_hover_text("Send a block of text one line at a time");
+                ui.checkbox(&mut c.capture_reply, "Capture Reply")
+                    .on_hover_text(
+                        "Show the lines that arrive right after sending in the reply area",
+                    );
+                if ui.button("Send").clicked() {
+                    send = Some(idx);
+                }
+            });
+            if c.fmt && c.byte_grid && !c.multiline {
+                ui.indent(("byte grid indent", idx), |ui| {
+                    byte_grid(ui, idx, &mut c.cmd);
+                });
+            }
+            if c.multiline {
+                ui.horizontal(|ui| {
+                    ui.add_space(20.);
+                    egui::ComboBox::from_id_source(("line ending", idx))
+                        .selected_text(format!("Line ending: {}", c.line_ending))
+                        .show_ui(ui, |ui| {
+                            for ending in [
+                                LineEnding::None,
+                                LineEnding::Lf,
+                                LineEnding::Cr,
+                                LineEnding::CrLf,
+                            ] {
+                                ui.selectable_value(&mut c.line_ending, ending, ending.to_string());
+                            }
+                        });
+                    let label = ui.label("Delay between lines");
+                    ui.add(
+                        egui::DragValue::new(&mut c.line_delay_ms)
+                            .range(0..=60_000)
+                            .suffix(" ms"),
+                    )
+                    .labelled_by(label.id);
+                    ui.checkbox(&mut c.skip_blank_lines, "Skip blank lines");
+                });
+            }
+        });
+        if let Some(idx) = send {
+            self.request_send(vec![idx]);
+        }
+        ui.separator();
+        self.show_discovery(ui);
+        ui.separator();
+        self.show_reply(ui);
+        ui.separator();
+        self.show_modbus_request(ui);
+        ui.separator();
+        self.show_macros(ui);
+        ui.separator();
+        self.show_keep_alive(ui);
+    }
+
+    /// Settings of the session's keep-alive, apart from the commands
+    fn show_keep_alive(&mut self, ui: &mut egui::Ui) {
+        let keep_alive = &mut self.keep_alive;
+        ui.horizontal_wrapped(|ui| {
+            ui.checkbox(&mut keep_alive.enabled, "Keep-Alive")
+                .on_hover_text(
+                    "Send the payload whenever nothing was sent for the interval, \
+                 for devices that drop idle connections or fall asleep",
+                );
+            let label = ui.label("Payload");
+            ui.add(egui::TextEdit::singleline(&mut keep_alive.payload).desired_width(80.))
+                .labelled_by(label.id)
+                .on_hover_text("Supports \\t, \\n, \\r, \\0, \\\\ and \\xNN escapes, or hex bytes");
+            ui.toggle_value(&mut keep_alive.hex, "ASCII/HEX");
+            let label = ui.label("Every");
+            ui.add(
+                egui::DragValue::new(&mut keep_alive.interval_secs)
+                    .range(1..=3600)
+                    .suffix(" s"),
+            )
+            .labelled_by(label.id)
+            .on_hover_text("Of quiet, any command sent starts the wait over");
+            match keep_alive.bytes() {
+                Ok(bytes) if bytes.is_empty() => {
+                    ui.colored_label(Color32::DARK_RED, "Nothing to send");
+                }
+                Ok(_) => {
+                    if self.connected && keep_alive.enabled {
+                        ui.label(format!("Sent {}", self.keep_alives_sent));
+                    }
                 }
                 Err(e) => {
-                    warn!(
-                        "Error connecting to port: {:?}, because: {}",
-                        &self.current_port, e
+                    ui.colored_label(Color32::DARK_RED, e);
+                }
+            }
+        });
+    }
+
+    /// The discovery command, and the commands it suggested for the user to
+    /// pick from
+    fn show_discovery(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            let label = ui.label("Discovery");
+            ui.add(egui::TextEdit::singleline(&mut self.discovery.command).desired_width(80.))
+                .labelled_by(label.id)
+                .on_hover_text(
+                    "Command the device answers with a list of its commands, like help or ?",
+                );
+            egui::ComboBox::from_id_source("discovery line ending")
+                .selected_text(format!("Line ending: {}", self.discovery.line_ending))
+                .show_ui(ui, |ui| {
+                    for ending in [
+                        LineEnding::None,
+                        LineEnding::Lf,
+                        LineEnding::Cr,
+                        LineEnding::CrLf,
+                    ] {
+                        ui.selectable_value(
+                            &mut self.discovery.line_ending,
+                            ending,
+                            ending.to_string(),
+                        );
+                    }
+                });
+            ui.checkbox(&mut self.discovery.on_connect, "On Connect")
+                .on_hover_text("Send the discovery command whenever a connection is made");
+            let ready = self.connected && !self.discovery.command.trim().is_empty();
+            if ui
+                .add_enabled(ready && !self.discovering, egui::Button::new("Discover"))
+                .on_hover_text(
+                    "Send it now and suggest the first word of every line of the reply, \
+                     captured within the Reply Window",
+                )
+                .on_disabled_hover_text("Connect and enter a command first")
+                .clicked()
+            {
+                self.discover_commands();
+            }
+            if self.discovering {
+                ui.spinner();
+            }
+        });
+        if self.suggestions.is_empty() {
+            return;
+        }
+        let mut add = false;
+        let mut dismiss = false;
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Suggested");
+            for (word, accepted) in self.suggestions.iter_mut() {
+                ui.checkbox(accepted, word.as_str());
+            }
+            add = ui
+                .add_enabled(
+                    self.suggestions.iter().any(|(_, accepted)| *accepted),
+                    egui::Button::new("Add Selected"),
+                )
+                .on_hover_text("Add the ticked commands to the list")
+                .clicked();
+            dismiss = ui.small_button("✖").on_hover_text("Dismiss").clicked();
+        });
+        if add {
+            let added: Vec<Command> = self
+                .suggestions
+                .drain(..)
+                .filter(|(_, accepted)| *accepted)
+                .map(|(word, _)| self.discovery.suggested_command(word))
+                .collect();
+            info!("Added {} suggested commands", added.len());
+            self.log
+                .push(format!("Added {} suggested commands", added.len()));
+            self.commands.extend(added);
+        }
+        if dismiss {
+            self.suggestions.clear();
+        }
+    }
+
+    /// The window replies are captured in, and the last reply
+    fn show_reply(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let label = ui.label("Reply Window");
+            ui.add(
+                egui::DragValue::new(&mut self.reply_window.millis)
+                    .range(10..=60_000)
+                    .suffix(" ms"),
+            )
+            .labelled_by(label.id);
+            ui.add(
+                egui::DragValue::new(&mut self.reply_window.lines)
+                    .range(1..=1_000)
+                    .suffix(" lines"),
+            )
+            .labelled_by(label.id)
+            .on_hover_text("Capturing stops at whichever limit is reached first");
+            if self.reply.is_some() && ui.button("Clear").clicked() {
+                self.reply = None;
+            }
+        });
+        let Some(reply) = &self.reply else {
+            return;
+        };
+        let state = match reply.open {
+            true => "capturing",
+            false => "done",
+        };
+        ui.label(format!(
+            "Reply to {} ({} lines, {})",
+            reply.command,
+            reply.lines.len(),
+            state
+        ));
+        egui::ScrollArea::vertical()
+            .id_source("reply")
+            .max_height(120.)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for line in reply.lines.iter() {
+                    ui.monospace(line);
+                }
+            });
+    }
+
+    /// Build a Modbus RTU request from its fields, to send or keep as a command
+    fn show_modbus_request(&mut self, ui: &mut egui::Ui) {
+        let request = &mut self.modbus_request;
+        let mut command = None;
+        ui.horizontal_wrapped(|ui| {
+            let title = ui.label("Modbus Request");
+            let label = ui.label("Address");
+            ui.add(egui::DragValue::new(&mut request.address).range(1..=247))
+                .labelled_by(label.id);
+            egui::ComboBox::from_id_source("modbus function")
+                .selected_text(request.function.to_string())
+                .show_ui(ui, |ui| {
+                    for function in Function::ALL {
+                        ui.selectable_value(&mut request.function, function, function.to_string());
+                    }
+                })
+                .response
+                .labelled_by(title.id);
+            let label = ui.label("Start");
+            ui.add(egui::DragValue::new(&mut request.start))
+                .labelled_by(label.id);
+            match request.function {
+                Function::WriteSingleCoil => {
+                    let mut on = request.value != 0;
+                    ui.checkbox(&mut on, "On");
+                    request.value = u16::from(on);
+                }
+                Function::WriteSingleRegister => {
+                    let label = ui.label("Value");
+                    ui.add(egui::DragValue::new(&mut request.value))
+                        .labelled_by(label.id);
+                }
+                _ => {
+                    let label = ui.label("Count");
+                    ui.add(egui::DragValue::new(&mut request.value).range(1..=125))
+                        .labelled_by(label.id);
+                }
+            }
+            let hex = request
+                .encode()
+                .iter()
+                .map(|b| format!("{:02X}", b))
+                .collect::<Vec<_>>()
+                .join(" ");
+            ui.monospace(&hex).on_hover_text("The request with its CRC");
+            let make = || Command {
+                cmd: hex.clone(),
+                fmt: true,
+                ..Default::default()
+            };
+            if ui.button("Send").clicked() {
+                command = Some((make(), true));
+            }
+            if ui
+                .button("Add as Command")
+                .on_hover_text("Keep the request in the command list")
+                .clicked()
+            {
+                command = Some((make(), false));
+            }
+        });
+        match command {
+            Some((command, true)) => {
+                self.send_command(&command);
+            }
+            Some((command, false)) => self.commands.push(command),
+            None => {}
+        }
+    }
+
+    /// Send `indices` of `commands` in order, asking first if any of them wants confirmation
+    fn request_send(&mut self, indices: Vec<usize>) {
+        if indices.iter().any(|idx| self.commands[*idx].confirm) {
+            self.pending_send = indices;
+            return;
+        }
+        indices
+            .into_iter()
+            .for_each(|idx| self.send_listed_command(idx));
+    }
+
+    /// Send `commands[idx]`, and clear it for the next one if asked to
+    fn send_listed_command(&mut self, idx: usize) {
+        let command = self.commands[idx].clone();
+        self.send_command(&command);
+        if self.clear_after_send {
+            self.commands[idx].cmd.clear()
+        }
+    }
+
+    /// Ask whether the commands in `pending_send` should really go out
+    fn show_send_confirmation(&mut self, ctx: &egui::Context) {
+        if self.pending_send.is_empty() {
+            return;
+        }
+        self.pending_send.retain(|idx| *idx < self.commands.len());
+        let commands: Vec<String> = self
+            .pending_send
+            .iter()
+            .map(|idx| self.commands[*idx].to_string())
+            .collect();
+        let mut answer = None;
+        egui::Window::new("Confirm send")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0., 0.])
+            .show(ctx, |ui| {
+                ui.label("Really send:");
+                commands.iter().for_each(|command| {
+                    ui.label(command);
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Send").clicked() {
+                        answer = Some(true);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        answer = Some(false);
+                    }
+                });
+            });
+        match answer {
+            Some(true) => {
+                std::mem::take(&mut self.pending_send)
+                    .into_iter()
+                    .for_each(|idx| self.send_listed_command(idx));
+            }
+            Some(false) => {
+                self.pending_send.clear();
+                for command in commands {
+                    info!("Cancelled sending {}", command);
+                    self.log.push(format!("Cancelled sending {}", command));
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Replace the command list with the one saved in `commands_file`
+    fn import_commands(&mut self) {
+        match commands::import(&self.commands_file) {
+            Ok(commands) => {
+                info!(
+                    "Imported {} commands from {}",
+                    commands.len(),
+                    self.commands_file
+                );
+                self.log.push(format!(
+                    "Imported {} commands from {}",
+                    commands.len(),
+                    self.commands_file
+                ));
+                self.commands = commands;
+                self.selected_commands.clear();
+            }
+            Err(e) => {
+                warn!(
+                    "Error importing commands from {}, because: {}",
+                    self.commands_file, e
+                );
+                self.log.push(format!(
+                    "Error importing commands from {}, because: {}",
+                    self.commands_file, e
+                ));
+                self.notice = Some(format!("Couldn't import commands, because {}", e));
+            }
+        }
+    }
+
+    fn export_commands(&mut self) {
+        match commands::export(&self.commands_file, &self.commands) {
+            Ok(()) => {
+                info!("Exported commands to {}", self.commands_file);
+                self.log
+                    .push(format!("Exported commands to {}", self.commands_file));
+            }
+            Err(e) => {
+                warn!(
+                    "Error exporting commands to {}, because: {}",
+                    self.commands_file, e
+                );
+                self.log.push(format!(
+                    "Error exporting commands to {}, because: {}",
+                    self.commands_file, e
+                ));
+                self.notice = Some(format!("Couldn't export commands, because {}", e));
+            }
+        }
+    }
+
+    fn show_macros(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Macros");
+            if ui.button("Add Macro").clicked() {
+                self.macros.push(Macro {
+                    name: format!("Macro {}", self.macros.len()),
+                    ..Default::default()
+                });
+            }
+        });
+        if let Some(run) = &self.macro_run {
+            let steps = self.macros[run.macro_idx].steps.len();
+            let progress = egui::ProgressBar::new(run.next_step as f32 / steps.max(1) as f32)
+                .text(format!(
+                    "Running '{}': step {}/{}",
+                    self.macros[run.macro_idx].name, run.next_step, steps
+                ))
+                .desired_width(300.);
+            let abort = ui
+                .horizontal(|ui| {
+                    ui.add(progress);
+                    ui.button("Abort").clicked()
+                })
+                .inner;
+            if abort {
+                self.abort_macro();
+            }
+        }
+        let running = self.macro_run.is_some();
+        let (mut run, mut remove) = (None, None);
+        self.macros.iter_mut().enumerate().for_each(|(idx, m)| {
+            egui::CollapsingHeader::new(m.name.clone())
+                .id_source(("macro", idx))
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Name");
+                        ui.text_edit_singleline(&mut m.name);
+                        if ui.add_enabled(!running, egui::Button::new("Run")).clicked() {
+                            run = Some(idx);
+                        }
+                        if ui
+                            .add_enabled(!running, egui::Button::new("Delete"))
+                            .clicked()
+                        {
+                            remove = Some(idx);
+                        }
+                    });
+                    let mut remove_step = None;
+                    m.steps.iter_mut().enumerate().for_each(|(step_idx, step)| {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("Step {}", step_idx));
+                            ui.text_edit_singleline(&mut step.command.cmd);
+                            ui.toggle_value(&mut step.command.fmt, "ASCII/HEX");
+                            ui.label("then wait");
+                            ui.add(egui::DragValue::new(&mut step.delay_ms).suffix(" ms"));
+                            if ui.button("Remove").clicked() {
+                                remove_step = Some(step_idx);
+                            }
+                        });
+                    });
+                    if let Some(step_idx) = remove_step {
+                        m.steps.remove(step_idx);
+                    }
+                    if ui.button("Add Step").clicked() {
+                        m.steps.push(MacroStep::default());
+                    }
+                });
+        });
+        if let Some(idx) = remove {
+            self.macros.remove(idx);
+        }
+        if let Some(idx) = run {
+            self.start_macro(idx);
+        }
+        ui.separator();
+        self.show_rules(ui);
+    }
+
+    fn show_rules(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Rules")
+                .on_hover_text("React to every received line that contains the pattern");
+            if ui.button("Add Rule").clicked() {
+                self.rules.push(Rule::default());
+            }
+            if ui
+                .add_enabled(
+                    !self.bookmarks.is_empty(),
+                    egui::Button::new("Clear Bookmarks"),
+                )
+                .clicked()
+            {
+                self.bookmarks.clear();
+            }
+        });
+        let mut remove = None;
+        self.rules.iter_mut().enumerate().for_each(|(idx, rule)| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut rule.enabled, "");
+                ui.label("When a line contains");
+                ui.text_edit_singleline(&mut rule.pattern);
+                egui::ComboBox::from_id_source(("rule action", idx))
+                    .selected_text(rule.action.to_string())
+                    .show_ui(ui, |ui| {
+                        for action in RuleAction::ALL {
+                            ui.selectable_value(&mut rule.action, action, action.to_string());
+                        }
+                    });
+                if rule.action == RuleAction::Send {
+                    ui.text_edit_singleline(&mut rule.reply.cmd);
+                    ui.toggle_value(&mut rule.reply.fmt, "ASCII/HEX");
+                }
+                if ui.button("Remove").clicked() {
+                    remove = Some(idx);
+                }
+            });
+        });
+        if let Some(idx) = remove {
+            self.rules.remove(idx);
+            // Reply times are kept by index, which just shifted
+            self.rule_replied.clear();
+        }
+    }
+
+    fn show_record_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Export")
+                .selected_text(format!("{}", self.record_options.scope))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.record_options.scope, ExportScope::All, "All")
+                        .on_hover_text("The whole buffer");
+                    ui.selectable_value(
+                        &mut self.record_options.scope,
+                        ExportScope::Visible,
+                        "Visible window",
+                    )
+                    .on_hover_text("Only what the plot currently shows");
+                });
+        });
+        if !self.plot_data.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Channels");
+                for idx in 0..self.plot_data.len() {
+                    let mut included = !self.record_options.excluded_channels.contains(&idx);
+                    if ui
+                        .checkbox(&mut included, format!("Channel {}", idx))
+                        .changed()
+                    {
+                        if included {
+                            self.record_options.excluded_channels.remove(&idx);
+                        } else {
+                            self.record_options.excluded_channels.insert(idx);
+                        }
+                    }
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.label("Data file");
+            ui.text_edit_singleline(&mut self.record_options.data_path);
+            let format = self.record_options.data_format;
+            egui::ComboBox::from_id_source("Data format")
+                .selected_text(format.to_string())
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(
+                        &mut self.record_options.data_format,
+                        DataFormat::Csv,
+                        "CSV",
                     );
+                    ui.selectable_value(
+                        &mut self.record_options.data_format,
+                        DataFormat::Binary,
+                        "Binary",
+                    )
+                    .on_hover_text("Much smaller than CSV for long captures");
+                });
+            if self.record_options.data_format == DataFormat::Csv {
+                ui.checkbox(
+                    &mut self.record_options.split_channels,
+                    "One File per Channel",
+                )
+                .on_hover_text(
+                    "A time,value CSV for every exported channel, named after the data file \
+                     and the channel",
+                );
+            }
+            if self.record_options.data_format != format {
+                // Keep the file name telling what's inside
+                self.record_options.data_path = PathBuf::from(&self.record_options.data_path)
+                    .with_extension(self.record_options.data_format.extension())
+                    .display()
+                    .to_string();
+            }
+            if ui
+                .button("Load Data")
+                .on_hover_text("Plot a CSV or binary data export instead of the capture")
+                .clicked()
+            {
+                let path = self.record_options.data_path.clone();
+                match self.load_data(&path) {
+                    Ok(()) => {
+                        info!("Loaded data from {}", path);
+                        self.log.push(format!("Loaded data from {}", path));
+                    }
+                    Err(e) => {
+                        warn!("Error loading {}, because: {}", path, e);
+                        self.log
+                            .push(format!("Error loading {}, because: {}", path, e));
+                    }
+                }
+            }
+            if ui.button("Export Data").clicked() {
+                self.export_data_logged();
+            }
+        });
+        if ui
+            .button("Copy Data as CSV")
+            .on_hover_text("Same rows as Export Data, for pasting into a spreadsheet")
+            .clicked()
+        {
+            let mut csv = Vec::new();
+            match self.write_data(&mut csv) {
+                Ok(()) => {
+                    ui.ctx()
+                        .copy_text(String::from_utf8_lossy(&csv).into_owned());
+                    info!("Copied {} bytes of data to the clipboard", csv.len());
                     self.log.push(format!(
-                        "Error connecting to port: {:?}, because: {}",
-                        &self.current_port, e
+                        "Copied {} bytes of data to the clipboard",
+                        csv.len()
                     ));
-                    warn!("{}", &e);
+                }
+                Err(e) => {
+                    warn!("Error copying data, because: {}", e);
+                    self.log.push(format!("Error copying data, because: {}", e));
                 }
             }
-        };
-    }
-
-    fn show_text_view(&mut self, ui: &mut egui::Ui) {
+        }
         ui.horizontal(|ui| {
-            ui.label("Serial Monitor");
-            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                ui.checkbox(&mut self.text_view_options.auto_scroll, "AutoScroll");
-                ui.checkbox(&mut self.text_view_options.time_stamp, "Time Stamps");
-            })
+            ui.label("Statistics file");
+            ui.text_edit_singleline(&mut self.record_options.stats_path);
+            if ui.button("Export Statistics").clicked() {
+                let path = self.record_options.stats_path.clone();
+                match self.export_stats(&path) {
+                    Ok(()) => {
+                        info!("Exported statistics to {}", path);
+                        self.log.push(format!("Exported statistics to {}", path));
+                    }
+                    Err(e) => {
+                        warn!("Error exporting statistics to {}, because: {}", path, e);
+                        self.log.push(format!(
+                            "Error exporting statistics to {}, because: {}",
+                            path, e
+                        ));
+                    }
+                }
+            }
         });
-        ui.add_space(10.);
-        let text_style = TextStyle::Body;
-        let row_height = ui.text_style_height(&text_style);
-
-        ScrollArea::vertical()
-            .max_width(f32::INFINITY)
-            .stick_to_bottom(self.text_view_options.auto_scroll)
-            .auto_shrink(false)
-            .show_rows(ui, row_height, self.n_items, |ui, row_range| {
-                for row in row_range {
-                    let text = match self.text_view_options.time_stamp {
-                        true => {
-                            format!(
-                                "[{}.{}] This is row {}",
-                                self.absolute_time.elapsed().as_secs(),
-                                self.absolute_time.elapsed().subsec_millis(),
-                                row + 1
-                            )
-                        }
-                        false => format!("This is row {}", row + 1),
-                    };
-                    if self.text_view_options.auto_scroll {
-                        ui.scroll_to_cursor(Some(Align::TOP));
+        ui.horizontal(|ui| {
+            ui.label("Figure file");
+            ui.text_edit_singleline(&mut self.record_options.svg_path);
+            if ui
+                .button("Export SVG")
+                .on_hover_text(
+                    "The plot as currently shown, as a vector figure with axes and legend",
+                )
+                .clicked()
+            {
+                let path = self.record_options.svg_path.clone();
+                match self.export_svg(&path) {
+                    Ok(()) => {
+                        info!("Exported the plot to {}", path);
+                        self.log.push(format!("Exported the plot to {}", path));
+                    }
+                    Err(e) => {
+                        warn!("Error exporting the plot to {}, because: {}", path, e);
+                        self.log.push(format!(
+                            "Error exporting the plot to {}, because: {}",
+                            path, e
+                        ));
                     }
-                    ui.label(text);
                 }
-            });
-    }
-
-    fn show_plot_settings(&mut self, ui: &mut egui::Ui) {
-        let x_range = self.plot_options.x_axis;
-        let y_range = self.plot_options.y_axis;
-        ui.horizontal_wrapped(|ui| {
-            // INFO: Add some way to enforce minimum buffer size and min x axis range
-            ui.label("Set Buffer Size");
-            ui.add(egui::DragValue::new(&mut self.plot_options.buffer_size).range(0..=100_000));
-            ui.add_space(15.);
-            ui.label("Range for X-axis");
-            // Min
-            ui.add(egui::DragValue::new(&mut self.plot_options.x_axis[0]).range(0..=x_range[1]));
-            // Max
-            ui.add(
-                egui::DragValue::new(&mut self.plot_options.x_axis[1])
-                    .range(0..=self.plot_options.plot_width),
-            );
+            }
         });
-        ui.horizontal_wrapped(|ui| {
-            ui.label("Set Plot Width ");
+        ui.horizontal(|ui| {
+            let summary = &mut self.record_options.summary;
+            ui.checkbox(&mut summary.enabled, "Log a Summary Every")
+                .on_hover_text(
+                    "Packets, parse errors, bad checksums and the mean of every channel, \
+                     logged while connected",
+                );
             ui.add(
-                egui::DragValue::new(&mut self.plot_options.plot_width)
-                    .range(0..=self.plot_options.buffer_size),
+                egui::DragValue::new(&mut summary.interval_secs)
+                    .range(1..=86_400)
+                    .suffix(" s"),
             );
-            ui.add_space(15.);
-            ui.label("Range for Y-axis");
-            // Min
-            ui.add(egui::DragValue::new(&mut self.plot_options.y_axis[0]).range(0..=y_range[1]));
-            // Max
-            ui.add(
-                egui::DragValue::new(&mut self.plot_options.y_axis[1])
-                    .range(0..=self.plot_options.plot_width),
+            ui.checkbox(&mut summary.csv, "Also to CSV");
+            ui.add_enabled(
+                summary.csv,
+                egui::TextEdit::singleline(&mut summary.csv_path),
             );
         });
-
         ui.horizontal(|ui| {
-            ui.label("Select Delimiter");
-            egui::ComboBox::from_label("Delimiter")
-                .selected_text(format!("{}", self.plot_options.delimiter))
-                .show_ui(ui, |ui| {
-                    ui.selectable_value(
-                        &mut self.plot_options.delimiter,
-                        Delimiter::Space,
-                        "Space",
-                    );
-                    ui.selectable_value(&mut self.plot_options.delimiter, Delimiter::Tab, "Tab");
-                    ui.selectable_value(
-                        &mut self.plot_options.delimiter,
-                        Delimiter::Comma,
-                        "Comma",
-                    );
-                    ui.selectable_value(
-                        &mut self.plot_options.delimiter,
-                        Delimiter::Other(String::default()),
-                        "Other",
-                    );
+            ui.label("Workspace file");
+            ui.text_edit_singleline(&mut self.record_options.workspace_path);
+            let path = self.record_options.workspace_path.clone();
+            if ui
+                .button("Save Workspace")
+                .on_hover_text("Settings, commands and the plotted data in one file")
+                .clicked()
+            {
+                match self.save_workspace(&path) {
+                    Ok(()) => {
+                        self.unexported = false;
+                        info!("Saved workspace to {}", path);
+                        self.log.push(format!("Saved workspace to {}", path));
+                    }
+                    Err(e) => {
+                        warn!("Error saving workspace to {}, because: {}", path, e);
+                        self.log.push(format!(
+                            "Error saving workspace to {}, because: {}",
+                            path, e
+                        ));
+                    }
+                }
+            }
+            if ui.button("Load Workspace").clicked() {
+                match self.load_workspace(&path) {
+                    Ok(()) => {
+                        info!("Loaded workspace from {}", path);
+                        self.log.push(format!("Loaded workspace from {}", path));
+                    }
+                    Err(e) => {
+                        warn!("Error loading workspace from {}, because: {}", path, e);
+                        self.log.push(format!(
+                            "Error loading workspace from {}, because: {}",
+                            path, e
+                        ));
+                    }
+                }
+            }
+        });
+    }
+
+    /// Export the data to the data file and log how that went, `true` if it worked
+    fn export_data_logged(&mut self) -> bool {
+        let path = self.record_options.data_path.clone();
+        match self.export_data(&path) {
+            Ok(()) if self.splits_channels() => {
+                self.unexported = false;
+                let count = self.exported_channels().count();
+                info!("Exported {} channel files next to {}", count, path);
+                self.log
+                    .push(format!("Exported {} channel files next to {}", count, path));
+                true
+            }
+            Ok(()) => {
+                self.unexported = false;
+                info!("Exported data to {}", path);
+                self.log.push(format!("Exported data to {}", path));
+                true
+            }
+            Err(e) => {
+                warn!("Error exporting data to {}, because: {}", path, e);
+                self.log
+                    .push(format!("Error exporting data to {}, because: {}", path, e));
+                false
+            }
+        }
+    }
+
+    /// Hold off closing the window while connected with samples that were never
+    /// exported, and ask what to do with them instead
+    fn intercept_close(&mut self, ctx: &egui::Context) {
+        if !ctx.input(|i| i.viewport().close_requested()) || self.quit_confirmed {
+            return;
+        }
+        if self.connected && self.unexported {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.quit_prompt = true;
+        }
+    }
+
+    /// Ask whether to export the capture before quitting, stay, or quit anyway
+    fn show_quit_confirmation(&mut self, ctx: &egui::Context) {
+        if !self.quit_prompt {
+            return;
+        }
+        let mut answer = None;
+        egui::Window::new("Quit while capturing?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0., 0.])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Still connected, and the samples captured since the last export \
+                     aren't saved anywhere. Export Data writes them to {}.",
+                    self.record_options.data_path
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("Export and Quit").clicked() {
+                        answer = Some(QuitAnswer::Export);
+                    }
+                    if ui.button("Quit Without Saving").clicked() {
+                        answer = Some(QuitAnswer::Discard);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        answer = Some(QuitAnswer::Stay);
+                    }
+                });
+            });
+        let quit = match answer {
+            Some(QuitAnswer::Export) => self.export_data_logged(),
+            Some(QuitAnswer::Discard) => true,
+            Some(QuitAnswer::Stay) => {
+                info!("Cancelled quitting");
+                self.log.push(String::from("Cancelled quitting"));
+                false
+            }
+            None => return,
+        };
+        // A failed export keeps the prompt up, the log says why
+        self.quit_prompt = !quit && answer != Some(QuitAnswer::Stay);
+        if quit {
+            self.quit_confirmed = true;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+    }
+
+    /// X range the exports are limited to by the selected scope
+    fn export_range(&self) -> RangeInclusive<f64> {
+        match (self.record_options.scope, &self.visible_x) {
+            (ExportScope::Visible, Some(range)) => range.clone(),
+            _ => f64::NEG_INFINITY..=f64::INFINITY,
+        }
+    }
+
+    /// Channels the exports include, with their index
+    fn exported_channels(&self) -> impl Iterator<Item = (usize, &Measurement)> {
+        self.plot_data
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !self.record_options.excluded_channels.contains(idx))
+    }
+
+    /// Whether exports write a file per channel instead of one for all
+    fn splits_channels(&self) -> bool {
+        self.record_options.split_channels && self.record_options.data_format == DataFormat::Csv
+    }
+
+    /// Write every sample in the export range to `path`, one row per sample
+    fn export_data(&self, path: &str) -> io::Result<()> {
+        if self.splits_channels() {
+            return self.export_channel_files(path);
+        }
+        let mut file = BufWriter::new(File::create(path)?);
+        match self.record_options.data_format {
+            DataFormat::Csv => self.write_data(&mut file)?,
+            DataFormat::Binary => self.capture().write(&mut file)?,
+        }
+        file.flush()
+    }
+
+    /// File next to `path` the samples of channel `idx` are exported to, like
+    /// `data_channel_2.csv` for `data.csv`
+    fn channel_path(&self, path: &str, idx: usize) -> PathBuf {
+        let path = PathBuf::from(path);
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        // Only characters that are safe in a file name on every system
+        let mut label = String::new();
+        for c in self.channel_label(idx).chars() {
+            match c.is_ascii_alphanumeric() {
+                true => label.push(c.to_ascii_lowercase()),
+                false if !label.ends_with('_') => label.push('_'),
+                false => {}
+            }
+        }
+        let name = format!("{}_{}.csv", stem, label.trim_matches('_'));
+        path.with_file_name(name)
+    }
+
+    /// Write the samples of every exported channel in the export range to a
+    /// file of its own next to `path`, one row per sample
+    fn export_channel_files(&self, path: &str) -> io::Result<()> {
+        let range = self.export_range();
+        let origin = self.time_origin();
+        for (idx, data) in self.exported_channels() {
+            let mut file = BufWriter::new(File::create(self.channel_path(path, idx))?);
+            writeln!(file, "time,value,timestamp")?;
+            for point in data.values.iter().filter(|p| range.contains(&p.x)) {
+                writeln!(
+                    file,
+                    "{},{},{}",
+                    point.x,
+                    point.y,
+                    self.csv_timestamp(origin, point.x)
+                )?;
+            }
+            file.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Quoted wall-clock time stamp of `x` seconds after `origin`
+    fn csv_timestamp(&self, origin: Duration, x: f64) -> String {
+        let wall = origin + Duration::from_secs_f64(x.max(0.));
+        let timestamp = timefmt::format_or_default(&self.timestamp_format, wall.as_millis());
+        format!("\"{}\"", timestamp.replace('"', "\"\""))
+    }
+
+    /// Wall-clock time of x = 0, since the Unix epoch
+    fn time_origin(&self) -> Duration {
+        std::time::SystemTime::now()
+            .checked_sub(self.absolute_time.elapsed())
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .unwrap_or_default()
+    }
+
+    /// Every sample in the export range, for a binary export
+    fn capture(&self) -> Capture {
+        let range = self.export_range();
+        Capture {
+            origin_millis: self.time_origin().as_millis() as u64,
+            channels: self
+                .exported_channels()
+                .map(|(idx, data)| CaptureChannel {
+                    index: idx as u32,
+                    label: self.channel_label(idx),
+                    points: data
+                        .values
+                        .iter()
+                        .filter(|p| range.contains(&p.x))
+                        .map(|p| [p.x, p.y])
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Write every sample in the export range as CSV, one row per sample
+    fn write_data(&self, out: &mut impl Write) -> io::Result<()> {
+        let range = self.export_range();
+        let origin = self.time_origin();
+        writeln!(out, "{},timestamp", DATA_HEADER)?;
+        for (idx, data) in self.exported_channels() {
+            for point in data.values.iter().filter(|p| range.contains(&p.x)) {
+                writeln!(
+                    out,
+                    "Channel {},{},{},{}",
+                    idx,
+                    point.x,
+                    point.y,
+                    self.csv_timestamp(origin, point.x)
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The analog plot at the bounds it was last drawn with, for an SVG export.
+    ///
+    /// Series are built the way `draw_plot` draws them, limited to the
+    /// exported channels. Digital lanes and hidden channels aren't part of
+    /// it, stacked lanes are drawn overlaid.
+    fn plot_figure(&self) -> Option<Figure> {
+        let (x, y) = (self.visible_x.clone()?, self.visible_y.clone()?);
+        let labels = self.axis_labels();
+        let log = self.log_y(&self.shown_analog());
+        let gaps = self.plot_options.gaps;
+        let segments = |segments: Vec<PlotPoints>| -> Vec<Vec<[f64; 2]>> {
+            let segments = match log {
+                true => log_scaled(segments, &mut 0),
+                false => segments,
+            };
+            segments
+                .iter()
+                .map(|points| points.points().iter().map(|p| [p.x, p.y]).collect())
+                .collect()
+        };
+        let mut series = Vec::new();
+        for (idx, data) in self.exported_channels() {
+            if self.is_digital(idx) || !self.is_shown(idx) {
+                continue;
+            }
+            let name = self.line_name(idx);
+            if !data.reference.is_empty() {
+                series.push(Series {
+                    name: format!("{} reference", name),
+                    color: Color32::from_gray(160)
+                        .gamma_multiply(0.5)
+                        .to_srgba_unmultiplied(),
+                    stroke: Stroke::Solid,
+                    segments: segments(vec![data.reference_values()]),
+                });
+            }
+            let color = channel_color(idx).to_srgba_unmultiplied();
+            let (stroke, values) = match self.plot_options.style {
+                PlotStyle::Line => (Stroke::Solid, data.plot_values(gaps)),
+                PlotStyle::Points => (Stroke::Points, data.plot_values(gaps)),
+                PlotStyle::Steps => (Stroke::Solid, data.step_values(gaps)),
+            };
+            series.push(Series {
+                name: name.clone(),
+                color,
+                stroke,
+                segments: segments(values),
+            });
+            if self
+                .plot_options
+                .channels
+                .get(idx)
+                .is_some_and(|c| c.derivative)
+            {
+                series.push(Series {
+                    name: format!("{} d/dt", name),
+                    color,
+                    stroke: Stroke::Dashed,
+                    segments: segments(data.derivative_values(gaps)),
                 });
-            if let Delimiter::Other(ref mut custom) = self.plot_options.delimiter {
-                ui.label("Custom Delimiter: ");
-                ui.text_edit_singleline(custom);
             }
+        }
+        let dropouts = self.dropouts.iter().map(|x| Marker {
+            x: *x,
+            color: Color32::GRAY.to_srgba_unmultiplied(),
+        });
+        let bookmarks = self.bookmarks.iter().map(|(x, _)| Marker {
+            x: *x,
+            color: Color32::GOLD.to_srgba_unmultiplied(),
         });
+        Some(Figure {
+            x_range: [*x.start(), *x.end()],
+            y_range: [*y.start(), *y.end()],
+            x_label: labels.x,
+            y_label: labels.y,
+            line_width: self.plot_options.line_width,
+            series,
+            markers: dropouts.chain(bookmarks).collect(),
+        })
     }
 
-    fn show_commands(&mut self, ui: &mut egui::Ui) {
-        if ui.button("Add Command").clicked() {
-            self.commands.push(Command::default());
+    /// Write the plot as it is shown to `path` as an SVG figure
+    fn export_svg(&self, path: &str) -> io::Result<()> {
+        let figure = self
+            .plot_figure()
+            .ok_or_else(|| io::Error::other("the plot hasn't been drawn yet"))?;
+        let labels = self.axis_labels();
+        let log = self.log_y(&self.shown_analog());
+        let mut file = BufWriter::new(File::create(path)?);
+        figure.write(&mut file, |value| match (log, labels.engineering) {
+            (true, engineering_notation) => log_tick(value, engineering_notation),
+            (false, true) => engineering(value),
+            (false, false) => svg::format_tick(value),
+        })?;
+        file.flush()
+    }
+
+    /// Write one row of summary statistics per channel to `path`
+    fn export_stats(&self, path: &str) -> io::Result<()> {
+        let range = self.export_range();
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "channel,count,min,max,mean,stddev")?;
+        for (idx, data) in self.exported_channels() {
+            match data.stats_within(&range) {
+                Some(stats) => writeln!(
+                    file,
+                    "Channel {},{},{},{},{},{}",
+                    idx, stats.count, stats.min, stats.max, stats.mean, stats.std_dev
+                )?,
+                None => writeln!(file, "Channel {},0,,,,", idx)?,
+            }
         }
-        self.commands.iter_mut().enumerate().for_each(|(idx, c)| {
-            ui.horizontal_wrapped(|ui| {
-                ui.label(format!("Command {}", idx));
-                ui.text_edit_singleline(&mut c.cmd);
-                ui.toggle_value(&mut c.fmt, "ASCII/HEX");
-                if ui.button("Send").clicked() {
-                    // TODO Send command
-                    info!("Sending Command {}", c);
-                    self.log.push(format!("Sending Command {}", c));
-                    c.cmd.clear()
+        file.flush()
+    }
+
+    fn draw_status_bar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if let Some(notice) = &self.notice {
+                ui.colored_label(Color32::GOLD, notice);
+                if ui.small_button("✖").on_hover_text("Dismiss").clicked() {
+                    self.notice = None;
+                }
+                ui.separator();
+            }
+            self.show_connection_status(ui);
+            if self.connected {
+                let (health, parts) = self.health();
+                let breakdown: Vec<String> = parts
+                    .into_iter()
+                    .map(|(rating, part)| format!("{} ({})", part, rating))
+                    .collect();
+                ui.separator();
+                ui.colored_label(health_color(health), format!("Health: {}", health))
+                    .on_hover_text(format!(
+                        "Over the last {} s:\n{}",
+                        HEALTH_WINDOW.as_secs(),
+                        breakdown.join("\n")
+                    ));
+                let secs = self.capture_duration().as_secs();
+                ui.separator();
+                ui.label(format!(
+                    "{:02}:{:02}:{:02}",
+                    secs / 3600,
+                    secs / 60 % 60,
+                    secs % 60
+                ))
+                .on_hover_text("Capture duration");
+                if self.paused_at.is_some() {
+                    ui.colored_label(Color32::GOLD, "Paused");
+                }
+            }
+            ui.separator();
+            ui.label(format!(
+                "{:.0} packets/s, {:.0} B/s",
+                self.throughput.packet_rate, self.throughput.byte_rate
+            ));
+            ui.separator();
+            ui.label(format!(
+                "Total: {} packets, {} bytes",
+                self.throughput.total_packets, self.throughput.total_bytes
+            ));
+            if ui.small_button("Reset").clicked() {
+                self.throughput = Throughput::default();
+            }
+            if self.bad_checksums > 0 {
+                ui.separator();
+                ui.colored_label(
+                    Color32::DARK_RED,
+                    format!("Bad checksums: {}", self.bad_checksums),
+                );
+            }
+            let idle = self.last_packet.elapsed();
+            if self.connected && idle >= self.idle_timeout {
+                ui.separator();
+                ui.colored_label(Color32::GOLD, format!("No data for {} s", idle.as_secs()));
+            }
+            if self.connected && self.baud_mismatch.is_likely() {
+                ui.separator();
+                ui.colored_label(Color32::GOLD, "Possible baud mismatch")
+                    .on_hover_text(format!(
+                        "{:.0} % of the bytes lately aren't printable, which usually means the \
+                         baud rate is wrong. Disconnect and try Auto-detect baud in the Port panel",
+                        self.baud_mismatch.ratio * 100.
+                    ));
+                if ui.small_button("✖").on_hover_text("Dismiss").clicked() {
+                    self.baud_mismatch.dismissed = true;
+                }
+            }
+            self.show_bytes_waiting(ui);
+            if self.is_lagging() {
+                ui.separator();
+                ui.colored_label(Color32::DARK_RED, "Receiver lagging")
+                    .on_hover_text("The display can't keep up, incoming packets are being dropped");
+            }
+            if self.seen_dropped > 0 {
+                ui.separator();
+                ui.colored_label(Color32::DARK_RED, format!("Dropped: {}", self.seen_dropped))
+                    .on_hover_text("Packets missing from the plot and text view");
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui
+                    .small_button("Compact")
+                    .on_hover_text("Hide the panels and give the plot the whole window (F11)")
+                    .clicked()
+                {
+                    self.compact = true;
                 }
             });
         });
     }
 
-    fn show_record_settings(&self, ui: &mut egui::Ui) {
-        ui.label("Showing recording settings");
+    /// Gauge of the bytes the OS holds that haven't been read yet
+    fn show_bytes_waiting(&self, ui: &mut egui::Ui) {
+        // Rough size of a driver's receive buffer, the gauge is full there
+        const GAUGE_FULL: u32 = 4096;
+        if !self.connected {
+            return;
+        }
+        let Some(waiting) = self
+            .serial_shared
+            .bytes_waiting
+            .read()
+            .ok()
+            .and_then(|w| *w)
+        else {
+            return;
+        };
+        ui.separator();
+        let fraction = (waiting as f32 / GAUGE_FULL as f32).min(1.);
+        let mut bar = egui::ProgressBar::new(fraction)
+            .desired_width(80.)
+            .text(format!("{} B waiting", waiting));
+        if fraction >= 0.5 {
+            bar = bar.fill(Color32::DARK_RED);
+        }
+        ui.add(bar).on_hover_text(
+            "Bytes the OS received but Symphony hasn't read yet, rising means reading can't keep up",
+        );
+    }
+
+    fn show_connection_status(&self, ui: &mut egui::Ui) {
+        if self.connected {
+            let label = ui.colored_label(Color32::DARK_GREEN, "Connected");
+            if let Some(info) = &self.port_info {
+                label.on_hover_text(info);
+            }
+            if self.source_kind == SourceKind::Demo {
+                ui.colored_label(Color32::GOLD, "DEMO DATA")
+                    .on_hover_text("Nothing shown comes from a real device");
+            }
+        } else if self.connecting || self.reconnect_at.is_some() {
+            ui.spinner();
+            let text = if self.reconnect_at.is_some() {
+                "Reconnecting..."
+            } else {
+                "Connecting..."
+            };
+            ui.colored_label(CONNECTING_COLOR, text);
+        } else {
+            ui.colored_label(Color32::GRAY, "Disconnected");
+        }
+    }
+
+    /// Connection status over the corner of the plot, all that is left of the
+    /// panels in compact mode
+    fn draw_compact_overlay(&mut self, ctx: &egui::Context) {
+        egui::Area::new(egui::Id::new("Compact overlay"))
+            .anchor(egui::Align2::RIGHT_TOP, [-10., 10.])
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        self.show_connection_status(ui);
+                        if self.paused_at.is_some() {
+                            ui.colored_label(Color32::GOLD, "Paused");
+                        }
+                        if ui
+                            .small_button("Exit Compact")
+                            .on_hover_text("Bring the panels back (F11)")
+                            .clicked()
+                        {
+                            self.compact = false;
+                        }
+                    });
+                });
+            });
     }
 
-    fn show_log(&self, ui: &mut egui::Ui) {
+    fn show_log(&mut self, ui: &mut egui::Ui) {
+        self.show_last_error(ui);
         self.log.iter().for_each(|line| {
             ui.label(line);
         })
     }
+
+    /// The latest error apart from the log, with a button to copy it as a bug report
+    fn show_last_error(&mut self, ui: &mut egui::Ui) {
+        let Some(report) = &self.last_error else {
+            return;
+        };
+        let mut dismiss = false;
+        ui.group(|ui| {
+            ui.horizontal_wrapped(|ui| {
+                ui.colored_label(Color32::DARK_RED, "Last Error");
+                if ui
+                    .button("Copy")
+                    .on_hover_text(
+                        "Copy the error and the port settings, to paste into a bug report",
+                    )
+                    .clicked()
+                {
+                    ui.ctx().copy_text(report.to_string());
+                }
+                dismiss = ui.small_button("✖").on_hover_text("Dismiss").clicked();
+            });
+            ui.colored_label(Color32::DARK_RED, &report.message);
+            if let Some(hint) = report.hint {
+                ui.label(format!("Hint: {}", hint));
+            }
+            egui::CollapsingHeader::new("Settings at the time")
+                .id_source("last error settings")
+                .show(ui, |ui| {
+                    for line in report.settings.iter() {
+                        ui.monospace(line);
+                    }
+                });
+        });
+        if dismiss {
+            self.last_error = None;
+        }
+        ui.separator();
+    }
 }
 
 impl eframe::App for Symphony {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         let span = span!(tracing::Level::INFO, "Update");
         let _guard = span.enter();
-        TopBottomPanel::top("Plotting area")
-            .resizable(true)
-            .min_height(0.4 * ctx.available_rect().height())
-            .max_height(0.95 * ctx.available_rect().height())
-            .default_height(0.75 * ctx.available_rect().height())
-            .show(ctx, |ui| {
+        let anti_aliasing = self.plot_options.anti_aliasing;
+        ctx.tessellation_options_mut(|options| options.feathering = anti_aliasing);
+        let backlog = self.read_packets();
+        self.check_lag();
+        if self.connected {
+            self.health_window.record(self.health_counts());
+        }
+        self.check_bad_frames();
+        self.check_overlong();
+        self.step_macro();
+        self.step_lines();
+        self.step_keep_alive();
+        self.step_reply();
+        self.step_baud_detect();
+        self.step_port_test();
+        self.step_connection();
+        self.step_summary();
+        self.load_dropped_files(ctx);
+        self.handle_shortcuts(ctx);
+        let background = ctx.input(|i| {
+            let viewport = i.viewport();
+            viewport.focused == Some(false) || viewport.minimized == Some(true)
+        });
+        let background_interval = match background {
+            true => self.background_redraw.interval(),
+            false => None,
+        };
+        let refresh_interval = (self.plot_options.refresh_hz > 0)
+            .then(|| Duration::from_secs_f64(1. / self.plot_options.refresh_hz as f64));
+        // Whichever of the two redraws less often wins
+        let throttle = background_interval.max(refresh_interval);
+        if backlog {
+            // Catching up on the queue can't wait, whatever the window's state
+            ctx.request_repaint();
+        } else if self.connected
+            || self.connecting
+            || self.baud_detect.is_some()
+            || self.port_test.is_some()
+        {
+            match throttle {
+                Some(interval) => ctx.request_repaint_after(interval),
+                None => ctx.request_repaint(),
+            }
+        } else if let Some(at) = self.reconnect_at {
+            ctx.request_repaint_after(at.saturating_duration_since(Instant::now()));
+        }
+        let title = self.title();
+        if title != self.window_title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.window_title = title;
+        }
+        if self.compact {
+            CentralPanel::default().show(ctx, |ui| {
                 self.draw_plot(ui);
             });
-        CentralPanel::default().show(ctx, |ui| {
-            self.draw_bottom_panel(ui);
+            self.draw_compact_overlay(ctx);
+        } else {
+            TopBottomPanel::bottom("Status bar").show(ctx, |ui| {
+                self.draw_status_bar(ui);
+            });
+            TopBottomPanel::top("Plotting area")
+                .resizable(true)
+                .min_height(0.4 * ctx.available_rect().height())
+                .max_height(0.95 * ctx.available_rect().height())
+                .default_height(0.75 * ctx.available_rect().height())
+                .show(ctx, |ui| {
+                    self.draw_plot(ui);
+                });
+            CentralPanel::default().show(ctx, |ui| {
+                self.draw_bottom_panel(ui);
+            });
+        }
+        self.show_send_confirmation(ctx);
+        self.intercept_close(ctx);
+        self.show_quit_confirmation(ctx);
+        self.autosave();
+        if self.dirty {
+            ctx.request_repaint_after(AUTOSAVE_DELAY);
+        }
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.save_settings();
+        self.join_serial_thread(EXIT_JOIN_TIMEOUT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_times_are_truncated_to_the_decimals() {
+        let ms = Duration::from_millis;
+        assert_eq!(format_relative(ms(0), 3), "0.000");
+        assert_eq!(format_relative(ms(5), 3), "0.005");
+        assert_eq!(format_relative(ms(999), 3), "0.999");
+        assert_eq!(format_relative(ms(3005), 3), "3.005");
+        assert_eq!(format_relative(ms(999), 1), "0.9");
+        assert_eq!(format_relative(ms(999), 0), "0");
+        assert_eq!(format_relative(ms(3005), 0), "3");
+        assert_eq!(format_relative(ms(5), 6), "0.005000");
+    }
+
+    /// A window with default settings and a channel for each of `channels`
+    fn with_channels(channels: &[&[(f64, f64)]]) -> Symphony {
+        let mut symphony = Symphony::new(None);
+        for points in channels {
+            let mut measurement = Measurement::new_with_look_behind(LOOK_BEHIND);
+            for (x, y) in points.iter() {
+                measurement.add(PlotPoint::new(*x, *y));
+            }
+            symphony.plot_data.push(measurement);
+            symphony
+                .plot_options
+                .channels
+                .push(ChannelOptions::default());
+        }
+        symphony
+    }
+
+    #[test]
+    fn an_invalid_terminator_keeps_connect_off() {
+        let mut symphony = Symphony::new(None);
+        symphony.source_kind = SourceKind::Demo;
+        assert_eq!(symphony.missing_source(), None);
+        symphony.framing = Framing::Custom(String::new());
+        assert_eq!(
+            symphony.missing_source().as_deref(),
+            Some("Enter a terminator first")
+        );
+        symphony.framing = Framing::Custom(String::from("\\q"));
+        assert!(symphony
+            .missing_source()
+            .is_some_and(|missing| missing.starts_with("Invalid terminator")));
+        assert!(!symphony.try_connect());
+        assert!(symphony.serial_handle.is_none());
+        symphony.framing = Framing::Custom(String::from("\\r\\n"));
+        assert_eq!(symphony.missing_source(), None);
+    }
+
+    #[test]
+    fn stacked_channels_get_a_lane_each() {
+        let mut symphony = with_channels(&[]);
+        assert_eq!(symphony.lanes(&[0, 2]), [vec![0, 2]]);
+        symphony.plot_options.layout = PlotLayout::Stacked;
+        assert_eq!(symphony.lanes(&[0, 2]), [vec![0], vec![2]]);
+        // Still one empty lane to show the axes in
+        assert_eq!(symphony.lanes(&[]), [Vec::<usize>::new()]);
+    }
+
+    #[test]
+    fn log_axes_are_set_per_lane() {
+        let mut symphony = with_channels(&[&[], &[]]);
+        symphony.plot_options.channels[1].log_y = true;
+        let log = |symphony: &Symphony| -> Vec<bool> {
+            let lanes = symphony.lanes(&[0, 1]);
+            lanes.iter().map(|lane| symphony.log_y(lane)).collect()
+        };
+        // A shared axis only goes log once every channel on it asks for it
+        assert_eq!(log(&symphony), [false]);
+        symphony.plot_options.layout = PlotLayout::Stacked;
+        assert_eq!(log(&symphony), [false, true]);
+        symphony.plot_options.channels[0].log_y = true;
+        symphony.plot_options.layout = PlotLayout::Overlaid;
+        assert_eq!(log(&symphony), [true]);
+        assert!(!symphony.log_y(&[]));
+    }
+
+    #[test]
+    fn stacked_lanes_autoscale_and_lock_on_their_own() {
+        let mut symphony = with_channels(&[&[(0., 0.), (1., 10.)], &[(0., 100.), (1., 200.)]]);
+        let overlaid = symphony.lanes(&[0, 1]);
+        assert_eq!(symphony.autoscale_bounds(&overlaid), [Some([-10., 210.])]);
+        symphony.plot_options.layout = PlotLayout::Stacked;
+        let stacked = symphony.lanes(&[0, 1]);
+        assert_eq!(
+            symphony.autoscale_bounds(&stacked),
+            [Some([-0.5, 10.5]), Some([95., 205.])]
+        );
+        symphony.plot_options.channels[0].auto_range = false;
+        symphony
+            .range_locks
+            .entry(symphony.source_name().to_owned())
+            .or_default()
+            .insert(1, [0., 1000.]);
+        assert_eq!(
+            symphony.autoscale_bounds(&stacked),
+            [None, Some([-50., 1050.])]
+        );
+    }
+
+    #[test]
+    fn loaded_tuning_is_kept_to_the_ranges_of_its_controls() {
+        let settings = Settings {
+            queue_capacity: 0,
+            idle_timeout_secs: 0,
+            break_ms: 60_000,
+            connect_timeout_secs: 0,
+            parse_queue_capacity: 0,
+            max_idle_sleep_ms: 60_000,
+            garbage_threshold: 2.,
+            line_limit: LineLimit {
+                max_len: 0,
+                ..LineLimit::default()
+            },
+            health_thresholds: HealthThresholds {
+                degraded: 0.5,
+                bad: 0.,
+            },
+            merge_window_ms: 60_000,
+            ..Settings::default()
+        }
+        .clamped();
+        assert_eq!(settings.queue_capacity, 1);
+        assert_eq!(settings.merge_window_ms, 5_000);
+        assert_eq!(settings.health_thresholds.bad, 0.5);
+        assert_eq!(settings.line_limit.max_len, 1024);
+        assert_eq!(settings.garbage_threshold, 1.);
+        assert_eq!(settings.max_idle_sleep_ms, 500);
+        assert_eq!(settings.parse_queue_capacity, 1);
+        assert_eq!(settings.connect_timeout_secs, 1);
+        assert_eq!(settings.break_ms, 5_000);
+        assert_eq!(settings.idle_timeout_secs, 1);
+    }
+
+    #[test]
+    fn tuning_missing_from_older_settings_files_gets_its_defaults() {
+        let settings: Settings =
+            serde_json::from_str(r#"{"queue_capacity": 50, "break_ms": 100}"#).unwrap();
+        assert_eq!(
+            settings,
+            Settings {
+                queue_capacity: 50,
+                break_ms: 100,
+                ..Settings::default()
+            }
+        );
+        let saved = serde_json::to_string(&settings).unwrap();
+        assert_eq!(serde_json::from_str::<Settings>(&saved).unwrap(), settings);
+    }
+
+    /// Run a frame of `ctx` that presses `keys` while a text field shows,
+    /// focused when `typing`, then handles the shortcuts like `update` does
+    fn press(symphony: &mut Symphony, ctx: &egui::Context, keys: &[egui::Key], typing: bool) {
+        let events = keys
+            .iter()
+            .map(|&key| egui::Event::Key {
+                key,
+                physical_key: None,
+                pressed: true,
+                repeat: false,
+                modifiers: egui::Modifiers::NONE,
+            })
+            .collect();
+        let input = egui::RawInput {
+            events,
+            ..Default::default()
+        };
+        let mut text = String::new();
+        let _ = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let field = ui.text_edit_singleline(&mut text);
+                match typing {
+                    true => field.request_focus(),
+                    false => field.surrender_focus(),
+                }
+            });
+            symphony.handle_shortcuts(ctx);
         });
-        self.n_items += 1;
+    }
+
+    #[test]
+    fn shortcuts_stay_off_while_a_text_field_has_focus() {
+        let mut symphony = Symphony::new(None);
+        let ctx = egui::Context::default();
+        let logged = symphony.log.iter().count();
+        // Focus taken in one frame shows in the next, like clicking into a field
+        press(&mut symphony, &ctx, &[], true);
+        press(&mut symphony, &ctx, &[egui::Key::Space, egui::Key::K], true);
+        assert!(symphony.paused_at.is_none());
+        assert!(!symphony.connecting);
+        assert_eq!(symphony.log.iter().count(), logged);
+        // F11 can't be typed, so it still works
+        press(&mut symphony, &ctx, &[egui::Key::F11], true);
+        assert!(symphony.compact);
+
+        press(&mut symphony, &ctx, &[], false);
+        press(&mut symphony, &ctx, &[egui::Key::Space], false);
+        assert!(symphony.paused_at.is_some());
+        press(&mut symphony, &ctx, &[egui::Key::Space], false);
+        assert!(symphony.paused_at.is_none());
     }
 }
@@ -0,0 +1,423 @@
+//! Capturing from a serial port without the window, for scripts.
+//!
+//! `symphony --headless --port /dev/ttyUSB0 --baud 115200` reads the port the
+//! way a connection from the window does and writes every number it parses
+//! to stdout, one `channel,time,value` row each. "Copy Command Line" in the
+//! Port panel builds these arguments from the window's settings, see
+//! [`Args::to_args`].
+
+use std::{
+    io::Write,
+    str::FromStr,
+    sync::{
+        atomic::Ordering,
+        mpsc::{self, Receiver, RecvTimeoutError},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serialib::{ConnectionState, Device, Framing, Packet, ReadOptions, SerialShared, Source};
+use serialport::{DataBits, FlowControl, Parity, StopBits};
+
+use crate::{
+    escapes::decode_escapes,
+    parse::{Parsed, Parser, Reading},
+};
+
+/// First line of the output, naming the columns of every row after it
+pub const HEADER: &str = "channel,time,value";
+
+/// Packets the serial thread may queue while rows are written
+const QUEUE_CAPACITY: usize = 10_000;
+
+/// How often the capture checks whether its time is up while nothing arrives
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A headless capture, as given by the arguments after `--headless`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Args {
+    pub device: Device,
+    /// A text framing, with a custom terminator as typed, escapes and all
+    pub framing: Framing,
+    /// Text between columns as typed, with `\t` style escapes
+    pub delimiter: String,
+    /// Tokens are `name:value` pairs, see [`Parser::labeled`]
+    pub labeled: bool,
+    /// Stop after this long, `None` to go on until the port closes
+    pub duration: Option<Duration>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            device: Device::default(),
+            framing: Framing::default(),
+            delimiter: String::from(" "),
+            labeled: false,
+            duration: None,
+        }
+    }
+}
+
+impl Args {
+    /// Read the arguments that follow `--headless`
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        while let Some(arg) = args.next() {
+            if arg == "--labeled" {
+                parsed.labeled = true;
+                continue;
+            }
+            let value = args
+                .next()
+                .ok_or_else(|| format!("{} needs a value", arg))?;
+            match arg.as_str() {
+                "--port" => parsed.device.path = value,
+                "--baud" => {
+                    parsed.device.baud_rate = value
+                        .parse()
+                        .map_err(|_| format!("'{}' isn't a baud rate", value))?
+                }
+                "--frame" => {
+                    let (data_bits, parity, stop_bits) = parse_frame(&value)?;
+                    parsed.device.data_bits = data_bits;
+                    parsed.device.parity = parity;
+                    parsed.device.stop_bits = stop_bits;
+                }
+                "--flow" => {
+                    parsed.device.flow_control = FlowControl::from_str(&value).map_err(|()| {
+                        format!("'{}' isn't none, software or hardware flow control", value)
+                    })?
+                }
+                "--framing" => parsed.framing = parse_framing(&value)?,
+                "--delimiter" => parsed.delimiter = value,
+                "--duration" => {
+                    let secs: f64 = value
+                        .parse()
+                        .ok()
+                        .filter(|secs: &f64| secs.is_finite() && *secs > 0.)
+                        .ok_or_else(|| format!("'{}' isn't a number of seconds", value))?;
+                    parsed.duration = Some(Duration::from_secs_f64(secs));
+                }
+                _ => return Err(format!("Unknown argument '{}'", arg)),
+            }
+        }
+        if parsed.device.path.is_empty() {
+            return Err(String::from("--headless needs a --port to read"));
+        }
+        parsed.device.validate()?;
+        decode_escapes(&parsed.delimiter)?;
+        Ok(parsed)
+    }
+
+    /// The arguments [`Args::parse`] reads back as `self`, starting with
+    /// `--headless`. Binary framings can't be given on the command line.
+    pub fn to_args(&self) -> Result<Vec<String>, String> {
+        let mut args: Vec<String> = [
+            "--headless",
+            "--port",
+            &self.device.path,
+            "--baud",
+            &self.device.baud_rate.to_string(),
+            "--frame",
+            &self.device.frame(),
+            "--flow",
+            &self.device.flow_control.to_string().to_lowercase(),
+            "--framing",
+            &framing_arg(&self.framing)?,
+            "--delimiter",
+            &self.delimiter,
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+        if self.labeled {
+            args.push(String::from("--labeled"));
+        }
+        if let Some(duration) = self.duration {
+            args.push(String::from("--duration"));
+            args.push(duration.as_secs_f64().to_string());
+        }
+        Ok(args)
+    }
+}
+
+/// Data bits, parity and stop bits of a frame like `8N1`
+fn parse_frame(frame: &str) -> Result<(DataBits, Parity, StopBits), String> {
+    let invalid = || format!("'{}' isn't a frame like 8N1", frame);
+    let chars: Vec<char> = frame.chars().collect();
+    let [data_bits, parity, stop_bits] = chars.as_slice() else {
+        return Err(invalid());
+    };
+    let digit = |c: &char| c.to_digit(10).map(|d| d as u8).ok_or_else(invalid);
+    let data_bits = DataBits::try_from(digit(data_bits)?).map_err(|_| invalid())?;
+    let parity = match parity.to_ascii_uppercase() {
+        'N' => Parity::None,
+        'O' => Parity::Odd,
+        'E' => Parity::Even,
+        _ => return Err(invalid()),
+    };
+    let stop_bits = StopBits::try_from(digit(stop_bits)?).map_err(|_| invalid())?;
+    Ok((data_bits, parity, stop_bits))
+}
+
+/// `framing` the way `--framing` takes it
+fn framing_arg(framing: &Framing) -> Result<String, String> {
+    match framing {
+        Framing::Auto => Ok(String::from("auto")),
+        Framing::Crlf => Ok(String::from("crlf")),
+        Framing::Lf => Ok(String::from("lf")),
+        Framing::Nul => Ok(String::from("nul")),
+        Framing::Custom(terminator) => Ok(format!("custom:{}", terminator)),
+        Framing::Fixed { .. } | Framing::ModbusRtu => Err(format!(
+            "{} framing is binary, headless capture only reads text",
+            framing
+        )),
+    }
+}
+
+fn parse_framing(arg: &str) -> Result<Framing, String> {
+    match arg {
+        "auto" => Ok(Framing::Auto),
+        "crlf" => Ok(Framing::Crlf),
+        "lf" => Ok(Framing::Lf),
+        "nul" => Ok(Framing::Nul),
+        _ => match arg.strip_prefix("custom:") {
+            Some(terminator) => {
+                decode_escapes(terminator)?;
+                Ok(Framing::Custom(String::from(terminator)))
+            }
+            None => Err(format!(
+                "'{}' isn't auto, crlf, lf, nul or custom:<terminator>",
+                arg
+            )),
+        },
+    }
+}
+
+/// Capture as `args` say, writing rows to `out` until the duration is up or
+/// the port closes
+pub fn run(args: &Args, out: &mut impl Write) -> Result<(), String> {
+    if let Some(warning) = args.device.warning() {
+        tracing::warn!("{}", warning);
+    }
+    let framing = match &args.framing {
+        Framing::Custom(terminator) => Framing::Custom(decode_escapes(terminator)?),
+        framing => framing.clone(),
+    };
+    let options = ReadOptions {
+        framing: framing.clone(),
+        ..ReadOptions::default()
+    };
+    let parser = Parser {
+        framing,
+        delimiter: Some(decode_escapes(&args.delimiter)?),
+        labeled: args.labeled,
+        ..Default::default()
+    };
+    let (raw_data_tx, raw_data_rx) = mpsc::sync_channel(QUEUE_CAPACITY);
+    let (_command_tx, command_rx) = mpsc::channel();
+    let shared = SerialShared::default();
+    let thread_shared = shared.clone();
+    let source = Source::Serial(args.device.clone());
+    let t_zero = Instant::now();
+    let handle = thread::spawn(move || {
+        serialib::serial_thread(
+            raw_data_tx,
+            command_rx,
+            source,
+            options,
+            t_zero,
+            thread_shared,
+        )
+    });
+    let written = write_rows(&raw_data_rx, &parser, args.duration, out);
+    shared.stop.store(true, Ordering::Relaxed);
+    drop(raw_data_rx);
+    let _ = handle.join();
+    // Failing to open ends the thread right away, which looks like the port closing
+    if let Ok(state) = shared.state.read() {
+        if let ConnectionState::Error(e) = &*state {
+            return Err(e.to_string());
+        }
+    }
+    written
+}
+
+/// Write [`HEADER`] and a row for every number in the packets from
+/// `raw_data_rx`, until `duration` is up or the sender hangs up
+fn write_rows(
+    raw_data_rx: &Receiver<Packet>,
+    parser: &Parser,
+    duration: Option<Duration>,
+    out: &mut impl Write,
+) -> Result<(), String> {
+    let started = Instant::now();
+    writeln!(out, "{}", HEADER).map_err(|e| e.to_string())?;
+    while duration.is_none_or(|duration| started.elapsed() < duration) {
+        let packet = match raw_data_rx.recv_timeout(POLL_INTERVAL) {
+            Ok(packet) => packet,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        let time = packet.relative_time as f64 / 1000.;
+        for (channel, reading) in columns(parser.parse(&packet)) {
+            if let Reading::Number { value, .. } = reading {
+                writeln!(out, "{},{},{}", csv_field(&channel), time, value)
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    out.flush().map_err(|e| e.to_string())
+}
+
+/// Channel and reading of every column in `parsed`, channels by position
+/// unless the tokens name them
+fn columns(parsed: Parsed) -> Vec<(String, Reading)> {
+    match parsed {
+        Parsed::Line(readings) | Parsed::Fields(readings) => readings
+            .into_iter()
+            .enumerate()
+            .map(|(idx, reading)| (idx.to_string(), reading))
+            .collect(),
+        Parsed::Labeled(readings) => readings,
+        Parsed::BadChecksum(_) | Parsed::Exception(_) | Parsed::Ignored => Vec::new(),
+    }
+}
+
+/// `field` quoted if a delimiter or quote in it would break the row
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => String::from(field),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn copied_arguments_parse_back_the_same() {
+        let cases = [
+            Args {
+                device: Device {
+                    path: String::from("/dev/ttyUSB0"),
+                    ..Device::default()
+                },
+                ..Args::default()
+            },
+            Args {
+                device: Device {
+                    path: String::from("COM3"),
+                    baud_rate: 115_200,
+                    data_bits: DataBits::Seven,
+                    parity: Parity::Even,
+                    stop_bits: StopBits::Two,
+                    flow_control: FlowControl::Hardware,
+                    ..Device::default()
+                },
+                framing: Framing::Custom(String::from("\\r\\n\\0")),
+                delimiter: String::from("\\t"),
+                labeled: true,
+                duration: Some(Duration::from_millis(2_500)),
+            },
+        ];
+        for capture in cases {
+            let copied = capture.to_args().unwrap();
+            assert_eq!(copied[0], "--headless");
+            let parsed = Args::parse(copied.into_iter().skip(1)).unwrap();
+            assert_eq!(parsed, capture);
+        }
+    }
+
+    #[test]
+    fn frames_and_framings_parse() {
+        let capture = args(&["--port", "p", "--frame", "8o1", "--framing", "lf"]).unwrap();
+        assert_eq!(capture.device.data_bits, DataBits::Eight);
+        assert_eq!(capture.device.parity, Parity::Odd);
+        assert_eq!(capture.device.stop_bits, StopBits::One);
+        assert_eq!(capture.framing, Framing::Lf);
+        let custom = args(&["--port", "p", "--framing", "custom:;"]).unwrap();
+        assert_eq!(custom.framing, Framing::Custom(String::from(";")));
+    }
+
+    #[test]
+    fn invalid_arguments_are_refused() {
+        let cases: [(&[&str], &str); 9] = [
+            (&[], "needs a --port"),
+            (&["--port"], "--port needs a value"),
+            (&["--port", "p", "--baud", "fast"], "isn't a baud rate"),
+            (&["--port", "p", "--baud", "0"], "greater than zero"),
+            (&["--port", "p", "--frame", "9N1"], "isn't a frame"),
+            (&["--port", "p", "--frame", "8X1"], "isn't a frame"),
+            (&["--port", "p", "--framing", "cr"], "isn't auto, crlf"),
+            (
+                &["--port", "p", "--duration", "-1"],
+                "isn't a number of seconds",
+            ),
+            (&["--port", "p", "--verbose", "1"], "Unknown argument"),
+        ];
+        for (input, reason) in cases {
+            match args(input) {
+                Ok(capture) => panic!("{input:?} parsed as {capture:?}"),
+                Err(e) => assert!(e.contains(reason), "{input:?} gave {e:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn long_frames_parse_with_a_warning() {
+        for frame in ["8E2", "8O2"] {
+            let capture = args(&["--port", "p", "--frame", frame]).unwrap();
+            assert!(capture.device.warning().is_some(), "{frame}");
+        }
+        let capture = args(&["--port", "p", "--frame", "8N2"]).unwrap();
+        assert_eq!(capture.device.warning(), None);
+    }
+
+    #[test]
+    fn binary_framings_have_no_command_line() {
+        let capture = Args {
+            framing: Framing::ModbusRtu,
+            ..Args::default()
+        };
+        assert!(capture.to_args().unwrap_err().contains("binary"));
+    }
+
+    #[test]
+    fn rows_hold_every_number_of_a_packet() {
+        let (tx, rx) = mpsc::sync_channel(4);
+        let packet = |ms: u128, payload: &str| Packet {
+            absolute_time: ms,
+            relative_time: ms,
+            payload: String::from(payload),
+            raw: payload.as_bytes().to_vec(),
+        };
+        tx.send(packet(1_500, "1 x 2.5")).unwrap();
+        tx.send(packet(2_000, "3")).unwrap();
+        drop(tx);
+        let parser = Parser {
+            delimiter: Some(String::from(" ")),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        write_rows(&rx, &parser, None, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "channel,time,value\n0,1.5,1\n2,1.5,2.5\n0,2,3\n"
+        );
+    }
+
+    #[test]
+    fn labeled_channels_are_quoted_when_they_need_it() {
+        assert_eq!(csv_field("temp"), "temp");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
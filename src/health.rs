@@ -0,0 +1,323 @@
+//! How trustworthy a connection looks, rated from its recent diagnostics.
+//!
+//! The counters of parse errors, bad checksums and dropped packets are
+//! sampled over the last [`HEALTH_WINDOW`], and the share of unprintable
+//! bytes hints at a wrong baud rate, see [`BaudMismatch`].
+
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Bytes the unprintable share is averaged over, about the last this many
+pub const GARBAGE_WINDOW: usize = 1024;
+
+/// How long the unprintable share must stay over the threshold before warning
+pub const GARBAGE_AFTER: Duration = Duration::from_secs(2);
+
+/// How far back the counters the connection health is judged on go
+pub const HEALTH_WINDOW: Duration = Duration::from_secs(10);
+
+/// How often the counters are sampled for the health window
+pub const HEALTH_SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Share of unprintable bytes in what arrived lately, a wrong baud rate turns
+/// text into mostly garbage
+#[derive(Debug, Default)]
+pub struct BaudMismatch {
+    /// Moving average over about the last [`GARBAGE_WINDOW`] bytes
+    pub ratio: f64,
+    /// Bytes seen since connecting, capped at [`GARBAGE_WINDOW`]
+    pub seen: usize,
+    /// When `ratio` went over the threshold, `None` while it's under
+    high_since: Option<Instant>,
+    /// The warning was dismissed, until the next connect
+    pub dismissed: bool,
+}
+
+impl BaudMismatch {
+    /// Fold `bytes` into the average and note when it crosses `threshold`
+    pub fn record(&mut self, bytes: &[u8], threshold: f64) {
+        if bytes.is_empty() {
+            return;
+        }
+        let unprintable = bytes
+            .iter()
+            .filter(|b| !(b.is_ascii_graphic() || matches!(b, b' ' | b'\r' | b'\n' | b'\t')))
+            .count();
+        let share = unprintable as f64 / bytes.len() as f64;
+        let weight = (bytes.len() as f64 / GARBAGE_WINDOW as f64).min(1.);
+        self.ratio = match self.seen {
+            0 => share,
+            _ => self.ratio * (1. - weight) + share * weight,
+        };
+        self.seen = (self.seen + bytes.len()).min(GARBAGE_WINDOW);
+        match self.ratio > threshold {
+            true => {
+                self.high_since.get_or_insert_with(Instant::now);
+            }
+            false => self.high_since = None,
+        }
+    }
+
+    /// Whether the share has been over the threshold long enough to warn about
+    pub fn is_likely(&self) -> bool {
+        !self.dismissed
+            && self.seen >= GARBAGE_WINDOW
+            && self
+                .high_since
+                .is_some_and(|since| since.elapsed() >= GARBAGE_AFTER)
+    }
+
+    /// How the unprintable share rates against `threshold`, `None` before
+    /// any bytes arrived
+    pub fn rate(&self, threshold: f64) -> Option<(Health, String)> {
+        if self.seen == 0 {
+            return None;
+        }
+        let rating = if self.ratio > threshold {
+            Health::Bad
+        } else if self.ratio > threshold / 2. {
+            Health::Degraded
+        } else {
+            Health::Good
+        };
+        Some((
+            rating,
+            format!("Unprintable: {:.0} % of bytes", self.ratio * 100.),
+        ))
+    }
+}
+
+/// How trustworthy the capture looks, worst last
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Health {
+    Good,
+    Degraded,
+    Bad,
+}
+
+impl Display for Health {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Health::Good => write!(f, "Good"),
+            Health::Degraded => write!(f, "Degraded"),
+            Health::Bad => write!(f, "Bad"),
+        }
+    }
+}
+
+impl Health {
+    /// The worst of the ratings of `parts`, good when there are none
+    pub fn overall(parts: &[(Health, String)]) -> Health {
+        parts
+            .iter()
+            .map(|(rating, _)| *rating)
+            .max()
+            .unwrap_or(Health::Good)
+    }
+
+    /// Bad once nothing arrived for `timeout`
+    pub fn idle(idle: Duration, timeout: Duration) -> (Health, String) {
+        let rating = match idle >= timeout {
+            true => Health::Bad,
+            false => Health::Good,
+        };
+        (rating, format!("Idle: {} s", idle.as_secs()))
+    }
+}
+
+/// Shares of troubled packets from which the connection counts as degraded or bad
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthThresholds {
+    pub degraded: f64,
+    pub bad: f64,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            degraded: 0.01,
+            bad: 0.05,
+        }
+    }
+}
+
+impl HealthThresholds {
+    pub fn rate(&self, share: f64) -> Health {
+        if share >= self.bad {
+            Health::Bad
+        } else if share >= self.degraded {
+            Health::Degraded
+        } else {
+            Health::Good
+        }
+    }
+}
+
+/// Counters the health is worked out from, as of one moment
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HealthCounts {
+    pub packets: usize,
+    pub parse_errors: usize,
+    pub bad_checksums: usize,
+    pub dropped: usize,
+}
+
+impl HealthCounts {
+    /// How the share of every kind of trouble in these counts rates
+    pub fn rate(&self, thresholds: &HealthThresholds) -> Vec<(Health, String)> {
+        let share = |count: usize, total: usize| match total {
+            0 => 0.,
+            total => count as f64 / total as f64,
+        };
+        let parse_errors = share(self.parse_errors, self.packets);
+        let bad_checksums = share(self.bad_checksums, self.packets);
+        let dropped = share(self.dropped, self.packets + self.dropped);
+        vec![
+            (
+                thresholds.rate(parse_errors),
+                format!("Parse errors: {:.1} % of packets", parse_errors * 100.),
+            ),
+            (
+                thresholds.rate(bad_checksums),
+                format!("Bad checksums: {:.1} % of packets", bad_checksums * 100.),
+            ),
+            (
+                thresholds.rate(dropped),
+                format!("Dropped: {:.1} % of packets", dropped * 100.),
+            ),
+        ]
+    }
+}
+
+/// Samples of the counters over the last [`HEALTH_WINDOW`]
+#[derive(Debug, Default)]
+pub struct HealthWindow {
+    samples: VecDeque<(Instant, HealthCounts)>,
+}
+
+impl HealthWindow {
+    pub fn record(&mut self, counts: HealthCounts) {
+        self.record_at(Instant::now(), counts);
+    }
+
+    fn record_at(&mut self, now: Instant, counts: HealthCounts) {
+        if self
+            .samples
+            .back()
+            .is_some_and(|(at, _)| now.duration_since(*at) < HEALTH_SAMPLE_INTERVAL)
+        {
+            return;
+        }
+        self.samples.push_back((now, counts));
+        // Keep the newest sample from before the window, so the window is spanned in full
+        while self
+            .samples
+            .get(1)
+            .is_some_and(|(at, _)| now.duration_since(*at) >= HEALTH_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// How much every counter went up across the window, up to `counts`
+    pub fn delta(&self, counts: HealthCounts) -> HealthCounts {
+        let oldest = self
+            .samples
+            .front()
+            .map(|(_, counts)| *counts)
+            .unwrap_or_default();
+        HealthCounts {
+            packets: counts.packets.saturating_sub(oldest.packets),
+            parse_errors: counts.parse_errors.saturating_sub(oldest.parse_errors),
+            bad_checksums: counts.bad_checksums.saturating_sub(oldest.bad_checksums),
+            dropped: counts.dropped.saturating_sub(oldest.dropped),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(packets: usize, parse_errors: usize) -> HealthCounts {
+        HealthCounts {
+            packets,
+            parse_errors,
+            ..HealthCounts::default()
+        }
+    }
+
+    #[test]
+    fn shares_rate_from_their_thresholds_on() {
+        let thresholds = HealthThresholds::default();
+        assert_eq!(thresholds.rate(0.), Health::Good);
+        assert_eq!(thresholds.rate(0.01), Health::Degraded);
+        assert_eq!(thresholds.rate(0.05), Health::Bad);
+
+        let delta = HealthCounts {
+            packets: 100,
+            parse_errors: 2,
+            bad_checksums: 0,
+            dropped: 100,
+        };
+        let ratings: Vec<Health> = delta
+            .rate(&thresholds)
+            .into_iter()
+            .map(|(r, _)| r)
+            .collect();
+        assert_eq!(ratings, [Health::Degraded, Health::Good, Health::Bad]);
+        // No packets at all is no trouble
+        let quiet = HealthCounts::default().rate(&thresholds);
+        assert_eq!(Health::overall(&quiet), Health::Good);
+    }
+
+    #[test]
+    fn the_window_counts_from_its_oldest_sample() {
+        let start = Instant::now();
+        let mut window = HealthWindow::default();
+        window.record_at(start, counts(10, 1));
+        // Too soon after the last sample to keep
+        window.record_at(start + HEALTH_SAMPLE_INTERVAL / 2, counts(20, 2));
+        assert_eq!(window.delta(counts(30, 3)), counts(20, 2));
+
+        window.record_at(start + HEALTH_WINDOW, counts(40, 4));
+        window.record_at(start + HEALTH_WINDOW * 2, counts(50, 5));
+        assert_eq!(window.delta(counts(60, 5)), counts(20, 1));
+    }
+
+    #[test]
+    fn garbage_warns_once_it_stays_over_the_threshold() {
+        let mut mismatch = BaudMismatch::default();
+        assert_eq!(mismatch.rate(0.3), None);
+        mismatch.record(b"hello\r\n", 0.3);
+        assert_eq!(mismatch.rate(0.3).map(|(r, _)| r), Some(Health::Good));
+
+        mismatch.record(&[0xFF; GARBAGE_WINDOW], 0.3);
+        assert_eq!(mismatch.rate(0.3).map(|(r, _)| r), Some(Health::Bad));
+        // Not for long enough yet
+        assert!(!mismatch.is_likely());
+        mismatch.high_since = Some(Instant::now() - GARBAGE_AFTER);
+        assert!(mismatch.is_likely());
+        mismatch.dismissed = true;
+        assert!(!mismatch.is_likely());
+
+        mismatch.record(&[b'a'; GARBAGE_WINDOW], 0.3);
+        assert_eq!(mismatch.high_since, None);
+    }
+
+    #[test]
+    fn going_idle_is_bad() {
+        let timeout = Duration::from_secs(5);
+        assert_eq!(
+            Health::idle(Duration::from_secs(4), timeout).0,
+            Health::Good
+        );
+        assert_eq!(Health::idle(timeout, timeout).0, Health::Bad);
+    }
+}
@@ -0,0 +1,112 @@
+//! A payload sent whenever no command went out for a while, for devices that
+//! drop idle connections or fall asleep.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{commands::parse_hex_command, escapes::decode_escapes};
+
+/// Bytes sent for the whole session whenever no command went out for a
+/// while, see [`KeepAlive::due`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeepAlive {
+    pub enabled: bool,
+    /// Text with `\n` style escapes, or hex bytes when `hex` is set
+    pub payload: String,
+    pub hex: bool,
+    /// Quiet time before the payload is sent, and again between sends
+    pub interval_secs: u64,
+}
+
+impl Default for KeepAlive {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            payload: String::from("\\n"),
+            hex: false,
+            interval_secs: 30,
+        }
+    }
+}
+
+impl KeepAlive {
+    /// The payload as sent, empty when there is nothing to send yet
+    pub fn bytes(&self) -> Result<Vec<u8>, String> {
+        let blank = match self.hex {
+            true => self.payload.trim().is_empty(),
+            false => self.payload.is_empty(),
+        };
+        if blank {
+            return Ok(Vec::new());
+        }
+        match self.hex {
+            true => parse_hex_command(&self.payload),
+            false => decode_escapes(&self.payload).map(String::into_bytes),
+        }
+    }
+
+    /// The bytes to send after `quiet` without sending anything, `None` while
+    /// it's off, not yet time, or the payload has nothing valid to send
+    pub fn due(&self, quiet: Duration) -> Option<Vec<u8>> {
+        let interval = Duration::from_secs(self.interval_secs.max(1));
+        if !self.enabled || quiet < interval {
+            return None;
+        }
+        self.bytes().ok().filter(|bytes| !bytes.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_empty_keep_alive_has_nothing_to_send() {
+        for hex in [false, true] {
+            for payload in ["", "  "] {
+                let keep_alive = KeepAlive {
+                    payload: String::from(payload),
+                    hex,
+                    ..KeepAlive::default()
+                };
+                let blank = hex || payload.is_empty();
+                assert_eq!(keep_alive.bytes().is_ok_and(|b| b.is_empty()), blank);
+            }
+        }
+        let bad = KeepAlive {
+            payload: String::from("zz"),
+            hex: true,
+            ..KeepAlive::default()
+        };
+        assert!(bad.bytes().is_err());
+    }
+
+    #[test]
+    fn the_payload_is_due_after_the_interval() {
+        let mut keep_alive = KeepAlive {
+            enabled: true,
+            payload: String::from("AT\\r"),
+            interval_secs: 5,
+            ..KeepAlive::default()
+        };
+        assert_eq!(keep_alive.due(Duration::from_secs(4)), None);
+        assert_eq!(
+            keep_alive.due(Duration::from_secs(5)).as_deref(),
+            Some(&b"AT\r"[..])
+        );
+        // Nothing valid to send is never due
+        keep_alive.hex = true;
+        assert_eq!(keep_alive.due(Duration::from_secs(5)), None);
+        keep_alive.payload.clear();
+        assert_eq!(keep_alive.due(Duration::from_secs(5)), None);
+        keep_alive.enabled = false;
+        keep_alive.payload = String::from("00");
+        assert_eq!(keep_alive.due(Duration::from_secs(60)), None);
+        // An interval of zero still waits a second between sends
+        keep_alive.enabled = true;
+        keep_alive.interval_secs = 0;
+        assert_eq!(keep_alive.due(Duration::ZERO), None);
+    }
+}
@@ -1 +1,21 @@
+pub mod binary;
+pub mod capture;
+pub mod checksum;
+pub mod commands;
+pub mod discovery;
+pub mod escapes;
 pub mod gui;
+pub mod headless;
+pub mod health;
+pub mod keep_alive;
+pub mod log;
+pub mod macros;
+pub mod measurement;
+pub mod nmea;
+pub mod parse;
+pub mod raw;
+pub mod rules;
+pub mod settings;
+pub mod svg;
+pub mod timefmt;
+pub mod transform;
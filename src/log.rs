@@ -0,0 +1,54 @@
+//! Lines of the Log panel.
+//!
+//! Everything worth telling the user goes here as well as to `tracing`, in
+//! the same words, so the panel reads like the terminal output.
+
+use std::time::{Duration, Instant};
+
+/// How long after a log line the same line is folded into it instead of added
+const LOG_FOLD_WINDOW: Duration = Duration::from_secs(10);
+
+/// Lines of the Log panel, where a line repeated within [`LOG_FOLD_WINDOW`]
+/// is shown once with a count, like during an error storm
+#[derive(Debug, Default)]
+pub struct Log {
+    entries: Vec<LogEntry>,
+}
+
+#[derive(Debug)]
+struct LogEntry {
+    text: String,
+    count: usize,
+    /// When the line last came up
+    last: Instant,
+}
+
+impl Log {
+    pub fn push(&mut self, text: String) {
+        let recent = self
+            .entries
+            .iter_mut()
+            .rev()
+            .take_while(|entry| entry.last.elapsed() < LOG_FOLD_WINDOW)
+            .find(|entry| entry.text == text);
+        match recent {
+            Some(entry) => {
+                entry.count += 1;
+                entry.last = Instant::now();
+            }
+            None => self.entries.push(LogEntry {
+                text,
+                count: 1,
+                last: Instant::now(),
+            }),
+        }
+    }
+
+    /// Every line as shown, with its count when it came up more than once
+    pub fn iter(&self) -> impl Iterator<Item = String> + '_ {
+        self.entries.iter().map(|entry| match entry.count {
+            1 => entry.text.clone(),
+            count => format!("{} (×{})", entry.text, count),
+        })
+    }
+}
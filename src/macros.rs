@@ -0,0 +1,69 @@
+//! Macros, named sequences of commands sent one after another.
+//!
+//! A [`MacroRun`] only keeps track of where a macro is, the GUI sends the
+//! commands it hands out and stops it when one can't be sent.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::Command;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MacroStep {
+    pub command: Command,
+    /// How long to wait after sending before the next step
+    pub delay_ms: u64,
+}
+
+/// A named sequence of commands sent one after another
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Macro {
+    pub name: String,
+    pub steps: Vec<MacroStep>,
+}
+
+/// Progress through the macro that is currently being sent
+#[derive(Debug)]
+pub struct MacroRun {
+    pub macro_idx: usize,
+    pub next_step: usize,
+    next_at: Instant,
+}
+
+/// What a running macro wants done next
+#[derive(Debug, Clone, PartialEq)]
+pub enum MacroAction {
+    /// The delay after the last step isn't over yet
+    Wait,
+    Send(Command),
+    /// Every step was sent
+    Finished,
+}
+
+impl MacroRun {
+    pub fn new(macro_idx: usize) -> Self {
+        Self {
+            macro_idx,
+            next_step: 0,
+            next_at: Instant::now(),
+        }
+    }
+
+    /// The command of the next step of `macros[macro_idx]` once the delay
+    /// after the previous one has passed, moving on past it
+    pub fn next(&mut self, macros: &[Macro]) -> MacroAction {
+        if Instant::now() < self.next_at {
+            return MacroAction::Wait;
+        }
+        let step = macros
+            .get(self.macro_idx)
+            .and_then(|m| m.steps.get(self.next_step));
+        let Some(step) = step else {
+            return MacroAction::Finished;
+        };
+        self.next_step += 1;
+        self.next_at = Instant::now() + Duration::from_millis(step.delay_ms);
+        MacroAction::Send(step.command.clone())
+    }
+}
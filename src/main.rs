@@ -1,15 +1,40 @@
+use std::{io, path::PathBuf};
+
 use eframe::{run_native, NativeOptions};
-use symphony::gui::Symphony;
+use symphony::{gui::Symphony, headless, settings};
 
 fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt::init();
+    // Headless captures write their rows to stdout, so the log goes elsewhere
+    tracing_subscriber::fmt().with_writer(io::stderr).init();
     tracing::info!("Starting App");
 
+    let mut args = std::env::args().skip(1);
+    let mut settings_path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--settings" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--settings needs a path"))?;
+                settings_path = Some(PathBuf::from(path));
+            }
+            "--headless" => {
+                let capture = headless::Args::parse(args).map_err(anyhow::Error::msg)?;
+                tracing::info!("Capturing from {} without a window", capture.device.path);
+                return headless::run(&capture, &mut io::stdout().lock())
+                    .map_err(anyhow::Error::msg);
+            }
+            _ => anyhow::bail!("Unknown argument '{}'", arg),
+        }
+    }
+    let settings_path = settings::resolve_path(settings_path);
+    tracing::info!("Using settings at {:?}", settings_path);
+
     let options = NativeOptions::default();
     run_native(
-        "MyApp",
+        "Symphony",
         options,
-        Box::new(|_cc| Ok(Box::<Symphony>::new(Symphony::new()))),
+        Box::new(|_cc| Ok(Box::<Symphony>::new(Symphony::new(settings_path)))),
     )
     .expect("Starting app from here");
     Ok(())
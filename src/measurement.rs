@@ -0,0 +1,586 @@
+//! Buffered values of a channel, and how they are bounded and drawn.
+//!
+//! Every channel keeps its own [`Measurement`]. Values only ever arrive in
+//! time order from a device, so the buffer is a queue trimmed from the front,
+//! and a value older than the newest one is handled by its [`Regression`].
+
+use std::{collections::VecDeque, fmt::Display, ops::RangeInclusive};
+
+use egui_plot::{PlotPoint, PlotPoints};
+use serde::{Deserialize, Serialize};
+
+/// What [`Measurement::add`] does with a sample older than the newest one
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Regression {
+    #[default]
+    /// Assume the device restarted and start over
+    Clear,
+    /// Insert the sample where it belongs in time
+    Sort,
+    /// Drop the sample
+    Ignore,
+}
+
+impl Display for Regression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Regression::Clear => write!(f, "Clear"),
+            Regression::Sort => write!(f, "Sort"),
+            Regression::Ignore => write!(f, "Ignore"),
+        }
+    }
+}
+
+/// Seconds of history each channel keeps behind its newest value
+pub const LOOK_BEHIND: usize = 5;
+
+/// Fewest samples a buffer or plot width can hold and still draw a readable line
+pub const MIN_SAMPLES: usize = 10;
+
+/// Most points of one channel handed to the plot, longer buffers are decimated
+const MAX_DRAWN_POINTS: usize = 20_000;
+
+/// Shortest time between two values a derivative is taken over, in seconds
+const MIN_DERIVATIVE_DT: f64 = 1e-6;
+
+/// Buffered values of one channel.
+///
+/// The buffer is bounded twice: by `look_behind`, the seconds of history kept
+/// behind the newest value, and by `max_samples`. Whichever limit drops more
+/// values is the one that applies. An `unlimited` buffer ignores both.
+#[derive(Debug, Default)]
+pub struct Measurement {
+    pub values: VecDeque<PlotPoint>,
+    pub look_behind: usize,
+    /// Most values kept, 0 for no limit besides `look_behind`
+    pub max_samples: usize,
+    /// Keep every value, bounded only by memory
+    pub unlimited: bool,
+    pub regression: Regression,
+    /// Whether every token parsed for this channel so far was an integer
+    pub integer: bool,
+    /// Snapshot of `values` kept as a static line to compare against
+    pub reference: Vec<PlotPoint>,
+}
+
+impl Measurement {
+    pub fn new_with_look_behind(look_behind: usize) -> Self {
+        Self {
+            values: VecDeque::new(),
+            look_behind,
+            max_samples: 0,
+            unlimited: false,
+            regression: Regression::default(),
+            integer: true,
+            reference: Vec::new(),
+        }
+    }
+
+    /// Move every value `by` seconds earlier, for when the time origin moves forward
+    pub fn shift(&mut self, by: f64) {
+        self.values.iter_mut().for_each(|p| p.x -= by);
+        self.reference.iter_mut().for_each(|p| p.x -= by);
+    }
+
+    pub fn capture_reference(&mut self) {
+        self.reference = Vec::from_iter(self.values.iter().copied());
+    }
+
+    pub fn reference_values(&self) -> PlotPoints {
+        PlotPoints::Owned(
+            self.reference
+                .iter()
+                .copied()
+                .filter(|p| !p.y.is_nan())
+                .collect(),
+        )
+    }
+
+    pub fn add(&mut self, measurement: PlotPoint) {
+        if let Some(last) = self.values.back() {
+            if measurement.x < last.x {
+                match self.regression {
+                    Regression::Clear => self.values.clear(),
+                    Regression::Sort => {
+                        let idx = self.values.partition_point(|p| p.x <= measurement.x);
+                        self.values.insert(idx, measurement);
+                        self.trim();
+                        return;
+                    }
+                    Regression::Ignore => return,
+                }
+            }
+        }
+
+        self.values.push_back(measurement);
+        self.trim();
+    }
+
+    /// Drop the oldest values until both `max_samples` and `look_behind` hold
+    pub fn trim(&mut self) {
+        if self.unlimited {
+            return;
+        }
+        if self.max_samples > 0 && self.values.len() > self.max_samples {
+            let excess = self.values.len() - self.max_samples;
+            self.values.drain(..excess);
+        }
+        let Some(newest) = self.values.back() else {
+            return;
+        };
+        let limit = newest.x - (self.look_behind as f64);
+        while let Some(front) = self.values.front() {
+            if front.x >= limit {
+                break;
+            }
+            self.values.pop_front();
+        }
+    }
+
+    /// Value at `x`, interpolated between the samples either side of it.
+    /// `None` outside the buffer or next to a gap
+    pub fn value_at(&self, x: f64) -> Option<f64> {
+        let idx = self.values.partition_point(|p| p.x < x);
+        let after = self.values.get(idx)?;
+        let y = match after.x == x {
+            true => after.y,
+            false => {
+                let before = self.values.get(idx.checked_sub(1)?)?;
+                let t = (x - before.x) / (after.x - before.x);
+                before.y + (after.y - before.y) * t
+            }
+        };
+        (!y.is_nan()).then_some(y)
+    }
+
+    /// Most recent value that isn't a gap
+    pub fn latest(&self) -> Option<PlotPoint> {
+        self.values.iter().rev().find(|p| !p.y.is_nan()).copied()
+    }
+
+    /// Summary of every buffered value, `None` while no value is buffered
+    pub fn stats(&self) -> Option<Stats> {
+        self.stats_within(&(f64::NEG_INFINITY..=f64::INFINITY))
+    }
+
+    /// Summary of the buffered values with an x inside `range`
+    pub fn stats_within(&self, range: &RangeInclusive<f64>) -> Option<Stats> {
+        let values = || {
+            self.values
+                .iter()
+                .filter(|p| range.contains(&p.x))
+                .map(|p| p.y)
+                .filter(|y| !y.is_nan())
+        };
+        let count = values().count();
+        if count == 0 {
+            return None;
+        }
+        let (mut min, mut max, mut sum) = (f64::INFINITY, f64::NEG_INFINITY, 0.);
+        for y in values() {
+            min = min.min(y);
+            max = max.max(y);
+            sum += y;
+        }
+        let mean = sum / count as f64;
+        let variance = values().map(|y| (y - mean).powi(2)).sum::<f64>() / count as f64;
+        Some(Stats {
+            count,
+            min,
+            max,
+            mean,
+            std_dev: variance.sqrt(),
+        })
+    }
+
+    /// Buffered values counted into `bins` equal bins between their min and max
+    pub fn histogram(&self, bins: usize) -> Option<Histogram> {
+        let stats = self.stats()?;
+        let span = stats.max - stats.min;
+        // Identical values all land in the one bin there is
+        let bins = if span > 0. { bins.max(1) } else { 1 };
+        let width = span / bins as f64;
+        let mut counts = vec![0; bins];
+        for y in self.values.iter().map(|p| p.y).filter(|y| !y.is_nan()) {
+            let bin = if span > 0. {
+                ((y - stats.min) / width) as usize
+            } else {
+                0
+            };
+            counts[bin.min(bins - 1)] += 1;
+        }
+        Some(Histogram {
+            min: stats.min,
+            width,
+            counts,
+        })
+    }
+
+    /// Whether every buffered value is either 0 or 1
+    pub fn is_boolean(&self) -> bool {
+        let mut values = self.values.iter().filter(|p| !p.y.is_nan()).peekable();
+        values.peek().is_some() && values.all(|p| p.y == 0. || p.y == 1.)
+    }
+
+    /// Runs of values to draw as separate lines, split at NaN samples when
+    /// `gaps` is [`GapMode::Break`] and with NaN samples left out otherwise.
+    ///
+    /// Past [`MAX_DRAWN_POINTS`] values, every bucket of consecutive values is
+    /// drawn as just its lowest and highest one, so spikes stay visible.
+    pub fn segments(&self, gaps: GapMode) -> Vec<Vec<PlotPoint>> {
+        let mut segments = vec![Vec::new()];
+        for point in self.values.iter() {
+            if !point.y.is_nan() {
+                segments.last_mut().unwrap().push(*point);
+            } else if gaps == GapMode::Break && !segments.last().unwrap().is_empty() {
+                segments.push(Vec::new());
+            }
+        }
+        thin_segments(segments, self.values.len())
+    }
+
+    /// Rate of change between successive values, in units per second, at the
+    /// time of the later value.
+    ///
+    /// The first value has nothing to compare with, and a value less than
+    /// [`MIN_DERIVATIVE_DT`] after the last one compared against, like another
+    /// value of the same packet, is skipped rather than divided by almost 0.
+    pub fn derivative_values(&self, gaps: GapMode) -> Vec<PlotPoints> {
+        let mut segments = vec![Vec::new()];
+        let mut previous: Option<PlotPoint> = None;
+        for point in self.values.iter() {
+            if point.y.is_nan() {
+                if gaps == GapMode::Break {
+                    previous = None;
+                    if !segments.last().unwrap().is_empty() {
+                        segments.push(Vec::new());
+                    }
+                }
+                continue;
+            }
+            if let Some(prev) = previous {
+                let dt = point.x - prev.x;
+                if dt < MIN_DERIVATIVE_DT {
+                    continue;
+                }
+                segments
+                    .last_mut()
+                    .unwrap()
+                    .push(PlotPoint::new(point.x, (point.y - prev.y) / dt));
+            }
+            previous = Some(*point);
+        }
+        thin_segments(segments, self.values.len())
+            .into_iter()
+            .map(PlotPoints::Owned)
+            .collect()
+    }
+
+    pub fn plot_values(&self, gaps: GapMode) -> Vec<PlotPoints> {
+        self.segments(gaps)
+            .into_iter()
+            .map(PlotPoints::Owned)
+            .collect()
+    }
+
+    /// Values with an extra point before every change so the line holds its
+    /// level until the next sample instead of interpolating towards it
+    pub fn step_values(&self, gaps: GapMode) -> Vec<PlotPoints> {
+        self.lane_values(0., gaps)
+    }
+
+    /// Step values shifted up by `base`, used to stack logic lanes
+    pub fn lane_values(&self, base: f64, gaps: GapMode) -> Vec<PlotPoints> {
+        self.segments(gaps)
+            .into_iter()
+            .map(|segment| {
+                let mut points = Vec::with_capacity(segment.len() * 2);
+                let mut previous: Option<PlotPoint> = None;
+                for point in segment {
+                    if let Some(prev) = previous {
+                        points.push(PlotPoint::new(point.x, base + prev.y));
+                    }
+                    points.push(PlotPoint::new(point.x, base + point.y));
+                    previous = Some(point);
+                }
+                PlotPoints::Owned(points)
+            })
+            .collect()
+    }
+}
+
+/// `segments` without the empty ones, decimated when `total` values went into
+/// them, more than [`MAX_DRAWN_POINTS`]
+fn thin_segments(mut segments: Vec<Vec<PlotPoint>>, total: usize) -> Vec<Vec<PlotPoint>> {
+    segments.retain(|segment| !segment.is_empty());
+    let bucket = total.div_ceil(MAX_DRAWN_POINTS / 2).max(1);
+    if bucket > 1 {
+        for segment in segments.iter_mut() {
+            *segment = decimate(segment, bucket);
+        }
+    }
+    segments
+}
+
+/// The lowest and highest point of every `bucket` consecutive `points`, in order
+fn decimate(points: &[PlotPoint], bucket: usize) -> Vec<PlotPoint> {
+    let mut kept = Vec::with_capacity(points.len() / bucket * 2 + 2);
+    for chunk in points.chunks(bucket) {
+        let (mut low, mut high) = (0, 0);
+        for (i, point) in chunk.iter().enumerate() {
+            if point.y < chunk[low].y {
+                low = i;
+            }
+            if point.y > chunk[high].y {
+                high = i;
+            }
+        }
+        kept.push(chunk[low.min(high)]);
+        if low != high {
+            kept.push(chunk[low.max(high)]);
+        }
+    }
+    kept
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// Population standard deviation
+    pub std_dev: f64,
+}
+
+/// Distribution of a channel's values
+#[derive(Debug)]
+pub struct Histogram {
+    /// Lower edge of the first bin
+    pub min: f64,
+    /// Width of every bin, 0 when all values are the same
+    pub width: f64,
+    pub counts: Vec<usize>,
+}
+
+/// What the plot does where a channel has no value
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum GapMode {
+    #[default]
+    /// Leave the gap empty
+    Break,
+    /// Draw straight across the gap
+    Connect,
+}
+
+impl Display for GapMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GapMode::Break => write!(f, "Break"),
+            GapMode::Connect => write!(f, "Connect"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer(regression: Regression) -> Measurement {
+        Measurement {
+            regression,
+            ..Measurement::new_with_look_behind(1_000)
+        }
+    }
+
+    fn points(measurement: &Measurement) -> Vec<(f64, f64)> {
+        measurement.values.iter().map(|p| (p.x, p.y)).collect()
+    }
+
+    /// Samples at 1, 2 and 3 s, then one at 1.5 s from before the newest
+    fn add_with_regression(measurement: &mut Measurement) {
+        for (x, y) in [(1., 10.), (2., 20.), (3., 30.), (1.5, 15.)] {
+            measurement.add(PlotPoint::new(x, y));
+        }
+    }
+
+    #[test]
+    fn in_order_samples_are_kept_by_every_mode() {
+        for regression in [Regression::Clear, Regression::Sort, Regression::Ignore] {
+            let mut measurement = buffer(regression);
+            for (x, y) in [(1., 10.), (2., 20.), (2., 21.), (3., 30.)] {
+                measurement.add(PlotPoint::new(x, y));
+            }
+            assert_eq!(
+                points(&measurement),
+                [(1., 10.), (2., 20.), (2., 21.), (3., 30.)],
+                "{regression}"
+            );
+        }
+    }
+
+    #[test]
+    fn clear_starts_over_at_the_older_sample() {
+        let mut measurement = buffer(Regression::Clear);
+        add_with_regression(&mut measurement);
+        assert_eq!(points(&measurement), [(1.5, 15.)]);
+        measurement.add(PlotPoint::new(2.5, 25.));
+        assert_eq!(points(&measurement), [(1.5, 15.), (2.5, 25.)]);
+    }
+
+    #[test]
+    fn shifted_values_stay_before_new_samples() {
+        let mut measurement = buffer(Regression::Clear);
+        for (x, y) in [(1., 10.), (3., 30.)] {
+            measurement.add(PlotPoint::new(x, y));
+        }
+        measurement.capture_reference();
+        measurement.shift(3.5);
+        measurement.add(PlotPoint::new(0.5, 5.));
+        assert_eq!(points(&measurement), [(-2.5, 10.), (-0.5, 30.), (0.5, 5.)]);
+        let reference: Vec<f64> = measurement.reference.iter().map(|p| p.x).collect();
+        assert_eq!(reference, [-2.5, -0.5]);
+    }
+
+    #[test]
+    fn sort_inserts_the_older_sample_in_time_order() {
+        let mut measurement = buffer(Regression::Sort);
+        add_with_regression(&mut measurement);
+        assert_eq!(
+            points(&measurement),
+            [(1., 10.), (1.5, 15.), (2., 20.), (3., 30.)]
+        );
+        // A sample at an existing time goes after the ones already there
+        measurement.add(PlotPoint::new(2., 21.));
+        assert_eq!(
+            points(&measurement),
+            [(1., 10.), (1.5, 15.), (2., 20.), (2., 21.), (3., 30.)]
+        );
+    }
+
+    #[test]
+    fn sort_still_trims_to_the_limits() {
+        let mut measurement = Measurement {
+            max_samples: 3,
+            ..buffer(Regression::Sort)
+        };
+        add_with_regression(&mut measurement);
+        assert_eq!(points(&measurement), [(1.5, 15.), (2., 20.), (3., 30.)]);
+    }
+
+    #[test]
+    fn ignore_drops_the_older_sample() {
+        let mut measurement = buffer(Regression::Ignore);
+        add_with_regression(&mut measurement);
+        assert_eq!(points(&measurement), [(1., 10.), (2., 20.), (3., 30.)]);
+        measurement.add(PlotPoint::new(4., 40.));
+        assert_eq!(
+            points(&measurement),
+            [(1., 10.), (2., 20.), (3., 30.), (4., 40.)]
+        );
+    }
+
+    fn with_values(ys: &[f64]) -> Measurement {
+        let mut measurement = buffer(Regression::Clear);
+        for (x, y) in ys.iter().enumerate() {
+            measurement.add(PlotPoint::new(x as f64, *y));
+        }
+        measurement
+    }
+
+    /// First and last x of every segment
+    fn bounds(segments: &[Vec<PlotPoint>]) -> Vec<(f64, f64)> {
+        segments
+            .iter()
+            .map(|segment| (segment[0].x, segment[segment.len() - 1].x))
+            .collect()
+    }
+
+    #[test]
+    fn interior_nan_breaks_the_line_in_two() {
+        let measurement = with_values(&[1., 2., f64::NAN, 4., 5.]);
+        let segments = measurement.segments(GapMode::Break);
+        assert_eq!(bounds(&segments), [(0., 1.), (3., 4.)]);
+        assert!(segments.iter().flatten().all(|p| !p.y.is_nan()));
+    }
+
+    #[test]
+    fn consecutive_nans_make_one_gap() {
+        let measurement = with_values(&[1., f64::NAN, f64::NAN, 4.]);
+        assert_eq!(
+            bounds(&measurement.segments(GapMode::Break)),
+            [(0., 0.), (3., 3.)]
+        );
+    }
+
+    #[test]
+    fn leading_and_trailing_nans_leave_no_empty_segments() {
+        let measurement = with_values(&[f64::NAN, 1., 2., f64::NAN]);
+        assert_eq!(bounds(&measurement.segments(GapMode::Break)), [(1., 2.)]);
+        let measurement = with_values(&[f64::NAN, f64::NAN]);
+        assert!(measurement.segments(GapMode::Break).is_empty());
+    }
+
+    #[test]
+    fn connect_draws_across_the_gap() {
+        let measurement = with_values(&[1., 2., f64::NAN, 4., 5.]);
+        let segments = measurement.segments(GapMode::Connect);
+        assert_eq!(bounds(&segments), [(0., 4.)]);
+        assert_eq!(segments[0].len(), 4);
+    }
+
+    /// Samples every `step` seconds from 0 to 19 s, with the given limits
+    fn bounded(look_behind: usize, max_samples: usize, step: f64) -> Measurement {
+        let mut measurement = Measurement {
+            max_samples,
+            ..Measurement::new_with_look_behind(look_behind)
+        };
+        let count = (19. / step) as usize;
+        for i in 0..=count {
+            measurement.add(PlotPoint::new(i as f64 * step, 1.));
+        }
+        measurement
+    }
+
+    /// Oldest and newest x left, and how many values
+    fn range(measurement: &Measurement) -> (f64, f64, usize) {
+        let values = &measurement.values;
+        (values[0].x, values[values.len() - 1].x, values.len())
+    }
+
+    #[test]
+    fn sample_limit_binds_alone() {
+        assert_eq!(range(&bounded(100, 5, 1.)), (15., 19., 5));
+    }
+
+    #[test]
+    fn time_window_binds_alone() {
+        // No sample limit, the 5 s window keeps the values from 14 s on
+        assert_eq!(range(&bounded(5, 0, 1.)), (14., 19., 6));
+        assert_eq!(range(&bounded(5, 100, 1.)), (14., 19., 6));
+    }
+
+    #[test]
+    fn the_limit_dropping_more_values_wins() {
+        // 11 values are in the 5 s window, the sample limit keeps fewer
+        assert_eq!(range(&bounded(5, 8, 0.5)), (15.5, 19., 8));
+        // 3 values are in the 2 s window, fewer than the sample limit
+        assert_eq!(range(&bounded(2, 8, 1.)), (17., 19., 3));
+        // Both limits keep the same values
+        assert_eq!(range(&bounded(5, 6, 1.)), (14., 19., 6));
+    }
+
+    #[test]
+    fn unlimited_ignores_both_limits() {
+        let mut measurement = Measurement {
+            unlimited: true,
+            ..bounded(2, 3, 1.)
+        };
+        // Values added before turning it on were already trimmed
+        assert_eq!(range(&measurement), (17., 19., 3));
+        for x in 20..40 {
+            measurement.add(PlotPoint::new(x as f64, 1.));
+        }
+        assert_eq!(range(&measurement), (17., 39., 23));
+    }
+}
@@ -0,0 +1,153 @@
+//! NMEA 0183 sentences from GPS modules, like `$GPGGA,...*47`.
+//!
+//! GGA, RMC and VTG sentences from any talker are understood, everything
+//! else is rejected as unsupported.
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::checksum::Scheme;
+
+/// Kilometres per hour in a knot
+const KMH_PER_KNOT: f64 = 1.852;
+
+/// A reading a sentence can carry
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Field {
+    #[default]
+    Latitude,
+    Longitude,
+    Altitude,
+    Speed,
+    Course,
+    Satellites,
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Field::Latitude => write!(f, "Latitude"),
+            Field::Longitude => write!(f, "Longitude"),
+            Field::Altitude => write!(f, "Altitude (m)"),
+            Field::Speed => write!(f, "Speed (km/h)"),
+            Field::Course => write!(f, "Course (°)"),
+            Field::Satellites => write!(f, "Satellites"),
+        }
+    }
+}
+
+impl Field {
+    pub const ALL: [Field; 6] = [
+        Field::Latitude,
+        Field::Longitude,
+        Field::Altitude,
+        Field::Speed,
+        Field::Course,
+        Field::Satellites,
+    ];
+}
+
+/// Why a line didn't give any readings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejected {
+    /// It isn't a sentence, or is missing fields
+    Malformed,
+    /// The checksum after `*` doesn't match
+    BadChecksum,
+    /// A sentence type without any of the readings
+    Unsupported,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NmeaOptions {
+    /// Parse payloads as sentences instead of splitting them on the delimiter
+    pub enabled: bool,
+    /// One field per channel, in channel order
+    pub fields: Vec<Field>,
+}
+
+impl Default for NmeaOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            fields: vec![
+                Field::Latitude,
+                Field::Longitude,
+                Field::Altitude,
+                Field::Speed,
+            ],
+        }
+    }
+}
+
+/// Readings in the sentence `line`, in no particular order.
+///
+/// Fields a sentence has no fix for are left out. Latitudes south and
+/// longitudes west of zero are negative, in decimal degrees.
+pub fn parse(line: &str) -> Result<Vec<(Field, f64)>, Rejected> {
+    let sentence = line.trim().strip_prefix('$').ok_or(Rejected::Malformed)?;
+    let (data, checksum) = sentence.rsplit_once('*').ok_or(Rejected::Malformed)?;
+    let expected = u16::from_str_radix(checksum, 16).map_err(|_| Rejected::Malformed)?;
+    if Scheme::Xor.compute(data.as_bytes()) != Some(expected) {
+        return Err(Rejected::BadChecksum);
+    }
+    let fields: Vec<&str> = data.split(',').collect();
+    let kind = fields[0].get(2..).ok_or(Rejected::Malformed)?;
+    let field = |idx: usize| fields.get(idx).copied().ok_or(Rejected::Malformed);
+    let mut readings = Vec::new();
+    match kind {
+        "GGA" => {
+            let quality = field(6)?;
+            if !quality.is_empty() && quality != "0" {
+                push_position(&mut readings, field(2)?, field(3)?, field(4)?, field(5)?);
+                push(&mut readings, Field::Altitude, field(9)?, 1.);
+            }
+            push(&mut readings, Field::Satellites, field(7)?, 1.);
+        }
+        "RMC" => {
+            if field(2)? == "A" {
+                push_position(&mut readings, field(3)?, field(4)?, field(5)?, field(6)?);
+                push(&mut readings, Field::Speed, field(7)?, KMH_PER_KNOT);
+                push(&mut readings, Field::Course, field(8)?, 1.);
+            }
+        }
+        "VTG" => {
+            push(&mut readings, Field::Course, field(1)?, 1.);
+            push(&mut readings, Field::Speed, field(7)?, 1.);
+        }
+        _ => return Err(Rejected::Unsupported),
+    }
+    Ok(readings)
+}
+
+/// Add `text` times `factor` as `field`, unless it's empty or not a number
+fn push(readings: &mut Vec<(Field, f64)>, field: Field, text: &str, factor: f64) {
+    if let Ok(value) = text.parse::<f64>() {
+        readings.push((field, value * factor));
+    }
+}
+
+/// Add the latitude and longitude of `ddmm.mmmm` and `dddmm.mmmm` coordinates
+fn push_position(
+    readings: &mut Vec<(Field, f64)>,
+    lat: &str,
+    north_south: &str,
+    lon: &str,
+    east_west: &str,
+) {
+    if let Some(lat) = degrees(lat, north_south == "S") {
+        readings.push((Field::Latitude, lat));
+    }
+    if let Some(lon) = degrees(lon, east_west == "W") {
+        readings.push((Field::Longitude, lon));
+    }
+}
+
+/// Decimal degrees of a coordinate in degrees and minutes, negative when `negate`
+fn degrees(text: &str, negate: bool) -> Option<f64> {
+    let value = text.parse::<f64>().ok()?;
+    let degrees = (value / 100.).trunc() + value % 100. / 60.;
+    Some(if negate { -degrees } else { degrees })
+}
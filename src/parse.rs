@@ -0,0 +1,201 @@
+//! Turning packets into column values, on a thread of their own.
+//!
+//! The serial thread only cuts the byte stream into packets and the parse
+//! thread splits, checks and converts them, so an expensive transform can't
+//! hold up the read loop. The GUI only puts finished values into channels.
+
+use std::sync::{
+    mpsc::{Receiver, SyncSender},
+    Arc, RwLock,
+};
+
+use serialib::{modbus, Framing, Packet};
+
+use crate::{
+    binary::{BinaryLayout, FieldType},
+    checksum::ChecksumOptions,
+    nmea::{self, NmeaOptions, Rejected},
+    transform::{PayloadTransform, Transform},
+};
+
+/// What a packet held in one source column
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reading {
+    Number {
+        value: f64,
+        integer: bool,
+    },
+    /// Something that isn't a number
+    Invalid,
+    /// Nothing between two delimiters
+    Empty,
+    /// The packet doesn't carry this column at all
+    Absent,
+}
+
+/// What became of a packet
+#[derive(Debug, Clone, PartialEq)]
+pub enum Parsed {
+    /// A delimited text line, one reading per token
+    Line(Vec<Reading>),
+    /// A line of `name:value` tokens, one reading per name
+    Labeled(Vec<(String, Reading)>),
+    /// A binary frame, Modbus reply or NMEA sentence, one reading per field
+    Fields(Vec<Reading>),
+    /// The checksum of the named kind of packet didn't match
+    BadChecksum(&'static str),
+    /// A Modbus device answered with an exception
+    Exception(String),
+    /// Nothing to plot, like a line the transform dropped
+    Ignored,
+}
+
+/// A packet with what the parse thread made of it
+#[derive(Debug)]
+pub struct ParsedPacket {
+    pub packet: Packet,
+    pub parsed: Parsed,
+}
+
+/// Settings that decide how payloads are parsed, as set in the Plot panel
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Parser {
+    /// Framing of the session, binary framings aren't split on a delimiter
+    pub framing: Framing,
+    /// Text to split on, `None` while the typed delimiter is invalid
+    pub delimiter: Option<String>,
+    pub transform: Transform,
+    pub checksum: ChecksumOptions,
+    pub binary: BinaryLayout,
+    pub nmea: NmeaOptions,
+    /// Tokens are `name:value` pairs, like the Arduino Serial Plotter takes
+    pub labeled: bool,
+}
+
+impl Parser {
+    pub fn parse(&self, packet: &Packet) -> Parsed {
+        if let Framing::Fixed { sync, .. } = self.framing {
+            // The sync byte only marks where a frame starts
+            let frame = packet
+                .raw
+                .get(usize::from(sync.is_some())..)
+                .unwrap_or_default();
+            let readings = self
+                .binary
+                .decode(frame)
+                .into_iter()
+                .zip(self.binary.fields.iter().map(FieldType::is_integer))
+                .map(|(value, integer)| Reading::Number { value, integer })
+                .collect();
+            return Parsed::Fields(readings);
+        }
+        if self.framing == Framing::ModbusRtu {
+            return match modbus::decode(&packet.raw) {
+                Ok(values) => Parsed::Fields(
+                    values
+                        .into_iter()
+                        .map(|value| Reading::Number {
+                            value,
+                            integer: true,
+                        })
+                        .collect(),
+                ),
+                Err(e) => Parsed::Exception(e),
+            };
+        }
+        if self.nmea.enabled {
+            return self.parse_sentence(&packet.payload);
+        }
+        let Some(delimiter) = &self.delimiter else {
+            return Parsed::Ignored;
+        };
+        let Some(payload) = self.transform.apply(&packet.payload) else {
+            return Parsed::Ignored;
+        };
+        let Some(payload) = self.checksum.verify(&payload, delimiter) else {
+            return Parsed::BadChecksum("packet");
+        };
+        let tokens = payload.split(delimiter.as_str());
+        if self.labeled {
+            return Parsed::Labeled(labeled_readings(tokens));
+        }
+        Parsed::Line(tokens.map(reading).collect())
+    }
+
+    /// Readings of the configured fields of the NMEA sentence `line`, ignoring
+    /// anything that isn't one
+    fn parse_sentence(&self, line: &str) -> Parsed {
+        let readings = match nmea::parse(line) {
+            Ok(readings) => readings,
+            Err(Rejected::BadChecksum) => return Parsed::BadChecksum("sentence"),
+            Err(Rejected::Malformed | Rejected::Unsupported) => return Parsed::Ignored,
+        };
+        let fields = self
+            .nmea
+            .fields
+            .iter()
+            .map(|field| match readings.iter().find(|(f, _)| f == field) {
+                Some((_, value)) => Reading::Number {
+                    value: *value,
+                    integer: *field == nmea::Field::Satellites,
+                },
+                None => Reading::Absent,
+            })
+            .collect();
+        Parsed::Fields(fields)
+    }
+}
+
+/// What the text of one column holds
+fn reading(token: &str) -> Reading {
+    // Stray spaces or a leftover \r around a number still parse, the text view keeps them
+    let token = token.trim_matches(|c: char| c.is_ascii_whitespace());
+    match token.parse::<f64>() {
+        Ok(value) => Reading::Number {
+            value,
+            integer: token.parse::<i64>().is_ok(),
+        },
+        Err(_) if token.is_empty() => Reading::Empty,
+        Err(_) => Reading::Invalid,
+    }
+}
+
+/// Name and reading of every `name:value` token.
+///
+/// A token without a name is named `Value N` after its position, counting
+/// from 1 like the Arduino Serial Plotter, so a line of plain numbers still
+/// plots each column as its own channel. Empty unnamed tokens, like after a
+/// trailing comma, are left out.
+fn labeled_readings<'a>(tokens: impl Iterator<Item = &'a str>) -> Vec<(String, Reading)> {
+    tokens
+        .enumerate()
+        .filter_map(|(position, token)| match token.split_once(':') {
+            Some((name, value)) => Some((String::from(name.trim()), reading(value))),
+            None => match reading(token) {
+                Reading::Empty => None,
+                value => Some((format!("Value {}", position + 1), value)),
+            },
+        })
+        .collect()
+}
+
+/// Parse every packet from `raw_data_rx` with the latest `parser` and pass it
+/// on to `parsed_tx`, until either side hangs up.
+///
+/// A full `parsed_tx` holds the thread up, so packets pile up in the serial
+/// thread's queue instead, where dropping them is counted.
+pub fn parse_thread(
+    raw_data_rx: Receiver<Packet>,
+    parsed_tx: SyncSender<ParsedPacket>,
+    parser: Arc<RwLock<Parser>>,
+) {
+    for packet in raw_data_rx {
+        let parsed = match parser.read() {
+            Ok(parser) => parser.parse(&packet),
+            Err(_) => return,
+        };
+        if parsed_tx.send(ParsedPacket { packet, parsed }).is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,247 @@
+//! The bytes of the raw stream view, kept as read and split into rows.
+//!
+//! The view keeps up to a few MiB, far more than can be laid out every
+//! frame. Rows are found once as bytes arrive, so drawing only decodes the
+//! rows on screen, see [`RawStream::row`].
+
+use std::collections::VecDeque;
+
+/// Longest row of the raw view in bytes, longer runs without a line break wrap
+pub const ROW_BYTES: usize = 128;
+
+/// The most recent bytes of a stream, up to a capacity, with where each of
+/// their rows starts
+#[derive(Debug, Default)]
+pub struct RawStream {
+    bytes: VecDeque<u8>,
+    /// Position in the whole stream of the first byte still kept
+    offset: u64,
+    /// Positions in the whole stream where each kept row starts
+    rows: VecDeque<u64>,
+    /// Bytes were skipped since the last push, so the next ones may finish a
+    /// character whose start never made it in
+    gap: bool,
+}
+
+impl RawStream {
+    /// Append `bytes`, dropping the oldest past `capacity` bytes
+    pub fn push(&mut self, bytes: &[u8], capacity: usize) {
+        let bytes = match self.gap {
+            true => &bytes[leading_continuations(bytes)..],
+            false => bytes,
+        };
+        if !bytes.is_empty() {
+            self.gap = false;
+        }
+        for &byte in bytes {
+            let position = self.offset + self.bytes.len() as u64;
+            let starts_row = match (self.rows.back(), self.bytes.back()) {
+                (None, _) | (_, Some(b'\n')) => true,
+                (Some(&start), _) => position - start >= ROW_BYTES as u64 && !is_continuation(byte),
+            };
+            if starts_row {
+                self.rows.push_back(position);
+            }
+            self.bytes.push_back(byte);
+        }
+        self.trim(capacity);
+    }
+
+    /// Leave out `bytes`, like while the view is paused. A character they
+    /// cut short at either end is left out whole.
+    pub fn skip(&mut self, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        let tail: Vec<u8> = self.bytes.iter().rev().take(4).rev().copied().collect();
+        for _ in 0..incomplete_tail(&tail) {
+            self.bytes.pop_back();
+        }
+        let end = self.offset + self.bytes.len() as u64;
+        while self.rows.back().is_some_and(|&start| start >= end) {
+            self.rows.pop_back();
+        }
+        self.gap = true;
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Text of row `idx`, line break included, without a character still
+    /// arriving at its end
+    pub fn row(&self, idx: usize) -> String {
+        let start = (self.rows[idx] - self.offset) as usize;
+        let end = self
+            .rows
+            .get(idx + 1)
+            .map_or(self.bytes.len(), |&next| (next - self.offset) as usize);
+        let bytes: Vec<u8> = self.bytes.range(start..end).copied().collect();
+        decode_stream(&bytes)
+    }
+
+    /// Drop the oldest bytes past `capacity`, and the rest of a character
+    /// that cuts short
+    fn trim(&mut self, capacity: usize) {
+        let mut overflow = self.bytes.len().saturating_sub(capacity);
+        if overflow == 0 {
+            return;
+        }
+        while self
+            .bytes
+            .get(overflow)
+            .is_some_and(|&b| is_continuation(b))
+        {
+            overflow += 1;
+        }
+        self.bytes.drain(..overflow);
+        self.offset += overflow as u64;
+        while self.rows.get(1).is_some_and(|&next| next <= self.offset) {
+            self.rows.pop_front();
+        }
+        match self.bytes.is_empty() {
+            true => self.rows.clear(),
+            false => {
+                if let Some(first) = self.rows.front_mut() {
+                    *first = (*first).max(self.offset);
+                }
+            }
+        }
+    }
+}
+
+fn is_continuation(byte: u8) -> bool {
+    byte & 0xC0 == 0x80
+}
+
+/// How many bytes at the start of `bytes` continue a character started before them
+fn leading_continuations(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .take(3)
+        .take_while(|&&b| is_continuation(b))
+        .count()
+}
+
+/// Decode a window into a byte stream as UTF-8, leaving out the pieces of
+/// characters cut off at either end.
+///
+/// A character can be split between the bytes shown now and those arriving
+/// next, or lose its start when old bytes are dropped, and decoding those
+/// pieces on their own would show replacement characters that were never sent.
+pub fn decode_stream(bytes: &[u8]) -> String {
+    let bytes = &bytes[leading_continuations(bytes)..];
+    let end = bytes.len() - incomplete_tail(bytes);
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+/// How many bytes at the end of `bytes` start a character that isn't all there yet
+pub fn incomplete_tail(bytes: &[u8]) -> usize {
+    // Walk back to where the last character starts and check it's all there
+    for back in 1..=bytes.len().min(4) {
+        let lead = bytes[bytes.len() - back];
+        if is_continuation(lead) {
+            continue;
+        }
+        let len = match lead {
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => 1,
+        };
+        return match len > back {
+            true => back,
+            false => 0,
+        };
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(stream: &RawStream) -> Vec<String> {
+        (0..stream.row_count()).map(|idx| stream.row(idx)).collect()
+    }
+
+    #[test]
+    fn characters_split_between_reads_are_decoded_whole() {
+        for text in ["aé", "a€b", "😀", "x😀€é"] {
+            let bytes = text.as_bytes();
+            for split in 0..=bytes.len() {
+                let mut stream = RawStream::default();
+                stream.push(&bytes[..split], 1024);
+                assert!(!rows(&stream).concat().contains('\u{FFFD}'));
+                stream.push(&bytes[split..], 1024);
+                assert_eq!(rows(&stream), [text], "split at {split}");
+            }
+        }
+    }
+
+    #[test]
+    fn characters_fed_a_byte_at_a_time_are_decoded_whole() {
+        let text = "é€😀";
+        let mut stream = RawStream::default();
+        for byte in text.bytes() {
+            stream.push(&[byte], 1024);
+            assert!(!rows(&stream).concat().contains('\u{FFFD}'));
+        }
+        assert_eq!(rows(&stream), [text]);
+    }
+
+    #[test]
+    fn rows_end_after_line_breaks_or_wrap() {
+        let mut stream = RawStream::default();
+        stream.push(b"a\r\nb\n", 1024);
+        stream.push(b"\nc", 1024);
+        assert_eq!(rows(&stream), ["a\r\n", "b\n", "\n", "c"]);
+
+        let long = "é".repeat(ROW_BYTES);
+        stream.clear();
+        stream.push(long.as_bytes(), 1024);
+        assert_eq!(rows(&stream), [&long[..ROW_BYTES], &long[ROW_BYTES..]]);
+    }
+
+    #[test]
+    fn the_oldest_bytes_go_past_the_capacity() {
+        let mut stream = RawStream::default();
+        stream.push(b"one\ntwo\n", 6);
+        assert_eq!(rows(&stream), ["e\n", "two\n"]);
+        stream.push(b"three", 6);
+        assert_eq!(rows(&stream), ["\n", "three"]);
+        // A character cut short at the front goes whole
+        stream.push("€x".as_bytes(), 3);
+        assert_eq!(rows(&stream), ["x"]);
+    }
+
+    #[test]
+    fn skipped_bytes_leave_no_pieces_behind() {
+        let bytes = "a€b€c".as_bytes();
+        let mut stream = RawStream::default();
+        // a and the start of €
+        stream.push(&bytes[..2], 1024);
+        // The rest of €, b and the start of the second €
+        stream.skip(&bytes[2..6]);
+        // The rest of the second €, then c
+        stream.push(&bytes[6..], 1024);
+        assert_eq!(rows(&stream), ["ac"]);
+    }
+
+    #[test]
+    fn windows_cut_mid_character_leave_the_pieces_out() {
+        let bytes = "é€😀".as_bytes();
+        for start in 0..bytes.len() {
+            for end in start..=bytes.len() {
+                let text = decode_stream(&bytes[start..end]);
+                assert!(!text.contains('\u{FFFD}'), "window {start}..{end}");
+            }
+        }
+        assert_eq!(decode_stream(bytes), "é€😀");
+        assert_eq!(decode_stream(&bytes[1..bytes.len() - 1]), "€");
+    }
+}
@@ -0,0 +1,137 @@
+//! Rules that react to received lines, by logging them, bookmarking them on
+//! the plot or sending a reply.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::Command;
+
+/// Shortest time between two replies of the same rule, so two devices
+/// answering each other can't flood the port
+pub const RULE_REPLY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What a rule does when a received line matches it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleAction {
+    #[default]
+    Log,
+    /// Mark the line's time on the plot
+    Bookmark,
+    /// Send the rule's reply, at most once per [`RULE_REPLY_INTERVAL`]
+    Send,
+}
+
+impl RuleAction {
+    pub const ALL: [RuleAction; 3] = [RuleAction::Log, RuleAction::Bookmark, RuleAction::Send];
+}
+
+impl Display for RuleAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleAction::Log => write!(f, "Log"),
+            RuleAction::Bookmark => write!(f, "Bookmark"),
+            RuleAction::Send => write!(f, "Send"),
+        }
+    }
+}
+
+/// Reacts to every received line that contains `pattern`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rule {
+    pub enabled: bool,
+    pub pattern: String,
+    pub action: RuleAction,
+    /// Sent for [`RuleAction::Send`]
+    pub reply: Command,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            pattern: String::new(),
+            action: RuleAction::default(),
+            reply: Command::default(),
+        }
+    }
+}
+
+impl Rule {
+    pub fn matches(&self, line: &str) -> bool {
+        self.enabled && !self.pattern.is_empty() && line.contains(&self.pattern)
+    }
+}
+
+/// Indices of the rules in `rules` that `line` matches
+pub fn matching(rules: &[Rule], line: &str) -> Vec<usize> {
+    (0..rules.len())
+        .filter(|idx| rules[*idx].matches(line))
+        .collect()
+}
+
+/// When each rule, by index, last sent its reply
+#[derive(Debug, Default)]
+pub struct ReplyThrottle {
+    replied: HashMap<usize, Instant>,
+}
+
+impl ReplyThrottle {
+    /// Whether rule `idx` may reply at `now`, noting the reply when it may
+    pub fn allow(&mut self, idx: usize, now: Instant) -> bool {
+        let recent = self
+            .replied
+            .get(&idx)
+            .is_some_and(|at| now.duration_since(*at) < RULE_REPLY_INTERVAL);
+        if !recent {
+            self.replied.insert(idx, now);
+        }
+        !recent
+    }
+
+    /// Forget every reply, for when the rules are edited or replaced
+    pub fn clear(&mut self) {
+        self.replied.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str) -> Rule {
+        Rule {
+            pattern: String::from(pattern),
+            ..Rule::default()
+        }
+    }
+
+    #[test]
+    fn rules_match_lines_containing_their_pattern() {
+        let disabled = Rule {
+            enabled: false,
+            ..rule("ERR")
+        };
+        let rules = [rule("ERR"), rule(""), disabled, rule("temp")];
+        assert_eq!(matching(&rules, "ERR 12: temp high"), [0, 3]);
+        assert_eq!(matching(&rules, "ok"), [] as [usize; 0]);
+    }
+
+    #[test]
+    fn each_rule_replies_at_most_once_per_interval() {
+        let start = Instant::now();
+        let mut throttle = ReplyThrottle::default();
+        assert!(throttle.allow(0, start));
+        assert!(!throttle.allow(0, start + RULE_REPLY_INTERVAL / 2));
+        // Other rules have their own interval
+        assert!(throttle.allow(1, start + RULE_REPLY_INTERVAL / 2));
+        assert!(throttle.allow(0, start + RULE_REPLY_INTERVAL));
+        throttle.clear();
+        assert!(throttle.allow(1, start + RULE_REPLY_INTERVAL));
+    }
+}
@@ -0,0 +1,98 @@
+use std::{
+    env,
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Environment variable that overrides where settings are kept
+pub const SETTINGS_ENV: &str = "SYMPHONY_SETTINGS";
+
+/// Location of the settings file inside the platform's preferences directory
+pub fn default_path() -> Option<PathBuf> {
+    preferences::prefs_base_dir().map(|dir| dir.join("symphony").join("settings.json"))
+}
+
+/// Pick the settings file, preferring `cli` over [`SETTINGS_ENV`] over the default
+pub fn resolve_path(cli: Option<PathBuf>) -> Option<PathBuf> {
+    cli.or_else(|| env::var_os(SETTINGS_ENV).map(PathBuf::from))
+        .or_else(default_path)
+}
+
+/// Load the saved settings, `None` if there are none yet.
+///
+/// A file that can't be read or doesn't parse is an error, so it isn't taken
+/// for missing and saved over, see [`back_up`].
+pub fn load<T: DeserializeOwned>(path: &Path) -> anyhow::Result<Option<T>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(Some(serde_json::from_str(&contents)?))
+}
+
+/// Move a settings file that didn't load out of the way, returning where it went
+pub fn back_up(path: &Path) -> anyhow::Result<PathBuf> {
+    let backup = with_suffix(path, ".bad");
+    fs::rename(path, &backup)?;
+    Ok(backup)
+}
+
+/// Write `settings` to a file next to `path` and move it over `path`, so a
+/// crash halfway leaves the old settings whole
+pub fn save<T: Serialize>(path: &Path, settings: &T) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let temp = with_suffix(path, ".tmp");
+    let mut file = File::create(&temp)?;
+    file.write_all(serde_json::to_string_pretty(settings)?.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&temp, path)?;
+    Ok(())
+}
+
+/// `path` with `suffix` added to its file name, in the same directory
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("symphony-{}-{}.json", name, std::process::id()))
+    }
+
+    #[test]
+    fn saved_settings_load_back_without_a_temporary_file_left() {
+        let path = temp_path("settings-saved");
+        save(&path, &vec![1, 2, 3]).unwrap();
+        save(&path, &vec![4]).unwrap();
+        let loaded: Option<Vec<i32>> = load(&path).unwrap();
+        assert!(!with_suffix(&path, ".tmp").exists());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, Some(vec![4]));
+    }
+
+    #[test]
+    fn missing_settings_are_none_and_broken_ones_an_error() {
+        let missing = temp_path("settings-missing");
+        assert!(load::<Vec<i32>>(&missing).unwrap().is_none());
+
+        let broken = temp_path("settings-broken");
+        fs::write(&broken, "[1, 2").unwrap();
+        assert!(load::<Vec<i32>>(&broken).is_err());
+        let backup = back_up(&broken).unwrap();
+        assert!(!broken.exists());
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "[1, 2");
+        fs::remove_file(&backup).unwrap();
+    }
+}
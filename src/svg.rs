@@ -0,0 +1,287 @@
+//! Vector figures of the plot, for papers where a screenshot looks pixelated.
+//!
+//! A [`Figure`] is drawn at fixed bounds in data units: a frame with ticks,
+//! the axis labels, every series clipped to the frame and a legend in its top
+//! right corner.
+
+use std::io::{self, Write};
+
+/// Size of the whole figure in pixels
+pub const WIDTH: f64 = 800.;
+pub const HEIGHT: f64 = 500.;
+
+/// Space around the frame for tick labels and axis labels
+const MARGIN_LEFT: f64 = 80.;
+const MARGIN_RIGHT: f64 = 20.;
+const MARGIN_TOP: f64 = 20.;
+const MARGIN_BOTTOM: f64 = 50.;
+
+/// Roughly how many ticks each axis gets
+const TICKS: f64 = 6.;
+
+const FONT_SIZE: f64 = 12.;
+
+/// How a series is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stroke {
+    Solid,
+    Dashed,
+    /// A dot per point, without a line between them
+    Points,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    /// Shown in the legend, series sharing a name get one entry
+    pub name: String,
+    /// sRGB with unmultiplied alpha
+    pub color: [u8; 4],
+    pub stroke: Stroke,
+    /// Runs of points in data units, drawn without joining one to the next
+    pub segments: Vec<Vec<[f64; 2]>>,
+}
+
+/// A vertical line across the frame, like a bookmark
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marker {
+    pub x: f64,
+    pub color: [u8; 4],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Figure {
+    /// Left and right edge of the frame in data units
+    pub x_range: [f64; 2],
+    /// Bottom and top edge of the frame in data units
+    pub y_range: [f64; 2],
+    pub x_label: String,
+    pub y_label: String,
+    pub line_width: f32,
+    pub series: Vec<Series>,
+    pub markers: Vec<Marker>,
+}
+
+impl Figure {
+    /// Write the figure as a standalone SVG document, labelling y ticks with
+    /// `y_tick`, like the plot's own axis formatter
+    pub fn write(&self, out: &mut impl Write, y_tick: impl Fn(f64) -> String) -> io::Result<()> {
+        let left = MARGIN_LEFT;
+        let right = WIDTH - MARGIN_RIGHT;
+        let top = MARGIN_TOP;
+        let bottom = HEIGHT - MARGIN_BOTTOM;
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="{FONT_SIZE}">"#,
+            w = WIDTH,
+            h = HEIGHT
+        )?;
+        writeln!(
+            out,
+            r#"<defs><clipPath id="frame"><rect x="{left}" y="{top}" width="{}" height="{}"/></clipPath></defs>"#,
+            right - left,
+            bottom - top
+        )?;
+        writeln!(out, r#"<rect width="100%" height="100%" fill="white"/>"#)?;
+
+        for x in ticks(self.x_range) {
+            let px = self.to_px([x, 0.])[0];
+            writeln!(
+                out,
+                r##"<line x1="{px:.2}" y1="{top}" x2="{px:.2}" y2="{bottom}" stroke="#e0e0e0"/>"##
+            )?;
+            writeln!(
+                out,
+                r#"<text x="{px:.2}" y="{}" text-anchor="middle">{}</text>"#,
+                bottom + FONT_SIZE + 4.,
+                escape(&format_tick(x))
+            )?;
+        }
+        for y in ticks(self.y_range) {
+            let py = self.to_px([0., y])[1];
+            writeln!(
+                out,
+                r##"<line x1="{left}" y1="{py:.2}" x2="{right}" y2="{py:.2}" stroke="#e0e0e0"/>"##
+            )?;
+            writeln!(
+                out,
+                r#"<text x="{}" y="{py:.2}" text-anchor="end" dominant-baseline="middle">{}</text>"#,
+                left - 6.,
+                escape(&y_tick(y))
+            )?;
+        }
+        writeln!(
+            out,
+            r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+            (left + right) / 2.,
+            HEIGHT - 10.,
+            escape(&self.x_label)
+        )?;
+        writeln!(
+            out,
+            r#"<text x="16" y="{y}" text-anchor="middle" transform="rotate(-90 16 {y})">{}</text>"#,
+            escape(&self.y_label),
+            y = (top + bottom) / 2.
+        )?;
+
+        writeln!(out, r#"<g clip-path="url(#frame)">"#)?;
+        for marker in self.markers.iter() {
+            let px = self.to_px([marker.x, 0.])[0];
+            writeln!(
+                out,
+                r#"<line x1="{px:.2}" y1="{top}" x2="{px:.2}" y2="{bottom}" {} stroke-dasharray="4 4"/>"#,
+                paint("stroke", marker.color)
+            )?;
+        }
+        for series in self.series.iter() {
+            self.write_series(out, series)?;
+        }
+        writeln!(out, "</g>")?;
+        writeln!(
+            out,
+            r#"<rect x="{left}" y="{top}" width="{}" height="{}" fill="none" stroke="black"/>"#,
+            right - left,
+            bottom - top
+        )?;
+        self.write_legend(out)?;
+        writeln!(out, "</svg>")
+    }
+
+    /// Pixel position of the data point `point`
+    fn to_px(&self, point: [f64; 2]) -> [f64; 2] {
+        let [x_min, x_max] = self.x_range;
+        let [y_min, y_max] = self.y_range;
+        let width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+        let height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+        [
+            MARGIN_LEFT + (point[0] - x_min) / (x_max - x_min) * width,
+            HEIGHT - MARGIN_BOTTOM - (point[1] - y_min) / (y_max - y_min) * height,
+        ]
+    }
+
+    fn write_series(&self, out: &mut impl Write, series: &Series) -> io::Result<()> {
+        let width = self.line_width;
+        for segment in series.segments.iter() {
+            let points = segment
+                .iter()
+                .filter(|p| p[0].is_finite() && p[1].is_finite())
+                .map(|p| self.to_px(*p));
+            if series.stroke == Stroke::Points {
+                for [x, y] in points {
+                    writeln!(
+                        out,
+                        r#"<circle cx="{x:.2}" cy="{y:.2}" r="2" {}/>"#,
+                        paint("fill", series.color)
+                    )?;
+                }
+                continue;
+            }
+            let coordinates: Vec<String> = points.map(|[x, y]| format!("{x:.2},{y:.2}")).collect();
+            if coordinates.is_empty() {
+                continue;
+            }
+            let dash = match series.stroke {
+                Stroke::Dashed => r#" stroke-dasharray="6 3""#,
+                _ => "",
+            };
+            writeln!(
+                out,
+                r#"<polyline points="{}" fill="none" {} stroke-width="{width}" stroke-linejoin="round"{dash}/>"#,
+                coordinates.join(" "),
+                paint("stroke", series.color)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// A swatch and the name of every distinct series, in the top right corner
+    fn write_legend(&self, out: &mut impl Write) -> io::Result<()> {
+        let mut entries: Vec<&Series> = Vec::new();
+        for series in self.series.iter() {
+            if !entries.iter().any(|entry| entry.name == series.name) {
+                entries.push(series);
+            }
+        }
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let longest = entries
+            .iter()
+            .map(|entry| entry.name.chars().count())
+            .max()
+            .unwrap_or_default();
+        // No text metrics without a renderer, average glyphs are about 0.6 em wide
+        let width = 36. + longest as f64 * FONT_SIZE * 0.6;
+        let row = FONT_SIZE + 6.;
+        let x = WIDTH - MARGIN_RIGHT - width - 8.;
+        let y = MARGIN_TOP + 8.;
+        writeln!(
+            out,
+            r##"<rect x="{x:.2}" y="{y}" width="{width:.2}" height="{}" fill="white" fill-opacity="0.85" stroke="#a0a0a0"/>"##,
+            entries.len() as f64 * row + 6.
+        )?;
+        for (idx, entry) in entries.iter().enumerate() {
+            let center = y + 3. + (idx as f64 + 0.5) * row;
+            writeln!(
+                out,
+                r#"<line x1="{:.2}" y1="{center:.2}" x2="{:.2}" y2="{center:.2}" {} stroke-width="3"/>"#,
+                x + 6.,
+                x + 26.,
+                paint("stroke", entry.color)
+            )?;
+            writeln!(
+                out,
+                r#"<text x="{:.2}" y="{center:.2}" dominant-baseline="middle">{}</text>"#,
+                x + 32.,
+                escape(&entry.name)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Evenly spaced round values across `range`, steps of 1, 2 or 5 times a power of ten
+fn ticks([lo, hi]: [f64; 2]) -> Vec<f64> {
+    let span = hi - lo;
+    if !span.is_finite() || span <= 0. {
+        return Vec::new();
+    }
+    let rough = span / TICKS;
+    let magnitude = 10f64.powf(rough.log10().floor());
+    let step = [1., 2., 5., 10.]
+        .into_iter()
+        .map(|factor| factor * magnitude)
+        .find(|step| *step >= rough)
+        .unwrap_or(10. * magnitude);
+    let first = (lo / step).ceil() as i64;
+    let last = (hi / step).floor() as i64;
+    (first..=last).map(|n| n as f64 * step).collect()
+}
+
+/// A tick value without float noise, like `0.3` rather than `0.30000000000000004`
+pub fn format_tick(value: f64) -> String {
+    let text = format!("{:.6}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" => String::from("0"),
+        _ => String::from(text),
+    }
+}
+
+/// `attribute` set to the RGB of `color`, with an opacity when it isn't opaque
+fn paint(attribute: &str, [r, g, b, a]: [u8; 4]) -> String {
+    match a {
+        255 => format!(r#"{attribute}="rgb({r},{g},{b})""#),
+        _ => format!(
+            r#"{attribute}="rgb({r},{g},{b})" {attribute}-opacity="{:.3}""#,
+            f64::from(a) / 255.
+        ),
+    }
+}
+
+/// `text` with the characters XML gives a meaning replaced by entities
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,127 @@
+//! strftime-style formatting of wall-clock times, in UTC.
+//!
+//! Only these specifiers are supported, any other is an error:
+//!
+//! | Specifier | Meaning                                 | Example      |
+//! |-----------|-----------------------------------------|--------------|
+//! | `%Y`      | Year                                    | `2024`       |
+//! | `%y`      | Year within the century                 | `24`         |
+//! | `%m`      | Month, from 01                          | `03`         |
+//! | `%d`      | Day of the month, from 01               | `09`         |
+//! | `%H`      | Hour, 00 to 23                          | `14`         |
+//! | `%M`      | Minute                                  | `05`         |
+//! | `%S`      | Second                                  | `07`         |
+//! | `%F`      | `%Y-%m-%d`                              | `2024-03-09` |
+//! | `%T`      | `%H:%M:%S`                              | `14:05:07`   |
+//! | `%s`      | Seconds since the Unix epoch            | `1709993107` |
+//! | `%.3f`    | Milliseconds, with the leading dot      | `.042`       |
+//! | `%3f`     | Milliseconds                            | `042`        |
+//! | `%%`      | A literal `%`                           | `%`          |
+
+/// Used when no format is set or the set one doesn't parse
+pub const DEFAULT_FORMAT: &str = "%H:%M:%S%.3f";
+
+/// Year, month and day of `days` after 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// `millis` after the Unix epoch written out as `format`
+pub fn render(format: &str, millis: u128) -> Result<String, String> {
+    let secs = (millis / 1_000) as i64;
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    let of_day = secs.rem_euclid(86_400);
+    let (hour, minute, second) = (of_day / 3_600, of_day / 60 % 60, of_day % 60);
+    let millis = millis % 1_000;
+
+    let mut out = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => out.push_str(&format!("{:04}", year)),
+            Some('y') => out.push_str(&format!("{:02}", year.rem_euclid(100))),
+            Some('m') => out.push_str(&format!("{:02}", month)),
+            Some('d') => out.push_str(&format!("{:02}", day)),
+            Some('H') => out.push_str(&format!("{:02}", hour)),
+            Some('M') => out.push_str(&format!("{:02}", minute)),
+            Some('S') => out.push_str(&format!("{:02}", second)),
+            Some('F') => out.push_str(&format!("{:04}-{:02}-{:02}", year, month, day)),
+            Some('T') => out.push_str(&format!("{:02}:{:02}:{:02}", hour, minute, second)),
+            Some('s') => out.push_str(&secs.to_string()),
+            Some('%') => out.push('%'),
+            Some('.') if chars.next() == Some('3') && chars.next() == Some('f') => {
+                out.push_str(&format!(".{:03}", millis))
+            }
+            Some('3') if chars.next() == Some('f') => out.push_str(&format!("{:03}", millis)),
+            Some(other) => return Err(format!("'%{}' isn't a supported specifier", other)),
+            None => return Err(String::from("it ends in a lone '%'")),
+        }
+    }
+    Ok(out)
+}
+
+/// Check that `format` can be rendered
+pub fn validate(format: &str) -> Result<(), String> {
+    render(format, 0).map(|_| ())
+}
+
+/// Like [`render`], but falls back to [`DEFAULT_FORMAT`] when `format` is invalid
+pub fn format_or_default(format: &str, millis: u128) -> String {
+    render(format, millis)
+        .unwrap_or_else(|_| render(DEFAULT_FORMAT, millis).expect("the default format is valid"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-03-09 14:05:07.042 UTC
+    const MILLIS: u128 = 1_709_993_107_042;
+
+    #[test]
+    fn every_supported_specifier_renders() {
+        let cases = [
+            ("%Y %y %m %d", "2024 24 03 09"),
+            ("%H:%M:%S", "14:05:07"),
+            ("%F %T", "2024-03-09 14:05:07"),
+            ("%s", "1709993107"),
+            ("%S%.3f", "07.042"),
+            ("%S.%3f", "07.042"),
+            ("100%%", "100%"),
+            (DEFAULT_FORMAT, "14:05:07.042"),
+        ];
+        for (format, expected) in cases {
+            assert_eq!(render(format, MILLIS).as_deref(), Ok(expected), "{format}");
+        }
+    }
+
+    #[test]
+    fn unsupported_specifiers_are_errors() {
+        for (format, error) in [
+            ("%j", "'%j' isn't a supported specifier"),
+            ("%H:%M %Z", "'%Z' isn't a supported specifier"),
+            ("%.6f", "'%.' isn't a supported specifier"),
+            ("%H%", "it ends in a lone '%'"),
+        ] {
+            assert_eq!(render(format, MILLIS).unwrap_err(), error, "{format}");
+            assert_eq!(
+                format_or_default(format, MILLIS),
+                "14:05:07.042",
+                "{format}"
+            );
+        }
+    }
+}
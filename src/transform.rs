@@ -0,0 +1,86 @@
+use std::{borrow::Cow, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+
+use crate::checksum::Scheme;
+
+/// Preprocessing applied to a payload before it is split into channels
+pub trait PayloadTransform {
+    /// The payload to parse, or `None` to drop it
+    fn apply<'a>(&self, payload: &'a str) -> Option<Cow<'a, str>> {
+        Some(Cow::Borrowed(payload))
+    }
+}
+
+/// Pass payloads through untouched
+pub struct Identity;
+
+impl PayloadTransform for Identity {}
+
+/// Strip leading and trailing whitespace
+pub struct Trim;
+
+impl PayloadTransform for Trim {
+    fn apply<'a>(&self, payload: &'a str) -> Option<Cow<'a, str>> {
+        Some(Cow::Borrowed(payload.trim()))
+    }
+}
+
+/// Drop payloads containing `pattern`
+pub struct DropMatching<'p> {
+    pub pattern: &'p str,
+}
+
+impl PayloadTransform for DropMatching<'_> {
+    fn apply<'a>(&self, payload: &'a str) -> Option<Cow<'a, str>> {
+        if !self.pattern.is_empty() && payload.contains(self.pattern) {
+            None
+        } else {
+            Some(Cow::Borrowed(payload))
+        }
+    }
+}
+
+/// Check a `data*HH` suffix holding the XOR of the data bytes, keep only `data`
+pub struct VerifyChecksum;
+
+impl PayloadTransform for VerifyChecksum {
+    fn apply<'a>(&self, payload: &'a str) -> Option<Cow<'a, str>> {
+        let (data, checksum) = payload.rsplit_once('*')?;
+        let expected = u16::from_str_radix(checksum.trim(), 16).ok()?;
+        let actual = Scheme::Xor.compute(data.as_bytes())?;
+        (actual == expected).then_some(Cow::Borrowed(data))
+    }
+}
+
+/// The built-in transform picked in the Plot panel
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Transform {
+    #[default]
+    Identity,
+    Trim,
+    DropMatching(String),
+    VerifyChecksum,
+}
+
+impl Display for Transform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Transform::Identity => write!(f, "None"),
+            Transform::Trim => write!(f, "Trim"),
+            Transform::DropMatching(_) => write!(f, "Drop matching"),
+            Transform::VerifyChecksum => write!(f, "Verify checksum"),
+        }
+    }
+}
+
+impl PayloadTransform for Transform {
+    fn apply<'a>(&self, payload: &'a str) -> Option<Cow<'a, str>> {
+        match self {
+            Transform::Identity => Identity.apply(payload),
+            Transform::Trim => Trim.apply(payload),
+            Transform::DropMatching(pattern) => DropMatching { pattern }.apply(payload),
+            Transform::VerifyChecksum => VerifyChecksum.apply(payload),
+        }
+    }
+}